@@ -0,0 +1,25 @@
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum DriverError {
+    #[error("I/O error: {0}")]
+    IoError(#[from] std::io::Error),
+    #[error("Framebuffer error: {0}")]
+    FramebufferError(String),
+    #[error("Unsupported framebuffer pixel layout: {0}")]
+    UnsupportedPixelLayout(String),
+    #[error("Unknown pixel format {0}")]
+    UnknownPixelFormat(String),
+    #[error("Unsupported input device: {0}")]
+    UnsupportedInputDevice(String),
+    #[error("Invalid input script: {0}")]
+    InvalidScript(String),
+    #[error("Image error: {0}")]
+    ImageError(#[from] image::ImageError),
+}
+
+impl From<linuxfb::Error> for DriverError {
+    fn from(error: linuxfb::Error) -> Self {
+        DriverError::FramebufferError(format!("{:?}", error))
+    }
+}
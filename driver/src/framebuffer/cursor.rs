@@ -0,0 +1,130 @@
+use slint::LogicalPosition;
+
+use crate::framebuffer::rotation::Rotation;
+
+// '#' outline, '.' fill, ' ' transparent. The hotspot is the top left corner.
+const ARROW: [&str; 16] = [
+    "#          ",
+    "##         ",
+    "#.#        ",
+    "#..#       ",
+    "#...#      ",
+    "#....#     ",
+    "#.....#    ",
+    "#......#   ",
+    "#.......#  ",
+    "#........# ",
+    "#.....#####",
+    "#..#..#    ",
+    "#.# #..#   ",
+    "##  #..#   ",
+    "#    #..#  ",
+    "     ####  ",
+];
+
+/// Mouse cursor drawn straight into the framebuffer after Slint rendered.
+/// What was underneath is put back before the next render into the same
+/// buffer, so partial rendering keeps working.
+pub(crate) struct Cursor {
+    position: Option<LogicalPosition>,
+    /// Positions of the last two `draw`s, newest first.
+    drawn: [Option<LogicalPosition>; 2],
+    /// Byte offset and original bytes of every drawn pixel, per buffer.
+    saved: Vec<Vec<(usize, [u8; 4])>>,
+    buffer_index: usize,
+    bytes_per_pixel: usize,
+    stride: usize,
+    rotation: Rotation,
+    width: f32,
+    height: f32,
+}
+
+impl Cursor {
+    /// `width`, `height` and `stride` are of the native, unrotated framebuffer.
+    pub fn new(buffer_count: usize, bytes_per_pixel: usize, stride: usize, rotation: Rotation, width: usize, height: usize) -> Self {
+        Self {
+            position: None,
+            drawn: [None; 2],
+            saved: vec![vec![]; buffer_count],
+            buffer_index: 0,
+            bytes_per_pixel,
+            stride,
+            rotation,
+            width: width as f32,
+            height: height as f32,
+        }
+    }
+
+    /// Returns true if the cursor has to be redrawn. `None` hides it.
+    pub fn set_position(&mut self, position: Option<LogicalPosition>) -> bool {
+        let changed = self.position != position;
+        self.position = position;
+        changed
+    }
+
+    pub fn restore(&mut self, frame: &mut [u8]) {
+        for (offset, bytes) in self.saved[self.buffer_index].drain(..) {
+            frame[offset..offset + self.bytes_per_pixel].copy_from_slice(&bytes[..self.bytes_per_pixel]);
+        }
+    }
+
+    /// Window areas that look different after the last `draw` than after the
+    /// one before: where the arrow was and where it is now, as x, y, width, height.
+    pub fn changed_areas(&self) -> Vec<(u32, u32, u32, u32)> {
+        if self.drawn[0] == self.drawn[1] {
+            return vec![];
+        }
+
+        let (logical_width, logical_height) = self.rotation.logical_size(self.width as u32, self.height as u32);
+        let arrow_width = ARROW[0].len() as u32;
+        let arrow_height = ARROW.len() as u32;
+
+        self.drawn
+            .iter()
+            .flatten()
+            .map(|position| (position.x.max(0.0).floor() as u32, position.y.max(0.0).floor() as u32))
+            .filter(|(x, y)| *x < logical_width && *y < logical_height)
+            .map(|(x, y)| (x, y, arrow_width.min(logical_width - x), arrow_height.min(logical_height - y)))
+            .collect()
+    }
+
+    /// Draws the cursor and moves on to the next buffer.
+    pub fn draw(&mut self, frame: &mut [u8]) {
+        let saved = &mut self.saved[self.buffer_index];
+        self.buffer_index = (self.buffer_index + 1) % self.saved.len();
+        self.drawn = [self.position, self.drawn[0]];
+
+        let Some(position) = self.position else {
+            return;
+        };
+
+        // Opaque alpha where there is one, the 8888 formats keep it in the last byte
+        let black: [u8; 4] = if self.bytes_per_pixel == 4 { [0, 0, 0, 0xFF] } else { [0; 4] };
+        let white: [u8; 4] = [0xFF; 4];
+        let (logical_width, logical_height) = self.rotation.logical_size(self.width as u32, self.height as u32);
+
+        for (row, line) in ARROW.iter().enumerate() {
+            for (column, pixel) in line.chars().enumerate() {
+                let color = match pixel {
+                    '#' => black,
+                    '.' => white,
+                    _ => continue,
+                };
+
+                let (x, y) = (position.x.floor() + column as f32, position.y.floor() + row as f32);
+                if x >= logical_width as f32 || y >= logical_height as f32 {
+                    continue;
+                }
+
+                let (x, y) = self.rotation.to_native(x, y, self.width, self.height);
+                let offset = (y as usize * self.stride + x as usize) * self.bytes_per_pixel;
+
+                let mut original = [0; 4];
+                original[..self.bytes_per_pixel].copy_from_slice(&frame[offset..offset + self.bytes_per_pixel]);
+                saved.push((offset, original));
+
+                frame[offset..offset + self.bytes_per_pixel].copy_from_slice(&color[..self.bytes_per_pixel]);
+            }
+        }
+    }
+}
@@ -0,0 +1,147 @@
+use std::{
+    cell::{Cell, RefCell},
+    io,
+    os::fd::{AsRawFd, RawFd},
+};
+
+use evdev::{EventSummary, KeyCode, RelativeAxisCode};
+use slint::{
+    platform::{Key, WindowEvent},
+    SharedString,
+};
+
+use crate::framebuffer::input_device::InputPlatform;
+
+// US layout, unshifted and shifted
+const CHARACTER_KEYS: &[(KeyCode, char, char)] = &[
+    (KeyCode::KEY_A, 'a', 'A'), (KeyCode::KEY_B, 'b', 'B'), (KeyCode::KEY_C, 'c', 'C'), (KeyCode::KEY_D, 'd', 'D'),
+    (KeyCode::KEY_E, 'e', 'E'), (KeyCode::KEY_F, 'f', 'F'), (KeyCode::KEY_G, 'g', 'G'), (KeyCode::KEY_H, 'h', 'H'),
+    (KeyCode::KEY_I, 'i', 'I'), (KeyCode::KEY_J, 'j', 'J'), (KeyCode::KEY_K, 'k', 'K'), (KeyCode::KEY_L, 'l', 'L'),
+    (KeyCode::KEY_M, 'm', 'M'), (KeyCode::KEY_N, 'n', 'N'), (KeyCode::KEY_O, 'o', 'O'), (KeyCode::KEY_P, 'p', 'P'),
+    (KeyCode::KEY_Q, 'q', 'Q'), (KeyCode::KEY_R, 'r', 'R'), (KeyCode::KEY_S, 's', 'S'), (KeyCode::KEY_T, 't', 'T'),
+    (KeyCode::KEY_U, 'u', 'U'), (KeyCode::KEY_V, 'v', 'V'), (KeyCode::KEY_W, 'w', 'W'), (KeyCode::KEY_X, 'x', 'X'),
+    (KeyCode::KEY_Y, 'y', 'Y'), (KeyCode::KEY_Z, 'z', 'Z'),
+    (KeyCode::KEY_1, '1', '!'), (KeyCode::KEY_2, '2', '@'), (KeyCode::KEY_3, '3', '#'), (KeyCode::KEY_4, '4', '$'),
+    (KeyCode::KEY_5, '5', '%'), (KeyCode::KEY_6, '6', '^'), (KeyCode::KEY_7, '7', '&'), (KeyCode::KEY_8, '8', '*'),
+    (KeyCode::KEY_9, '9', '('), (KeyCode::KEY_0, '0', ')'),
+    (KeyCode::KEY_MINUS, '-', '_'), (KeyCode::KEY_EQUAL, '=', '+'), (KeyCode::KEY_LEFTBRACE, '[', '{'),
+    (KeyCode::KEY_RIGHTBRACE, ']', '}'), (KeyCode::KEY_BACKSLASH, '\\', '|'), (KeyCode::KEY_SEMICOLON, ';', ':'),
+    (KeyCode::KEY_APOSTROPHE, '\'', '"'), (KeyCode::KEY_GRAVE, '`', '~'), (KeyCode::KEY_COMMA, ',', '<'),
+    (KeyCode::KEY_DOT, '.', '>'), (KeyCode::KEY_SLASH, '/', '?'), (KeyCode::KEY_SPACE, ' ', ' '),
+    (KeyCode::KEY_KP0, '0', '0'), (KeyCode::KEY_KP1, '1', '1'), (KeyCode::KEY_KP2, '2', '2'), (KeyCode::KEY_KP3, '3', '3'),
+    (KeyCode::KEY_KP4, '4', '4'), (KeyCode::KEY_KP5, '5', '5'), (KeyCode::KEY_KP6, '6', '6'), (KeyCode::KEY_KP7, '7', '7'),
+    (KeyCode::KEY_KP8, '8', '8'), (KeyCode::KEY_KP9, '9', '9'), (KeyCode::KEY_KPDOT, '.', '.'),
+    (KeyCode::KEY_KPMINUS, '-', '-'), (KeyCode::KEY_KPPLUS, '+', '+'), (KeyCode::KEY_KPASTERISK, '*', '*'),
+    (KeyCode::KEY_KPSLASH, '/', '/'),
+];
+
+fn special_key(code: KeyCode) -> Option<Key> {
+    let key = match code {
+        KeyCode::KEY_BACKSPACE => Key::Backspace,
+        KeyCode::KEY_TAB => Key::Tab,
+        KeyCode::KEY_ENTER | KeyCode::KEY_KPENTER => Key::Return,
+        KeyCode::KEY_ESC => Key::Escape,
+        KeyCode::KEY_DELETE => Key::Delete,
+        KeyCode::KEY_LEFTSHIFT => Key::Shift,
+        KeyCode::KEY_RIGHTSHIFT => Key::ShiftR,
+        KeyCode::KEY_LEFTCTRL => Key::Control,
+        KeyCode::KEY_RIGHTCTRL => Key::ControlR,
+        KeyCode::KEY_LEFTALT => Key::Alt,
+        KeyCode::KEY_RIGHTALT => Key::AltGr,
+        KeyCode::KEY_LEFTMETA => Key::Meta,
+        KeyCode::KEY_RIGHTMETA => Key::MetaR,
+        KeyCode::KEY_CAPSLOCK => Key::CapsLock,
+        KeyCode::KEY_UP => Key::UpArrow,
+        KeyCode::KEY_DOWN => Key::DownArrow,
+        KeyCode::KEY_LEFT => Key::LeftArrow,
+        KeyCode::KEY_RIGHT => Key::RightArrow,
+        KeyCode::KEY_HOME => Key::Home,
+        KeyCode::KEY_END => Key::End,
+        KeyCode::KEY_PAGEUP => Key::PageUp,
+        KeyCode::KEY_PAGEDOWN => Key::PageDown,
+        KeyCode::KEY_INSERT => Key::Insert,
+        // Typical gpio-keys and remote control codes
+        KeyCode::KEY_OK | KeyCode::KEY_SELECT => Key::Return,
+        KeyCode::KEY_BACK => Key::Escape,
+        KeyCode::KEY_NEXT => Key::Tab,
+        KeyCode::KEY_PREVIOUS => Key::Backtab,
+        _ => return None,
+    };
+
+    Some(key)
+}
+
+/// Keyboards, `gpio-keys` buttons and rotary encoders. Encoder steps move the
+/// focus like Tab and Shift+Tab, so the UI can be operated without touch.
+pub struct EvdevKeysPlatform {
+    device: RefCell<evdev::Device>,
+    shift: Cell<bool>,
+}
+
+impl EvdevKeysPlatform {
+    pub fn new(device: evdev::Device) -> Self {
+        Self {
+            device: RefCell::new(device),
+            shift: Cell::new(false),
+        }
+    }
+
+    fn key_text(&self, code: KeyCode) -> Option<SharedString> {
+        if let Some(key) = special_key(code) {
+            return Some(key.into());
+        }
+
+        CHARACTER_KEYS
+            .iter()
+            .find(|(key_code, _, _)| *key_code == code)
+            .map(|(_, plain, shifted)| SharedString::from(if self.shift.get() { *shifted } else { *plain }.to_string()))
+    }
+}
+
+impl InputPlatform for EvdevKeysPlatform {
+    fn process_events(&self) -> io::Result<Vec<WindowEvent>> {
+        let mut device = self.device.borrow_mut();
+        let mut result = vec![];
+
+        let events = match device.fetch_events() {
+            Ok(events) => events,
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => return Ok(result),
+            Err(e) => return Err(e),
+        };
+
+        for event in events {
+            match event.destructure() {
+                EventSummary::Key(_, code, value) => {
+                    if code == KeyCode::KEY_LEFTSHIFT || code == KeyCode::KEY_RIGHTSHIFT {
+                        self.shift.set(value != 0);
+                    }
+
+                    let Some(text) = self.key_text(code) else {
+                        continue;
+                    };
+
+                    // 2 is auto repeat
+                    result.push(match value {
+                        0 => WindowEvent::KeyReleased { text },
+                        _ => WindowEvent::KeyPressed { text },
+                    });
+                }
+                EventSummary::RelativeAxis(_, RelativeAxisCode::REL_X | RelativeAxisCode::REL_DIAL | RelativeAxisCode::REL_WHEEL, value) => {
+                    let key: SharedString = if value > 0 { Key::Tab } else { Key::Backtab }.into();
+
+                    for _ in 0..value.unsigned_abs() {
+                        result.push(WindowEvent::KeyPressed { text: key.clone() });
+                        result.push(WindowEvent::KeyReleased { text: key.clone() });
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Ok(result)
+    }
+
+    fn raw_fd(&self) -> RawFd {
+        self.device.borrow().as_raw_fd()
+    }
+}
@@ -0,0 +1,123 @@
+use std::{
+    cell::{Cell, RefCell},
+    io,
+    os::fd::{AsRawFd, RawFd},
+};
+
+use evdev::{EventSummary, KeyCode, RelativeAxisCode, SynchronizationCode};
+use slint::{
+    platform::{PointerEventButton, WindowEvent},
+    LogicalPosition,
+};
+
+use crate::framebuffer::input_device::InputPlatform;
+
+// Pixels scrolled per wheel notch
+const WHEEL_STEP: f32 = 60.0;
+
+#[derive(Default)]
+struct MouseFrame {
+    dx: i32,
+    dy: i32,
+    wheel_x: i32,
+    wheel_y: i32,
+    buttons: Vec<(PointerEventButton, bool)>,
+}
+
+/// Relative pointer devices. Moves are in window coordinates, so they follow
+/// the rotation of the panel.
+pub struct EvdevMousePlatform {
+    device: RefCell<evdev::Device>,
+    frame: RefCell<MouseFrame>,
+    position: Cell<LogicalPosition>,
+    width: f32,
+    height: f32,
+}
+
+impl EvdevMousePlatform {
+    /// `width` and `height` are the window size.
+    pub fn new(device: evdev::Device, width: u32, height: u32) -> Self {
+        Self {
+            device: RefCell::new(device),
+            frame: RefCell::new(MouseFrame::default()),
+            position: Cell::new(LogicalPosition::new(width as f32 / 2.0, height as f32 / 2.0)),
+            width: width as f32,
+            height: height as f32,
+        }
+    }
+
+    fn end_frame(&self, frame: &mut MouseFrame, result: &mut Vec<WindowEvent>) {
+        let mut position = self.position.get();
+
+        if frame.dx != 0 || frame.dy != 0 {
+            position.x = (position.x + frame.dx as f32).clamp(0.0, self.width - 1.0);
+            position.y = (position.y + frame.dy as f32).clamp(0.0, self.height - 1.0);
+            self.position.set(position);
+
+            result.push(WindowEvent::PointerMoved { position });
+        }
+
+        if frame.wheel_x != 0 || frame.wheel_y != 0 {
+            result.push(WindowEvent::PointerScrolled {
+                position,
+                delta_x: frame.wheel_x as f32 * WHEEL_STEP,
+                delta_y: frame.wheel_y as f32 * WHEEL_STEP,
+            });
+        }
+
+        for (button, pressed) in std::mem::take(&mut frame.buttons) {
+            result.push(match pressed {
+                true => WindowEvent::PointerPressed { position, button },
+                false => WindowEvent::PointerReleased { position, button },
+            });
+        }
+
+        *frame = MouseFrame::default();
+    }
+}
+
+impl InputPlatform for EvdevMousePlatform {
+    fn process_events(&self) -> io::Result<Vec<WindowEvent>> {
+        let mut device = self.device.borrow_mut();
+        let mut frame = self.frame.borrow_mut();
+        let mut result = vec![];
+
+        let events = match device.fetch_events() {
+            Ok(events) => events,
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => return Ok(result),
+            Err(e) => return Err(e),
+        };
+
+        for event in events {
+            match event.destructure() {
+                EventSummary::RelativeAxis(_, RelativeAxisCode::REL_X, value) => frame.dx += value,
+                EventSummary::RelativeAxis(_, RelativeAxisCode::REL_Y, value) => frame.dy += value,
+                EventSummary::RelativeAxis(_, RelativeAxisCode::REL_WHEEL, value) => frame.wheel_y += value,
+                EventSummary::RelativeAxis(_, RelativeAxisCode::REL_HWHEEL, value) => frame.wheel_x += value,
+                EventSummary::Key(_, code, value) if value != 2 => {
+                    let button = match code {
+                        KeyCode::BTN_LEFT => PointerEventButton::Left,
+                        KeyCode::BTN_RIGHT => PointerEventButton::Right,
+                        KeyCode::BTN_MIDDLE => PointerEventButton::Middle,
+                        _ => continue,
+                    };
+                    frame.buttons.push((button, value == 1));
+                }
+                EventSummary::Synchronization(_, SynchronizationCode::SYN_REPORT, _) => {
+                    self.end_frame(&mut frame, &mut result);
+                }
+                _ => {}
+            }
+        }
+
+        Ok(result)
+    }
+
+    fn raw_fd(&self) -> RawFd {
+        self.device.borrow().as_raw_fd()
+    }
+
+    fn cursor_position(&self) -> Option<LogicalPosition> {
+        Some(self.position.get())
+    }
+}
@@ -0,0 +1,54 @@
+use std::{
+    cell::RefCell,
+    io,
+    os::fd::{AsRawFd, RawFd},
+};
+
+use slint::platform::WindowEvent;
+
+use crate::framebuffer::{input_device::InputPlatform, singletouch::SingleTouchState, touch_mapping::TouchMapping};
+
+/// Single-touch panels reporting `ABS_X`/`ABS_Y`, e.g. resistive screens.
+pub struct EvdevStTouchPlatform {
+    touch_device: RefCell<evdev::Device>,
+    state: RefCell<SingleTouchState>,
+    mapping: TouchMapping,
+}
+
+impl EvdevStTouchPlatform {
+    pub fn new(touch_device: evdev::Device, mapping: TouchMapping, pressure_threshold: Option<i32>, jitter_distance: f32) -> Self {
+        Self {
+            touch_device: RefCell::new(touch_device),
+            state: RefCell::new(SingleTouchState::new(pressure_threshold, jitter_distance)),
+            mapping,
+        }
+    }
+}
+
+impl InputPlatform for EvdevStTouchPlatform {
+    fn process_events(&self) -> io::Result<Vec<WindowEvent>> {
+        let mut touch_device = self.touch_device.borrow_mut();
+        let mut state = self.state.borrow_mut();
+        let mut result = vec![];
+
+        let events = match touch_device.fetch_events() {
+            Ok(events) => events,
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => return Ok(result),
+            Err(e) => return Err(e),
+        };
+
+        for event in events {
+            result.extend(state.process(event.destructure(), |x, y| self.mapping.map(x, y)));
+        }
+
+        Ok(result)
+    }
+
+    fn raw_fd(&self) -> RawFd {
+        self.touch_device.borrow().as_raw_fd()
+    }
+
+    fn is_touch(&self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,68 @@
+use std::{
+    io,
+    os::fd::{AsRawFd, FromRawFd, OwnedFd, RawFd},
+    time::Duration,
+};
+
+/// An eventfd that interrupts `wait_for_events` from other threads.
+pub struct Wakeup(OwnedFd);
+
+impl Wakeup {
+    pub fn new() -> io::Result<Wakeup> {
+        let fd = unsafe { libc::eventfd(0, libc::EFD_NONBLOCK | libc::EFD_CLOEXEC) };
+
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(Wakeup(unsafe { OwnedFd::from_raw_fd(fd) }))
+    }
+
+    pub fn wake(&self) {
+        let value: u64 = 1;
+
+        // Only fails if the counter would overflow, in which case it is already readable
+        unsafe {
+            libc::write(self.0.as_raw_fd(), &value as *const u64 as *const libc::c_void, size_of::<u64>());
+        }
+    }
+
+    fn reset(&self) {
+        let mut value: u64 = 0;
+
+        unsafe {
+            libc::read(self.0.as_raw_fd(), &mut value as *mut u64 as *mut libc::c_void, size_of::<u64>());
+        }
+    }
+}
+
+/// Blocks until one of `fds` is readable, `wakeup` was woken or `timeout` has
+/// passed. Waits indefinitely without a timeout.
+pub fn wait_for_events(wakeup: &Wakeup, fds: &[RawFd], timeout: Option<Duration>) -> io::Result<()> {
+    let mut poll_fds: Vec<libc::pollfd> = std::iter::once(wakeup.0.as_raw_fd())
+        .chain(fds.iter().copied())
+        .map(|fd| libc::pollfd { fd, events: libc::POLLIN, revents: 0 })
+        .collect();
+
+    // Rounded up, so sub-millisecond deadlines don't turn into a busy loop
+    let timeout_ms = match timeout {
+        Some(timeout) => timeout.as_micros().div_ceil(1000).min(i32::MAX as u128) as i32,
+        None => -1,
+    };
+
+    let result = unsafe { libc::poll(poll_fds.as_mut_ptr(), poll_fds.len() as libc::nfds_t, timeout_ms) };
+
+    if result < 0 {
+        let error = io::Error::last_os_error();
+
+        if error.kind() != io::ErrorKind::Interrupted {
+            return Err(error);
+        }
+    }
+
+    if poll_fds[0].revents & libc::POLLIN != 0 {
+        wakeup.reset();
+    }
+
+    Ok(())
+}
@@ -1,25 +1,36 @@
 // Based on https://github.com/nilclass/slint-framebuffer-example
 
-use evdev::Device;
 use linuxfb::{double::Buffer, Framebuffer};
 use memmap::MmapMut;
 use slint::{
     platform::{
-        software_renderer::{
-            MinimalSoftwareWindow, PremultipliedRgbaColor, RepaintBufferType, Rgb565Pixel,
-            TargetPixel,
-        },
-        EventLoopProxy, Platform, WindowEvent,
+        software_renderer::{MinimalSoftwareWindow, RepaintBufferType},
+        EventLoopProxy, Platform,
     },
     EventLoopError, PhysicalSize, PlatformError, Rgb8Pixel,
 };
 use std::{
     cell::RefCell,
+    os::fd::RawFd,
     rc::Rc,
+    sync::Arc,
     time::{Duration, Instant},
 };
 
-use crate::framebuffer::evdev_mt_touch_platform::EvdevMtTouchPlatform;
+use crate::{
+    error::DriverError,
+    framebuffer::{
+        cursor::Cursor,
+        event_wait::{wait_for_events, Wakeup},
+        gestures::GestureRecognizer,
+        init_framebuffer::FramebufferOptions,
+        input_manager::InputManager,
+        pixel_format::{render_frame, PixelFormat},
+        rotation::Rotation,
+        screensaver::ScreensaverHandle,
+    },
+    vnc::{Rect, VncServer},
+};
 
 trait FramebufferHandler
 {
@@ -80,101 +91,130 @@ impl FramebufferHandler for DoubleBufferFramebuffer
     }
 }
 
-pub trait TouchPlatform {
-    fn process_touch_events(&self) -> Vec<WindowEvent>;
-}
-
 pub struct FramebufferPlatform {
     window: Rc<MinimalSoftwareWindow>,
     fb: RefCell<Box<dyn FramebufferHandler>>,
     width: usize,
     height: usize,
     stride: usize,
-    bytes_per_pixel: usize,
-    touch_device: Option<Box<dyn TouchPlatform>>,
+    pixel_format: PixelFormat,
+    rotation: Rotation,
+    inputs: RefCell<InputManager>,
+    /// Only drawn while a mouse is connected.
+    cursor: RefCell<Cursor>,
+    gestures: RefCell<GestureRecognizer>,
+    screensaver: Option<ScreensaverHandle>,
+    /// Mirrors the screen, read back from the framebuffer after rendering.
+    vnc: Option<(Arc<VncServer>, RefCell<Vec<Rgb8Pixel>>)>,
     queue: Option<Queue>,
 }
 
 impl FramebufferPlatform {
-    pub fn new(fb: Framebuffer, touch_device: Option<Device>, double_buffering: bool) -> Self {
+    pub fn new(fb: Framebuffer, options: FramebufferOptions) -> Result<Self, DriverError> {
+        let FramebufferOptions { inputs, double_buffering, pixel_format, rotation, touch, gestures, on_swipe, screensaver, vnc, .. } = options;
         let size = fb.get_size();
         let bytes_per_pixel = fb.get_bytes_per_pixel();
         let physical_size = fb.get_physical_size();
 
+        let pixel_format = match pixel_format {
+            Some(pixel_format) if pixel_format.bytes_per_pixel() != bytes_per_pixel => {
+                return Err(DriverError::UnsupportedPixelLayout(format!(
+                    "{:?} needs {} bytes per pixel, but the framebuffer has {}",
+                    pixel_format, pixel_format.bytes_per_pixel(), bytes_per_pixel
+                )));
+            }
+            Some(pixel_format) => pixel_format,
+            None => PixelFormat::from_layout(bytes_per_pixel, &fb.get_pixel_layout())?,
+        };
+
         println!("Framebuffer id: {:?}", fb.get_id());
         println!("Size in pixels: {:?}", size);
         println!("Bytes per pixel: {:?}", bytes_per_pixel);
+        println!("Pixel format: {:?}", pixel_format);
         println!("Physical size in mm: {:?}", physical_size);
+        println!("Rotation: {:?}", rotation);
 
-        let mut mutex_touch_device: Option<Box<dyn TouchPlatform>> = None;
-
-        if let Some(touch_device) = touch_device {
-            println!("Input device name: {:?}", touch_device.name());
-            touch_device.set_nonblocking(true).unwrap();
-
-            // TODO: Allow this to be configured
-            mutex_touch_device = Some(Box::new(EvdevMtTouchPlatform::new(touch_device)));
-        } else {
+        if inputs.is_empty() {
             println!("No input device configured");
         }
 
+        let inputs = InputManager::new(&inputs, touch, rotation, size.0, size.1);
+        let cursor = Cursor::new(if double_buffering { 2 } else { 1 }, bytes_per_pixel as usize, size.0 as usize, rotation, size.0 as usize, size.1 as usize);
+
         let window = MinimalSoftwareWindow::new(if double_buffering { RepaintBufferType::SwappedBuffers } else { RepaintBufferType::ReusedBuffer });
-        window.set_size(PhysicalSize::new(size.0, size.1));
+        let (logical_width, logical_height) = rotation.logical_size(size.0, size.1);
+        window.set_size(PhysicalSize::new(logical_width, logical_height));
+
+        let vnc = match vnc {
+            Some(vnc) => Some((
+                VncServer::start(&vnc, logical_width, logical_height)?,
+                RefCell::new(vec![Rgb8Pixel::default(); (logical_width * logical_height) as usize]),
+            )),
+            None => None,
+        };
+
+        let wakeup = Arc::new(Wakeup::new()?);
+        if let Some(screensaver) = &screensaver {
+            screensaver.lock().unwrap().set_wakeup(wakeup.clone());
+        }
 
         let framebuffer_handler: Box<dyn FramebufferHandler> = match double_buffering
         {
-            true => Box::new(DoubleBufferFramebuffer::new(fb).expect("Failed to initialise double buffer")),
-            false => Box::new(SingleBufferFramebuffer::new(fb).expect("Failed to initialise single buffer"))
+            true => Box::new(DoubleBufferFramebuffer::new(fb)?),
+            false => Box::new(SingleBufferFramebuffer::new(fb)?)
         };
 
 
-        Self {
+        Ok(Self {
             window,
             fb: RefCell::new(framebuffer_handler),
             width: size.0 as usize,
             height: size.1 as usize,
             stride: size.0 as usize,
-            bytes_per_pixel: bytes_per_pixel as usize,
-            touch_device: mutex_touch_device,
-            queue: Some(Queue(Default::default(), std::thread::current())),
-        }
+            pixel_format,
+            rotation,
+            inputs: RefCell::new(inputs),
+            cursor: RefCell::new(cursor),
+            gestures: RefCell::new(GestureRecognizer::new(gestures, logical_width as f32, logical_height as f32, on_swipe)),
+            screensaver,
+            vnc,
+            queue: Some(Queue(Default::default(), wakeup)),
+        })
     }
 }
 
-#[derive(Clone, Copy)]
-pub struct PremultipliedAbgrColor {
-    pub blue: u8,
-    pub green: u8,
-    pub red: u8,
-    pub alpha: u8,
-}
-
-impl TargetPixel for PremultipliedAbgrColor {
-    fn blend(&mut self, color: PremultipliedRgbaColor) {
-        let a = (u8::MAX - color.alpha) as u16;
-        self.red = (self.red as u16 * a / 255) as u8 + color.red;
-        self.green = (self.green as u16 * a / 255) as u8 + color.green;
-        self.blue = (self.blue as u16 * a / 255) as u8 + color.blue;
-        self.alpha = (self.alpha as u16 + color.alpha as u16
-            - (self.alpha as u16 * color.alpha as u16) / 255) as u8;
-    }
-
-    fn from_rgb(r: u8, g: u8, b: u8) -> Self {
-        Self {
-            red: r,
-            green: g,
-            blue: b,
-            alpha: 255,
+impl FramebufferPlatform {
+    /// Copies `frame` into `pixels` in window coordinates.
+    /// Converts `area` of the frame, in window coordinates, back into `pixels`.
+    fn read_back(&self, frame: &[u8], pixels: &mut [Rgb8Pixel], area: Rect) {
+        let bytes_per_pixel = self.pixel_format.bytes_per_pixel() as usize;
+        let (logical_width, logical_height) = self.rotation.logical_size(self.width as u32, self.height as u32);
+        let right = (area.x as usize + area.width as usize).min(logical_width as usize);
+        let bottom = (area.y as usize + area.height as usize).min(logical_height as usize);
+
+        for y in area.y as usize..bottom {
+            for x in area.x as usize..right {
+                let (native_x, native_y) = self.rotation.to_native(x as f32, y as f32, self.width as f32, self.height as f32);
+                let offset = (native_y as usize * self.stride + native_x as usize) * bytes_per_pixel;
+                pixels[y * logical_width as usize + x] = self.pixel_format.read_pixel(&frame[offset..]);
+            }
         }
     }
 
-    fn background() -> Self {
-        Self {
-            red: 0,
-            green: 0,
-            blue: 0,
-            alpha: 0,
-        }
+    /// Time until the next Slint timer, gesture or screensaver step, `None` if nothing is pending.
+    fn next_timeout(&self) -> Option<Duration> {
+        let now = Instant::now();
+        let deadlines = [
+            self.gestures.borrow().next_deadline(),
+            self.screensaver.as_ref().and_then(|screensaver| screensaver.lock().unwrap().next_deadline()),
+        ];
+
+        deadlines
+            .into_iter()
+            .flatten()
+            .map(|deadline| deadline.saturating_duration_since(now))
+            .chain(slint::platform::duration_until_next_timer_update())
+            .min()
     }
 }
 
@@ -210,35 +250,84 @@ impl Platform for FramebufferPlatform {
                 None => {}
             }
 
-            if let Some(touch_device) = &self.touch_device {
-                //let now = Instant::now();
-                let events = touch_device.process_touch_events();
-                //let has_event = !events.is_empty();
+            let now = Instant::now();
+            let mut events = vec![];
+            let mut cursor_position = None;
+
+            self.inputs.borrow_mut().process_events(|input_device, mut device_events| {
+                if let Some(screensaver) = &self.screensaver {
+                    screensaver.lock().unwrap().on_input(&mut device_events);
+                }
+
+                if input_device.is_touch() {
+                    device_events = self.gestures.borrow_mut().process(device_events, now);
+                }
+
+                cursor_position = cursor_position.or(input_device.cursor_position());
+                events.extend(device_events);
+            });
+
+            if self.cursor.borrow_mut().set_position(cursor_position) {
+                self.window.request_redraw();
+            }
+
+            if let Some((vnc, _)) = &self.vnc {
+                let mut vnc_events = vnc.take_events();
+
+                if let Some(screensaver) = &self.screensaver {
+                    screensaver.lock().unwrap().on_input(&mut vnc_events);
+                }
+
+                events.extend(vnc_events);
 
-                for event in events {
-                    //println!("Got event {:?}", event);
-                    self.window.try_dispatch_event(event).unwrap();
+                // A new viewer needs a complete frame in the buffer that is read back
+                if vnc.is_stale() && vnc.has_clients() {
+                    self.window.request_redraw();
                 }
+            }
+
+            events.extend(self.gestures.borrow_mut().update(now));
+
+            for event in events {
+                self.window.try_dispatch_event(event).unwrap();
+            }
 
-                //if has_event {
-                //    println!("Elapsed: {:.2?}", now.elapsed());
-                //}
+            if let Some(screensaver) = &self.screensaver {
+                screensaver.lock().unwrap().update();
             }
 
             self.window.draw_if_needed(|renderer| {
-                let frame = fb.as_mut_slice();
-                if self.bytes_per_pixel == 2 {
-                    let (_, pixels, _) = unsafe { frame.align_to_mut::<Rgb565Pixel>() };
-                    renderer.render(pixels, self.stride);
-                } else if self.bytes_per_pixel == 4 {
-                    // TODO: This may be different on other machines. Should be configurable!
-                    let (_, pixels, _) = unsafe { frame.align_to_mut::<PremultipliedAbgrColor>() };
-                    renderer.render(pixels, self.stride);
-                } else if self.bytes_per_pixel == 3 {
-                    let (_, pixels, _) = unsafe { frame.align_to_mut::<Rgb8Pixel>() };
-                    renderer.render(pixels, self.stride);
-                } else {
-                    panic!("Unsupported framebuffer pixel format");
+                renderer.set_rendering_rotation(self.rotation.rendering_rotation());
+
+                let mut cursor = self.cursor.borrow_mut();
+                cursor.restore(fb.as_mut_slice());
+                let region = render_frame(renderer, fb.as_mut_slice(), self.stride, self.pixel_format);
+                cursor.draw(fb.as_mut_slice());
+
+                if let Some((vnc, pixels)) = &self.vnc
+                    && vnc.has_clients()
+                {
+                    let rendered = region.iter().filter(|(_, size)| size.width > 0 && size.height > 0).map(|(origin, size)| {
+                        self.rotation.rect_to_logical(origin.x as u32, origin.y as u32, size.width, size.height, self.width as u32, self.height as u32)
+                    });
+                    let dirty = rendered
+                        .chain(cursor.changed_areas())
+                        .map(|(x, y, width, height)| Rect { x: x as u16, y: y as u16, width: width as u16, height: height as u16 })
+                        .collect::<Vec<_>>();
+
+                    // Only the dirty parts changed, unless a new viewer needs the complete frame
+                    let complete = vnc.is_stale();
+                    let (logical_width, logical_height) = self.rotation.logical_size(self.width as u32, self.height as u32);
+                    let read_back_areas = match complete {
+                        true => vec![Rect { x: 0, y: 0, width: logical_width as u16, height: logical_height as u16 }],
+                        false => dirty.clone(),
+                    };
+
+                    let mut pixels = pixels.borrow_mut();
+                    for area in read_back_areas {
+                        self.read_back(fb.as_mut_slice(), &mut pixels, area);
+                    }
+                    vnc.update_partial(&pixels, dirty, complete);
                 }
 
                 fb.flip().unwrap();
@@ -247,10 +336,9 @@ impl Platform for FramebufferPlatform {
             let queue_length = queue.0.lock().unwrap().len();
 
             if !self.window.has_active_animations() && queue_length <= 0 {
-                std::thread::park_timeout(
-                    slint::platform::duration_until_next_timer_update()
-                        .unwrap_or(Duration::from_millis(20)),
-                );
+                let input_fds: Vec<RawFd> = self.inputs.borrow().raw_fds();
+                wait_for_events(&queue.1, &input_fds, self.next_timeout())
+                    .map_err(|e| PlatformError::Other(format!("Failed to wait for input: {}", e)))?;
             }
         }
         Ok(())
@@ -265,13 +353,13 @@ enum Event {
 #[derive(Clone)]
 struct Queue(
     std::sync::Arc<std::sync::Mutex<std::collections::VecDeque<Event>>>,
-    std::thread::Thread,
+    std::sync::Arc<Wakeup>,
 );
 
 impl EventLoopProxy for Queue {
     fn quit_event_loop(&self) -> Result<(), EventLoopError> {
         self.0.lock().unwrap().push_back(Event::Quit);
-        self.1.unpark();
+        self.1.wake();
         Ok(())
     }
 
@@ -280,7 +368,7 @@ impl EventLoopProxy for Queue {
         event: Box<dyn FnOnce() + Send>,
     ) -> Result<(), EventLoopError> {
         self.0.lock().unwrap().push_back(Event::Event(event));
-        self.1.unpark();
+        self.1.wake();
         Ok(())
     }
 }
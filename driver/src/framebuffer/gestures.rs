@@ -0,0 +1,462 @@
+use std::time::{Duration, Instant};
+
+use slint::{
+    platform::{PointerEventButton, WindowEvent},
+    LogicalPosition,
+};
+
+// Pixels per second below which kinetic scrolling stops.
+const MIN_KINETIC_VELOCITY: f32 = 50.0;
+// Lifting off after resting this long doesn't fling.
+const FLING_TIMEOUT: Duration = Duration::from_millis(100);
+const KINETIC_FRAME: Duration = Duration::from_millis(16);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SwipeEdge {
+    Left,
+    Right,
+    Top,
+    Bottom,
+}
+
+/// Called on the UI thread when a swipe in from an edge is recognised.
+pub type SwipeCallback = Box<dyn Fn(SwipeEdge)>;
+
+#[derive(Debug, Clone)]
+pub struct GestureOptions {
+    /// Holding a touch this long without moving sends a right click instead.
+    pub long_press: Option<Duration>,
+    /// Pixels a touch has to move before it counts as a drag.
+    pub drag_threshold: f32,
+    /// Turns drags into scroll events that keep going after lift-off.
+    pub drag_scrolling: bool,
+    /// Kinetic scrolling slows down to about a third within this time.
+    pub kinetic_time_constant: Duration,
+    /// Swipes have to start this many pixels from an edge, 0 disables them.
+    pub edge_width: f32,
+    /// Pixels a swipe has to travel inwards.
+    pub swipe_distance: f32,
+}
+
+impl Default for GestureOptions {
+    fn default() -> Self {
+        Self {
+            long_press: None,
+            drag_threshold: 10.0,
+            drag_scrolling: false,
+            kinetic_time_constant: Duration::from_millis(325),
+            edge_width: 0.0,
+            swipe_distance: 60.0,
+        }
+    }
+}
+
+enum TouchGesture {
+    Idle,
+    /// Forwarded to Slint as a normal left button touch.
+    Tap {
+        start: LogicalPosition,
+        started_at: Instant,
+        edge: Option<SwipeEdge>,
+        moved: bool,
+    },
+    Scrolling {
+        last: LogicalPosition,
+        last_time: Instant,
+        velocity: (f32, f32),
+    },
+    /// Already handled as a long-press or swipe, the rest of the touch is dropped.
+    Consumed,
+}
+
+struct Kinetic {
+    position: LogicalPosition,
+    velocity: (f32, f32),
+    last_update: Instant,
+}
+
+/// Turns the left button touches of a touch `InputPlatform` into gestures.
+pub struct GestureRecognizer {
+    options: GestureOptions,
+    width: f32,
+    height: f32,
+    state: TouchGesture,
+    kinetic: Option<Kinetic>,
+    on_swipe: Option<SwipeCallback>,
+}
+
+impl GestureRecognizer {
+    /// `width` and `height` are the window size.
+    pub fn new(options: GestureOptions, width: f32, height: f32, on_swipe: Option<SwipeCallback>) -> Self {
+        Self {
+            options,
+            width,
+            height,
+            state: TouchGesture::Idle,
+            kinetic: None,
+            on_swipe,
+        }
+    }
+
+    pub fn process(&mut self, events: Vec<WindowEvent>, now: Instant) -> Vec<WindowEvent> {
+        let mut result = vec![];
+
+        for event in events {
+            match event {
+                WindowEvent::PointerPressed { position, button: PointerEventButton::Left } => {
+                    self.kinetic = None;
+                    self.state = TouchGesture::Tap {
+                        start: position,
+                        started_at: now,
+                        edge: self.edge_at(position),
+                        moved: false,
+                    };
+                    result.push(event);
+                }
+                WindowEvent::PointerMoved { position } => self.on_move(position, now, event, &mut result),
+                WindowEvent::PointerReleased { position, button: PointerEventButton::Left } => {
+                    match std::mem::replace(&mut self.state, TouchGesture::Idle) {
+                        TouchGesture::Scrolling { last_time, velocity, .. } => {
+                            let speed = velocity.0.hypot(velocity.1);
+
+                            if speed >= MIN_KINETIC_VELOCITY && now.duration_since(last_time) < FLING_TIMEOUT {
+                                self.kinetic = Some(Kinetic { position, velocity, last_update: now });
+                            }
+                        }
+                        TouchGesture::Consumed => {}
+                        _ => result.push(event),
+                    }
+                }
+                _ => result.push(event),
+            }
+        }
+
+        result
+    }
+
+    fn on_move(&mut self, position: LogicalPosition, now: Instant, event: WindowEvent, result: &mut Vec<WindowEvent>) {
+        match &mut self.state {
+            TouchGesture::Tap { start, edge, moved, .. } => {
+                let (dx, dy) = (position.x - start.x, position.y - start.y);
+
+                if dx.hypot(dy) >= self.options.drag_threshold {
+                    *moved = true;
+                }
+
+                if let Some(edge) = *edge {
+                    let (inwards, sideways) = match edge {
+                        SwipeEdge::Left => (dx, dy),
+                        SwipeEdge::Right => (-dx, dy),
+                        SwipeEdge::Top => (dy, dx),
+                        SwipeEdge::Bottom => (-dy, dx),
+                    };
+
+                    if inwards >= self.options.swipe_distance && inwards > sideways.abs() {
+                        result.push(WindowEvent::PointerExited);
+                        self.state = TouchGesture::Consumed;
+
+                        if let Some(on_swipe) = &self.on_swipe {
+                            on_swipe(edge);
+                        }
+                        return;
+                    }
+                }
+
+                if !self.options.drag_scrolling {
+                    result.push(event);
+                } else if *moved {
+                    // Cancel the press so the touched item isn't clicked
+                    result.push(WindowEvent::PointerExited);
+                    result.push(WindowEvent::PointerScrolled { position, delta_x: dx, delta_y: dy });
+                    self.state = TouchGesture::Scrolling { last: position, last_time: now, velocity: (0.0, 0.0) };
+                }
+            }
+            TouchGesture::Scrolling { last, last_time, velocity } => {
+                let (dx, dy) = (position.x - last.x, position.y - last.y);
+                let dt = now.duration_since(*last_time).as_secs_f32().max(0.001);
+
+                // Smoothed, single samples are too noisy to fling with
+                velocity.0 = velocity.0 * 0.2 + dx / dt * 0.8;
+                velocity.1 = velocity.1 * 0.2 + dy / dt * 0.8;
+                *last = position;
+                *last_time = now;
+
+                result.push(WindowEvent::PointerScrolled { position, delta_x: dx, delta_y: dy });
+            }
+            TouchGesture::Consumed => {}
+            TouchGesture::Idle => result.push(event),
+        }
+    }
+
+    /// Runs the time based parts: long-press and kinetic scrolling.
+    pub fn update(&mut self, now: Instant) -> Vec<WindowEvent> {
+        let mut result = vec![];
+
+        if let (TouchGesture::Tap { start, started_at, moved: false, .. }, Some(long_press)) = (&self.state, self.options.long_press)
+            && now.duration_since(*started_at) >= long_press
+        {
+            let position = *start;
+            result.push(WindowEvent::PointerExited);
+            result.push(WindowEvent::PointerPressed { position, button: PointerEventButton::Right });
+            result.push(WindowEvent::PointerReleased { position, button: PointerEventButton::Right });
+            self.state = TouchGesture::Consumed;
+        }
+
+        if let Some(kinetic) = &mut self.kinetic {
+            let dt = now.duration_since(kinetic.last_update).as_secs_f32();
+            let decay = (-dt / self.options.kinetic_time_constant.as_secs_f32()).exp();
+
+            kinetic.velocity = (kinetic.velocity.0 * decay, kinetic.velocity.1 * decay);
+            kinetic.last_update = now;
+
+            result.push(WindowEvent::PointerScrolled {
+                position: kinetic.position,
+                delta_x: kinetic.velocity.0 * dt,
+                delta_y: kinetic.velocity.1 * dt,
+            });
+
+            if kinetic.velocity.0.hypot(kinetic.velocity.1) < MIN_KINETIC_VELOCITY {
+                self.kinetic = None;
+            }
+        }
+
+        result
+    }
+
+    /// When `update` has to run next, if anything is pending.
+    pub fn next_deadline(&self) -> Option<Instant> {
+        if let Some(kinetic) = &self.kinetic {
+            return Some(kinetic.last_update + KINETIC_FRAME);
+        }
+
+        match (&self.state, self.options.long_press) {
+            (TouchGesture::Tap { started_at, moved: false, .. }, Some(long_press)) => Some(*started_at + long_press),
+            _ => None,
+        }
+    }
+
+    fn edge_at(&self, position: LogicalPosition) -> Option<SwipeEdge> {
+        let edge_width = self.options.edge_width;
+
+        if edge_width <= 0.0 || self.on_swipe.is_none() {
+            None
+        } else if position.x < edge_width {
+            Some(SwipeEdge::Left)
+        } else if position.x >= self.width - edge_width {
+            Some(SwipeEdge::Right)
+        } else if position.y < edge_width {
+            Some(SwipeEdge::Top)
+        } else if position.y >= self.height - edge_width {
+            Some(SwipeEdge::Bottom)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use super::*;
+
+    fn recognizer(edge_width: f32) -> (GestureRecognizer, Rc<RefCell<Vec<SwipeEdge>>>) {
+        let swipes = Rc::new(RefCell::new(vec![]));
+        let options = GestureOptions { edge_width, ..GestureOptions::default() };
+        let on_swipe: SwipeCallback = {
+            let swipes = swipes.clone();
+            Box::new(move |edge| swipes.borrow_mut().push(edge))
+        };
+
+        (GestureRecognizer::new(options, 480.0, 272.0, Some(on_swipe)), swipes)
+    }
+
+    /// Touches down at `from`, moves to `to` and lifts off, returning what reached Slint.
+    fn touch(recognizer: &mut GestureRecognizer, from: (f32, f32), to: (f32, f32)) -> Vec<WindowEvent> {
+        let button = PointerEventButton::Left;
+        let now = Instant::now();
+
+        recognizer.process(
+            vec![
+                WindowEvent::PointerPressed { position: LogicalPosition::new(from.0, from.1), button },
+                WindowEvent::PointerMoved { position: LogicalPosition::new(to.0, to.1) },
+                WindowEvent::PointerReleased { position: LogicalPosition::new(to.0, to.1), button },
+            ],
+            now,
+        )
+    }
+
+    #[test]
+    fn swipes_in_from_every_edge() {
+        let (mut recognizer, swipes) = recognizer(20.0);
+
+        touch(&mut recognizer, (5.0, 100.0), (100.0, 110.0));
+        touch(&mut recognizer, (475.0, 100.0), (380.0, 90.0));
+        touch(&mut recognizer, (240.0, 5.0), (250.0, 100.0));
+        touch(&mut recognizer, (240.0, 268.0), (230.0, 170.0));
+
+        assert_eq!(*swipes.borrow(), vec![SwipeEdge::Left, SwipeEdge::Right, SwipeEdge::Top, SwipeEdge::Bottom]);
+    }
+
+    #[test]
+    fn swipes_cancel_the_touch() {
+        let (mut recognizer, _) = recognizer(20.0);
+
+        let events = touch(&mut recognizer, (5.0, 100.0), (100.0, 100.0));
+
+        assert_eq!(events.len(), 2);
+        assert!(matches!(events[0], WindowEvent::PointerPressed { .. }));
+        assert!(matches!(events[1], WindowEvent::PointerExited));
+    }
+
+    #[test]
+    fn short_or_sideways_moves_are_not_swipes() {
+        let (mut recognizer, swipes) = recognizer(20.0);
+
+        let events = touch(&mut recognizer, (5.0, 100.0), (40.0, 100.0));
+        touch(&mut recognizer, (5.0, 50.0), (80.0, 200.0));
+        touch(&mut recognizer, (5.0, 100.0), (0.0, 100.0));
+
+        assert!(swipes.borrow().is_empty());
+        assert!(matches!(events.last(), Some(WindowEvent::PointerReleased { .. })));
+    }
+
+    #[test]
+    fn swipes_have_to_start_at_an_edge() {
+        let (mut inset, swipes) = recognizer(20.0);
+        touch(&mut inset, (30.0, 100.0), (200.0, 100.0));
+        assert!(swipes.borrow().is_empty());
+
+        let (mut disabled, swipes) = recognizer(0.0);
+        touch(&mut disabled, (0.0, 100.0), (200.0, 100.0));
+        assert!(swipes.borrow().is_empty());
+    }
+
+    fn press(recognizer: &mut GestureRecognizer, x: f32, y: f32, now: Instant) -> Vec<WindowEvent> {
+        recognizer.process(vec![WindowEvent::PointerPressed { position: LogicalPosition::new(x, y), button: PointerEventButton::Left }], now)
+    }
+
+    fn move_to(recognizer: &mut GestureRecognizer, x: f32, y: f32, now: Instant) -> Vec<WindowEvent> {
+        recognizer.process(vec![WindowEvent::PointerMoved { position: LogicalPosition::new(x, y) }], now)
+    }
+
+    fn release(recognizer: &mut GestureRecognizer, x: f32, y: f32, now: Instant) -> Vec<WindowEvent> {
+        recognizer.process(vec![WindowEvent::PointerReleased { position: LogicalPosition::new(x, y), button: PointerEventButton::Left }], now)
+    }
+
+    fn scrolling_recognizer() -> GestureRecognizer {
+        let options = GestureOptions { drag_scrolling: true, ..GestureOptions::default() };
+        GestureRecognizer::new(options, 480.0, 272.0, None)
+    }
+
+    #[test]
+    fn long_press_sends_a_right_click() {
+        let options = GestureOptions { long_press: Some(Duration::from_millis(500)), ..GestureOptions::default() };
+        let mut recognizer = GestureRecognizer::new(options, 480.0, 272.0, None);
+        let start = Instant::now();
+
+        press(&mut recognizer, 100.0, 100.0, start);
+        assert_eq!(recognizer.next_deadline(), Some(start + Duration::from_millis(500)));
+        assert!(recognizer.update(start + Duration::from_millis(499)).is_empty());
+
+        let events = recognizer.update(start + Duration::from_millis(500));
+        assert_eq!(events.len(), 3);
+        assert!(matches!(events[0], WindowEvent::PointerExited));
+        assert!(matches!(events[1], WindowEvent::PointerPressed { button: PointerEventButton::Right, position } if position == LogicalPosition::new(100.0, 100.0)));
+        assert!(matches!(events[2], WindowEvent::PointerReleased { button: PointerEventButton::Right, .. }));
+        assert_eq!(recognizer.next_deadline(), None);
+
+        // The left button release was already cancelled by the long-press
+        assert!(release(&mut recognizer, 100.0, 100.0, start + Duration::from_millis(600)).is_empty());
+    }
+
+    #[test]
+    fn moving_cancels_the_long_press() {
+        let options = GestureOptions { long_press: Some(Duration::from_millis(500)), ..GestureOptions::default() };
+        let mut recognizer = GestureRecognizer::new(options, 480.0, 272.0, None);
+        let start = Instant::now();
+
+        press(&mut recognizer, 100.0, 100.0, start);
+        move_to(&mut recognizer, 150.0, 100.0, start + Duration::from_millis(100));
+
+        assert_eq!(recognizer.next_deadline(), None);
+        assert!(recognizer.update(start + Duration::from_millis(600)).is_empty());
+    }
+
+    #[test]
+    fn drags_turn_into_scrolling() {
+        let mut recognizer = scrolling_recognizer();
+        let start = Instant::now();
+
+        press(&mut recognizer, 100.0, 100.0, start);
+        // Below the drag threshold nothing is sent yet
+        assert!(move_to(&mut recognizer, 100.0, 105.0, start + Duration::from_millis(16)).is_empty());
+
+        let events = move_to(&mut recognizer, 100.0, 130.0, start + Duration::from_millis(32));
+        assert_eq!(events.len(), 2);
+        assert!(matches!(events[0], WindowEvent::PointerExited));
+        assert!(matches!(events[1], WindowEvent::PointerScrolled { delta_x, delta_y, .. } if delta_x == 0.0 && delta_y == 30.0));
+
+        let events = move_to(&mut recognizer, 90.0, 150.0, start + Duration::from_millis(48));
+        assert_eq!(events.len(), 1);
+        assert!(matches!(events[0], WindowEvent::PointerScrolled { delta_x, delta_y, .. } if delta_x == -10.0 && delta_y == 20.0));
+
+        // Resting before lift-off doesn't fling, and the release isn't forwarded either
+        assert!(release(&mut recognizer, 90.0, 150.0, start + Duration::from_millis(500)).is_empty());
+        assert_eq!(recognizer.next_deadline(), None);
+    }
+
+    #[test]
+    fn flings_decay_until_they_stop() {
+        let mut recognizer = scrolling_recognizer();
+        let start = Instant::now();
+        let frame = Duration::from_millis(16);
+
+        press(&mut recognizer, 100.0, 100.0, start);
+        move_to(&mut recognizer, 100.0, 120.0, start + frame);
+        move_to(&mut recognizer, 100.0, 140.0, start + frame * 2);
+        release(&mut recognizer, 100.0, 140.0, start + frame * 2);
+
+        let mut now = start + frame * 2;
+        let mut last_delta = f32::MAX;
+        let mut frames = 0;
+
+        while let Some(deadline) = recognizer.next_deadline() {
+            assert_eq!(deadline, now + KINETIC_FRAME);
+            now = deadline;
+
+            let events = recognizer.update(now);
+            assert_eq!(events.len(), 1);
+
+            let WindowEvent::PointerScrolled { position, delta_x, delta_y } = events[0] else {
+                panic!("expected a scroll event, got {:?}", events[0]);
+            };
+            assert_eq!(position, LogicalPosition::new(100.0, 140.0));
+            assert_eq!(delta_x, 0.0);
+            assert!(delta_y > 0.0 && delta_y < last_delta);
+
+            last_delta = delta_y;
+            frames += 1;
+            assert!(frames < 1000, "kinetic scrolling never stopped");
+        }
+
+        assert!(frames > 1);
+        assert!(recognizer.update(now + KINETIC_FRAME).is_empty());
+    }
+
+    #[test]
+    fn touching_stops_a_fling() {
+        let mut recognizer = scrolling_recognizer();
+        let start = Instant::now();
+        let frame = Duration::from_millis(16);
+
+        press(&mut recognizer, 100.0, 100.0, start);
+        move_to(&mut recognizer, 100.0, 120.0, start + frame);
+        move_to(&mut recognizer, 100.0, 140.0, start + frame * 2);
+        release(&mut recognizer, 100.0, 140.0, start + frame * 2);
+        assert!(recognizer.next_deadline().is_some());
+
+        press(&mut recognizer, 100.0, 200.0, start + frame * 3);
+        assert_eq!(recognizer.next_deadline(), None);
+    }
+}
@@ -1,16 +1,48 @@
-use evdev::Device;
 use linuxfb::Framebuffer;
 use slint::platform::Platform;
 
-use crate::framebuffer::framebuffer_platform::FramebufferPlatform;
+use crate::{
+    error::DriverError,
+    framebuffer::{framebuffer_platform::FramebufferPlatform, gestures::{GestureOptions, SwipeCallback}, input_device::{InputKind, InputOptions}, pixel_format::PixelFormat, rotation::Rotation, screensaver::ScreensaverHandle, touch_device::TouchOptions},
+    vnc::VncOptions,
+};
 
-pub fn init_framebuffer(fb_path: String, event_path: Option<String>, double_buffering: bool) -> Box<dyn Platform + 'static> {
-    let fb = Framebuffer::new(fb_path).expect("Failed to initialise framebuffer");
-    
-    let touch_device = match event_path {
-        Some(path) => Some(Device::open(&path).expect("Failed to open touch event device")),
-        None => None,
-    };
+pub struct FramebufferOptions {
+    pub fb_path: String,
+    /// Touchscreens, mice, keyboards and encoders, all feeding the same window.
+    pub inputs: Vec<InputOptions>,
+    pub double_buffering: bool,
+    /// Detected from the framebuffer if unset.
+    pub pixel_format: Option<PixelFormat>,
+    pub rotation: Rotation,
+    pub touch: TouchOptions,
+    pub gestures: GestureOptions,
+    pub on_swipe: Option<SwipeCallback>,
+    pub screensaver: Option<ScreensaverHandle>,
+    /// Mirrors the screen to VNC viewers, which can also operate it.
+    pub vnc: Option<VncOptions>,
+}
 
-    Box::new(FramebufferPlatform::new(fb, touch_device, double_buffering))
-}
\ No newline at end of file
+impl FramebufferOptions {
+    /// `event_path` is taken as a touchscreen, more inputs can be pushed to `inputs`.
+    pub fn new(fb_path: String, event_path: Option<String>) -> Self {
+        Self {
+            fb_path,
+            inputs: event_path.into_iter().map(|path| InputOptions::from_path(path, InputKind::Touch)).collect(),
+            double_buffering: true,
+            pixel_format: None,
+            rotation: Rotation::default(),
+            touch: TouchOptions::default(),
+            gestures: GestureOptions::default(),
+            on_swipe: None,
+            screensaver: None,
+            vnc: None,
+        }
+    }
+}
+
+pub fn init_framebuffer(options: FramebufferOptions) -> Result<Box<dyn Platform + 'static>, DriverError> {
+    let fb = Framebuffer::new(options.fb_path.clone())?;
+
+    Ok(Box::new(FramebufferPlatform::new(fb, options)?))
+}
@@ -0,0 +1,125 @@
+use std::{io, os::fd::RawFd, str::FromStr};
+
+use evdev::{AbsoluteAxisCode, Device, RelativeAxisCode};
+use slint::{platform::WindowEvent, LogicalPosition};
+
+use crate::{
+    error::DriverError,
+    framebuffer::{
+        evdev_keys_platform::EvdevKeysPlatform,
+        evdev_mouse_platform::EvdevMousePlatform,
+        rotation::Rotation,
+        touch_device::{open_touch_platform, TouchOptions},
+    },
+};
+
+pub trait InputPlatform {
+    /// Errors other than `WouldBlock` mean the device is gone, e.g. `ENODEV` after unplugging.
+    fn process_events(&self) -> io::Result<Vec<WindowEvent>>;
+    /// Polled for readability, `process_events` runs when it is.
+    fn raw_fd(&self) -> RawFd;
+    /// Touch input goes through gesture recognition.
+    fn is_touch(&self) -> bool {
+        false
+    }
+    /// Where to draw the mouse cursor, for devices that move one.
+    fn cursor_position(&self) -> Option<LogicalPosition> {
+        None
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum InputKind {
+    /// Picked from the axes the device reports.
+    #[default]
+    Auto,
+    Touch,
+    /// Relative pointer with a rendered cursor.
+    Mouse,
+    /// Keyboards, `gpio-keys` buttons and rotary encoders.
+    Keys,
+}
+
+impl FromStr for InputKind {
+    type Err = DriverError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "auto" => Ok(InputKind::Auto),
+            "touch" => Ok(InputKind::Touch),
+            "mouse" => Ok(InputKind::Mouse),
+            "keys" | "keyboard" | "encoder" => Ok(InputKind::Keys),
+            _ => Err(DriverError::UnsupportedInputDevice(format!("Unknown input type {}", s))),
+        }
+    }
+}
+
+/// A configured input. Without a `path` the first device in `/dev/input`
+/// matching `name`, `vendor` and `kind` is used, a touchscreen if all are unset.
+#[derive(Debug, Clone, Default)]
+pub struct InputOptions {
+    pub path: Option<String>,
+    /// Part of the device name, case insensitive.
+    pub name: Option<String>,
+    pub vendor: Option<u16>,
+    pub kind: InputKind,
+}
+
+impl InputOptions {
+    pub fn from_path(path: String, kind: InputKind) -> Self {
+        Self {
+            path: Some(path),
+            kind,
+            ..Default::default()
+        }
+    }
+
+    /// Whether a discovered device is meant by these options.
+    pub(crate) fn matches(&self, device: &Device) -> bool {
+        let name_matches = self.name.as_ref().is_none_or(|name| {
+            device.name().is_some_and(|device_name| device_name.to_lowercase().contains(&name.to_lowercase()))
+        });
+        let vendor_matches = self.vendor.is_none_or(|vendor| device.input_id().vendor() == vendor);
+        let kind_matches = match self.kind {
+            InputKind::Auto if self.name.is_some() || self.vendor.is_some() => true,
+            InputKind::Auto => detect_kind(device) == InputKind::Touch,
+            kind => detect_kind(device) == kind,
+        };
+
+        name_matches && vendor_matches && kind_matches
+    }
+}
+
+fn detect_kind(device: &Device) -> InputKind {
+    let has_absolute = |axis| device.supported_absolute_axes().is_some_and(|axes| axes.contains(axis));
+    let has_relative = |axis| device.supported_relative_axes().is_some_and(|axes| axes.contains(axis));
+
+    if has_absolute(AbsoluteAxisCode::ABS_MT_POSITION_X) || has_absolute(AbsoluteAxisCode::ABS_X) {
+        InputKind::Touch
+    } else if has_relative(RelativeAxisCode::REL_X) && has_relative(RelativeAxisCode::REL_Y) {
+        InputKind::Mouse
+    } else {
+        InputKind::Keys
+    }
+}
+
+/// `width` and `height` are the native, unrotated size of the panel.
+pub(crate) fn open_input_platform(path: &str, device: Device, options: &InputOptions, touch_options: &TouchOptions, rotation: Rotation, width: u32, height: u32) -> Result<Box<dyn InputPlatform>, DriverError> {
+    device.set_nonblocking(true)?;
+
+    let kind = match options.kind {
+        InputKind::Auto => detect_kind(&device),
+        kind => kind,
+    };
+
+    println!("Input device {}: {:?} as {:?}", path, device.name(), kind);
+
+    match kind {
+        InputKind::Mouse => {
+            let (logical_width, logical_height) = rotation.logical_size(width, height);
+            Ok(Box::new(EvdevMousePlatform::new(device, logical_width, logical_height)))
+        }
+        InputKind::Keys => Ok(Box::new(EvdevKeysPlatform::new(device))),
+        _ => open_touch_platform(device, touch_options.clone(), rotation, width, height),
+    }
+}
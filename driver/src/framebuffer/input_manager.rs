@@ -0,0 +1,199 @@
+use std::{
+    ffi::CString,
+    fs, io,
+    os::fd::{AsRawFd, FromRawFd, OwnedFd, RawFd},
+};
+
+use evdev::Device;
+use slint::platform::WindowEvent;
+
+use crate::framebuffer::{
+    input_device::{open_input_platform, InputOptions, InputPlatform},
+    rotation::Rotation,
+    touch_device::TouchOptions,
+};
+
+const INPUT_DIR: &str = "/dev/input";
+
+struct InputSlot {
+    options: InputOptions,
+    /// Path of the open device, discovered ones included.
+    path: Option<String>,
+    device: Option<Box<dyn InputPlatform>>,
+}
+
+/// Owns the configured input devices. Missing ones are opened once they show
+/// up in `/dev/input`, unplugged ones are dropped and opened again later, so
+/// the UI keeps running without input instead of failing.
+pub(crate) struct InputManager {
+    slots: Vec<InputSlot>,
+    touch_options: TouchOptions,
+    rotation: Rotation,
+    width: u32,
+    height: u32,
+    /// inotify watch on `/dev/input`, unset if it could not be created.
+    watch: Option<OwnedFd>,
+}
+
+impl InputManager {
+    /// `width` and `height` are the native, unrotated size of the panel.
+    pub fn new(options: &[InputOptions], touch_options: TouchOptions, rotation: Rotation, width: u32, height: u32) -> Self {
+        let watch = match options.is_empty() {
+            true => None,
+            false => match watch_input_dir() {
+                Ok(watch) => Some(watch),
+                Err(e) => {
+                    println!("Not watching {} for new input devices: {}", INPUT_DIR, e);
+                    None
+                }
+            },
+        };
+
+        let mut manager = Self {
+            slots: options
+                .iter()
+                .map(|options| InputSlot { options: options.clone(), path: None, device: None })
+                .collect(),
+            touch_options,
+            rotation,
+            width,
+            height,
+            watch,
+        };
+
+        manager.open_missing();
+
+        for slot in manager.slots.iter().filter(|slot| slot.device.is_none()) {
+            println!("Waiting for input device {:?}", slot.options);
+        }
+
+        manager
+    }
+
+    /// Device fds and the inotify watch, to wait on.
+    pub fn raw_fds(&self) -> Vec<RawFd> {
+        self.slots
+            .iter()
+            .filter_map(|slot| slot.device.as_ref().map(|device| device.raw_fd()))
+            .chain(self.watch.as_ref().map(|watch| watch.as_raw_fd()))
+            .collect()
+    }
+
+    /// Hands the pending events of every open device to `handle`.
+    pub fn process_events(&mut self, mut handle: impl FnMut(&dyn InputPlatform, Vec<WindowEvent>)) {
+        if let Some(watch) = &self.watch
+            && drain_watch(watch)
+        {
+            self.open_missing();
+        }
+
+        for slot in &mut self.slots {
+            let Some(device) = &slot.device else {
+                continue;
+            };
+
+            match device.process_events() {
+                Ok(events) => handle(device.as_ref(), events),
+                Err(e) => {
+                    println!("Lost input device {}: {}", slot.path.as_deref().unwrap_or_default(), e);
+                    slot.device = None;
+                    slot.path = None;
+                }
+            }
+        }
+    }
+
+    fn open_missing(&mut self) {
+        // Discovery never takes a device that is open or configured by path
+        let mut reserved: Vec<String> = self
+            .slots
+            .iter()
+            .filter_map(|slot| slot.path.clone().or_else(|| slot.options.path.clone()))
+            .collect();
+        let mut discovered: Option<Vec<String>> = None;
+
+        for slot in self.slots.iter_mut().filter(|slot| slot.device.is_none()) {
+            let candidates = match &slot.options.path {
+                Some(path) => vec![path.clone()],
+                None => discovered
+                    .get_or_insert_with(list_event_devices)
+                    .iter()
+                    .filter(|path| !reserved.contains(path))
+                    .cloned()
+                    .collect(),
+            };
+
+            for path in candidates {
+                // Not there yet, or udev did not set the permissions yet
+                let Ok(device) = Device::open(&path) else {
+                    continue;
+                };
+
+                if slot.options.path.is_none() && !slot.options.matches(&device) {
+                    continue;
+                }
+
+                match open_input_platform(&path, device, &slot.options, &self.touch_options, self.rotation, self.width, self.height) {
+                    Ok(device) => {
+                        slot.device = Some(device);
+                        slot.path = Some(path.clone());
+                        reserved.push(path);
+                        break;
+                    }
+                    Err(e) => println!("Failed to open input device {}: {}", path, e),
+                }
+            }
+        }
+    }
+}
+
+/// `/dev/input/event*` in numeric order.
+fn list_event_devices() -> Vec<String> {
+    let mut devices: Vec<(u32, String)> = match fs::read_dir(INPUT_DIR) {
+        Ok(entries) => entries
+            .flatten()
+            .filter_map(|entry| {
+                let name = entry.file_name().into_string().ok()?;
+                let number = name.strip_prefix("event")?.parse().ok()?;
+                Some((number, entry.path().to_string_lossy().into_owned()))
+            })
+            .collect(),
+        Err(e) => {
+            println!("Failed to list {}: {}", INPUT_DIR, e);
+            vec![]
+        }
+    };
+
+    devices.sort();
+    devices.into_iter().map(|(_, path)| path).collect()
+}
+
+fn watch_input_dir() -> io::Result<OwnedFd> {
+    let fd = unsafe { libc::inotify_init1(libc::IN_NONBLOCK | libc::IN_CLOEXEC) };
+
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+    let path = CString::new(INPUT_DIR).unwrap();
+
+    // Nodes are created before udev sets their permissions, hence IN_ATTRIB
+    if unsafe { libc::inotify_add_watch(fd.as_raw_fd(), path.as_ptr(), libc::IN_CREATE | libc::IN_ATTRIB) } < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok(fd)
+}
+
+/// Reads all pending notifications, returns whether there were any.
+fn drain_watch(watch: &OwnedFd) -> bool {
+    let mut buffer = [0u8; 4096];
+    let mut notified = false;
+
+    while unsafe { libc::read(watch.as_raw_fd(), buffer.as_mut_ptr() as *mut libc::c_void, buffer.len()) } > 0 {
+        notified = true;
+    }
+
+    notified
+}
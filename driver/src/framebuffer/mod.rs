@@ -1,5 +1,27 @@
+mod cursor;
+mod event_wait;
+mod evdev_keys_platform;
+mod evdev_mouse_platform;
 mod evdev_mt_touch_platform;
+mod evdev_st_touch_platform;
 mod framebuffer_platform;
+mod gestures;
 mod init_framebuffer;
+mod input_device;
+mod input_manager;
+mod multitouch;
+mod pixel_format;
+mod rotation;
+mod screensaver;
+mod singletouch;
+mod touch_device;
+mod touch_mapping;
 
-pub use init_framebuffer::init_framebuffer;
\ No newline at end of file
+pub use gestures::{GestureOptions, SwipeCallback, SwipeEdge};
+pub use init_framebuffer::{init_framebuffer, FramebufferOptions};
+pub use input_device::{InputKind, InputOptions};
+pub use pixel_format::PixelFormat;
+pub use rotation::Rotation;
+pub use screensaver::{Screensaver, ScreensaverHandle, ScreensaverOptions, ScreenState};
+pub use touch_device::{TouchKind, TouchOptions};
+pub use touch_mapping::{compute_calibration_matrix, CalibrationMatrix, CalibrationSample, TouchCalibrationHandle, TouchMappingOptions};
@@ -0,0 +1,265 @@
+use evdev::{AbsoluteAxisCode, EventSummary, KeyCode, SynchronizationCode};
+
+/// Pointer transitions in raw device coordinates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TouchEvent {
+    Pressed(i32, i32),
+    Moved(i32, i32),
+    Released(i32, i32),
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Slot {
+    /// `None` while no contact is tracked in this slot.
+    tracking_id: Option<i32>,
+    x: i32,
+    y: i32,
+}
+
+/// Multitouch protocol state machine. Events are accumulated and applied per
+/// `SYN_REPORT` frame; the first contact drives the pointer, further fingers
+/// are tracked but not reported.
+#[derive(Debug, Default)]
+pub struct MultitouchState {
+    slots: Vec<Slot>,
+    /// Committed at the end of the previous frame.
+    committed: Vec<Slot>,
+    current_slot: usize,
+    /// Devices without tracking ids only report contact through `BTN_TOUCH`.
+    has_tracking_ids: bool,
+    button: bool,
+    primary: Option<usize>,
+}
+
+impl MultitouchState {
+    pub fn process(&mut self, event: EventSummary) -> Vec<TouchEvent> {
+        match event {
+            EventSummary::AbsoluteAxis(_, AbsoluteAxisCode::ABS_MT_SLOT, value) => {
+                self.current_slot = value.max(0) as usize;
+            }
+            EventSummary::AbsoluteAxis(_, AbsoluteAxisCode::ABS_MT_TRACKING_ID, value) => {
+                self.has_tracking_ids = true;
+                self.slot_mut().tracking_id = if value < 0 { None } else { Some(value) };
+            }
+            EventSummary::AbsoluteAxis(_, AbsoluteAxisCode::ABS_MT_POSITION_X, value) => {
+                self.slot_mut().x = value;
+            }
+            EventSummary::AbsoluteAxis(_, AbsoluteAxisCode::ABS_MT_POSITION_Y, value) => {
+                self.slot_mut().y = value;
+            }
+            EventSummary::Key(_, KeyCode::BTN_TOUCH, value) => {
+                self.button = value != 0;
+            }
+            EventSummary::Synchronization(_, SynchronizationCode::SYN_REPORT, _) => {
+                return self.end_frame();
+            }
+            _ => {}
+        }
+
+        vec![]
+    }
+
+    fn slot_mut(&mut self) -> &mut Slot {
+        if self.slots.len() <= self.current_slot {
+            self.slots.resize(self.current_slot + 1, Slot::default());
+        }
+
+        &mut self.slots[self.current_slot]
+    }
+
+    fn end_frame(&mut self) -> Vec<TouchEvent> {
+        if !self.has_tracking_ids {
+            let button = self.button;
+            let slot = self.slot_mut();
+            slot.tracking_id = if button { Some(0) } else { None };
+        }
+
+        self.committed.resize(self.slots.len(), Slot::default());
+        let mut result = vec![];
+
+        if let Some(primary) = self.primary {
+            let before = self.committed[primary];
+            let now = self.slots[primary];
+
+            if now.tracking_id != before.tracking_id {
+                // Lifted, or lifted and replaced by a new contact within the same frame
+                result.push(TouchEvent::Released(before.x, before.y));
+                self.primary = None;
+            } else if (now.x, now.y) != (before.x, before.y) {
+                result.push(TouchEvent::Moved(now.x, now.y));
+            }
+        }
+
+        if self.primary.is_none() {
+            let new_contact = self.slots.iter().zip(&self.committed).position(|(now, before)| {
+                now.tracking_id.is_some() && now.tracking_id != before.tracking_id
+            });
+
+            if let Some(index) = new_contact {
+                let slot = self.slots[index];
+                result.push(TouchEvent::Pressed(slot.x, slot.y));
+                self.primary = Some(index);
+            }
+        }
+
+        self.committed.clone_from(&self.slots);
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use evdev::{EventType, InputEvent};
+
+    use super::*;
+
+    const SYN: u16 = EventType::SYNCHRONIZATION.0;
+    const KEY: u16 = EventType::KEY.0;
+    const ABS: u16 = EventType::ABSOLUTE.0;
+
+    const SLOT: u16 = AbsoluteAxisCode::ABS_MT_SLOT.0;
+    const TRACKING_ID: u16 = AbsoluteAxisCode::ABS_MT_TRACKING_ID.0;
+    const X: u16 = AbsoluteAxisCode::ABS_MT_POSITION_X.0;
+    const Y: u16 = AbsoluteAxisCode::ABS_MT_POSITION_Y.0;
+    const TOUCH: u16 = KeyCode::BTN_TOUCH.0;
+    const REPORT: u16 = SynchronizationCode::SYN_REPORT.0;
+
+    /// Feeds `(type, code, value)` triples as printed by evtest.
+    fn replay(recording: &[(u16, u16, i32)]) -> Vec<TouchEvent> {
+        let mut state = MultitouchState::default();
+
+        recording
+            .iter()
+            .flat_map(|(type_, code, value)| state.process(InputEvent::new(*type_, *code, *value).destructure()))
+            .collect()
+    }
+
+    #[test]
+    fn tap_within_one_batch() {
+        let events = replay(&[
+            (ABS, TRACKING_ID, 12),
+            (ABS, X, 100),
+            (ABS, Y, 200),
+            (KEY, TOUCH, 1),
+            (SYN, REPORT, 0),
+            (ABS, TRACKING_ID, -1),
+            (KEY, TOUCH, 0),
+            (SYN, REPORT, 0),
+        ]);
+
+        assert_eq!(events, vec![TouchEvent::Pressed(100, 200), TouchEvent::Released(100, 200)]);
+    }
+
+    #[test]
+    fn drag_emits_every_move() {
+        let events = replay(&[
+            (ABS, TRACKING_ID, 1),
+            (ABS, X, 10),
+            (ABS, Y, 10),
+            (KEY, TOUCH, 1),
+            (SYN, REPORT, 0),
+            (ABS, X, 20),
+            (SYN, REPORT, 0),
+            (ABS, Y, 30),
+            (SYN, REPORT, 0),
+            (ABS, X, 25),
+            (ABS, Y, 35),
+            (SYN, REPORT, 0),
+            (ABS, TRACKING_ID, -1),
+            (KEY, TOUCH, 0),
+            (SYN, REPORT, 0),
+        ]);
+
+        assert_eq!(
+            events,
+            vec![
+                TouchEvent::Pressed(10, 10),
+                TouchEvent::Moved(20, 10),
+                TouchEvent::Moved(20, 30),
+                TouchEvent::Moved(25, 35),
+                TouchEvent::Released(25, 35),
+            ]
+        );
+    }
+
+    #[test]
+    fn second_finger_does_not_move_pointer() {
+        let events = replay(&[
+            (ABS, SLOT, 0),
+            (ABS, TRACKING_ID, 5),
+            (ABS, X, 100),
+            (ABS, Y, 100),
+            (KEY, TOUCH, 1),
+            (SYN, REPORT, 0),
+            (ABS, SLOT, 1),
+            (ABS, TRACKING_ID, 6),
+            (ABS, X, 300),
+            (ABS, Y, 300),
+            (SYN, REPORT, 0),
+            (ABS, X, 310),
+            (ABS, SLOT, 0),
+            (ABS, X, 105),
+            (SYN, REPORT, 0),
+            (ABS, TRACKING_ID, -1),
+            (SYN, REPORT, 0),
+            (ABS, SLOT, 1),
+            (ABS, TRACKING_ID, -1),
+            (KEY, TOUCH, 0),
+            (SYN, REPORT, 0),
+        ]);
+
+        assert_eq!(
+            events,
+            vec![
+                TouchEvent::Pressed(100, 100),
+                TouchEvent::Moved(105, 100),
+                TouchEvent::Released(105, 100),
+            ]
+        );
+    }
+
+    #[test]
+    fn new_contact_in_same_frame_releases_first() {
+        let events = replay(&[
+            (ABS, TRACKING_ID, 1),
+            (ABS, X, 10),
+            (ABS, Y, 10),
+            (SYN, REPORT, 0),
+            (ABS, TRACKING_ID, 2),
+            (ABS, X, 50),
+            (ABS, Y, 60),
+            (SYN, REPORT, 0),
+        ]);
+
+        assert_eq!(
+            events,
+            vec![TouchEvent::Pressed(10, 10), TouchEvent::Released(10, 10), TouchEvent::Pressed(50, 60)]
+        );
+    }
+
+    #[test]
+    fn button_only_device() {
+        let events = replay(&[
+            (ABS, X, 40),
+            (ABS, Y, 50),
+            (KEY, TOUCH, 1),
+            (SYN, REPORT, 0),
+            (ABS, X, 45),
+            (SYN, REPORT, 0),
+            (KEY, TOUCH, 0),
+            (SYN, REPORT, 0),
+        ]);
+
+        assert_eq!(
+            events,
+            vec![TouchEvent::Pressed(40, 50), TouchEvent::Moved(45, 50), TouchEvent::Released(45, 50)]
+        );
+    }
+
+    #[test]
+    fn events_without_report_are_held_back() {
+        let events = replay(&[(ABS, TRACKING_ID, 1), (ABS, X, 10), (ABS, Y, 10), (KEY, TOUCH, 1)]);
+
+        assert!(events.is_empty());
+    }
+}
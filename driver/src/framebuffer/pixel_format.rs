@@ -0,0 +1,265 @@
+use std::str::FromStr;
+
+use linuxfb::PixelLayout;
+use slint::platform::software_renderer::{PhysicalRegion, PremultipliedRgbaColor, Rgb565Pixel, TargetPixel};
+use slint::Rgb8Pixel;
+
+use crate::error::DriverError;
+
+/// Framebuffer pixel formats, named like DRM fourcc codes: channels are listed from the
+/// most to the least significant bits of a little endian pixel, so the order in memory is reversed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PixelFormat {
+    Rgb565,
+    Bgr565,
+    Rgb888,
+    Bgr888,
+    Xrgb8888,
+    Argb8888,
+    Abgr8888,
+}
+
+impl PixelFormat {
+    pub fn bytes_per_pixel(&self) -> u32 {
+        match self {
+            PixelFormat::Rgb565 | PixelFormat::Bgr565 => 2,
+            PixelFormat::Rgb888 | PixelFormat::Bgr888 => 3,
+            PixelFormat::Xrgb8888 | PixelFormat::Argb8888 | PixelFormat::Abgr8888 => 4,
+        }
+    }
+
+    /// Reads the pixel at the start of `bytes` back, e.g. to mirror the screen.
+    pub fn read_pixel(&self, bytes: &[u8]) -> Rgb8Pixel {
+        let rgb565 = || u16::from_ne_bytes([bytes[0], bytes[1]]);
+
+        match self {
+            PixelFormat::Rgb565 => {
+                let value = rgb565();
+                Rgb8Pixel { r: ((value & 0xf800) >> 8) as u8, g: ((value & 0x07e0) >> 3) as u8, b: ((value & 0x001f) << 3) as u8 }
+            }
+            PixelFormat::Bgr565 => {
+                let pixel = Bgr565Pixel(rgb565());
+                Rgb8Pixel { r: pixel.red(), g: pixel.green(), b: pixel.blue() }
+            }
+            PixelFormat::Rgb888 | PixelFormat::Xrgb8888 | PixelFormat::Argb8888 => Rgb8Pixel { r: bytes[2], g: bytes[1], b: bytes[0] },
+            PixelFormat::Bgr888 | PixelFormat::Abgr8888 => Rgb8Pixel { r: bytes[0], g: bytes[1], b: bytes[2] },
+        }
+    }
+
+    /// Derives the format from the bitfields in the framebuffer's var screeninfo.
+    pub fn from_layout(bytes_per_pixel: u32, layout: &PixelLayout) -> Result<PixelFormat, DriverError> {
+        let red = (layout.red.offset, layout.red.length);
+        let green = (layout.green.offset, layout.green.length);
+        let blue = (layout.blue.offset, layout.blue.length);
+        let alpha = layout.alpha.length;
+
+        let format = match (bytes_per_pixel, red, green, blue, alpha) {
+            (2, (11, 5), (5, 6), (0, 5), _) => Some(PixelFormat::Rgb565),
+            (2, (0, 5), (5, 6), (11, 5), _) => Some(PixelFormat::Bgr565),
+            (3, (16, 8), (8, 8), (0, 8), _) => Some(PixelFormat::Rgb888),
+            (3, (0, 8), (8, 8), (16, 8), _) => Some(PixelFormat::Bgr888),
+            (4, (16, 8), (8, 8), (0, 8), 0) => Some(PixelFormat::Xrgb8888),
+            (4, (16, 8), (8, 8), (0, 8), _) => Some(PixelFormat::Argb8888),
+            // Without an alpha channel the X byte is simply ignored
+            (4, (0, 8), (8, 8), (16, 8), _) => Some(PixelFormat::Abgr8888),
+            _ => None,
+        };
+
+        format.ok_or_else(|| {
+            DriverError::UnsupportedPixelLayout(format!(
+                "{} bytes per pixel, red {:?}, green {:?}, blue {:?}, alpha length {}. Set pixel_format to override",
+                bytes_per_pixel, red, green, blue, alpha
+            ))
+        })
+    }
+}
+
+impl FromStr for PixelFormat {
+    type Err = DriverError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "rgb565" => Ok(PixelFormat::Rgb565),
+            "bgr565" => Ok(PixelFormat::Bgr565),
+            "rgb888" => Ok(PixelFormat::Rgb888),
+            "bgr888" => Ok(PixelFormat::Bgr888),
+            "xrgb8888" => Ok(PixelFormat::Xrgb8888),
+            "argb8888" => Ok(PixelFormat::Argb8888),
+            "abgr8888" => Ok(PixelFormat::Abgr8888),
+            _ => Err(DriverError::UnknownPixelFormat(s.to_string())),
+        }
+    }
+}
+
+/// Renders into `frame`, reinterpreting the bytes as the pixel type of the format.
+/// Returns the region that changed, in native (rotated) framebuffer coordinates.
+pub fn render_frame(
+    renderer: &slint::platform::software_renderer::SoftwareRenderer,
+    frame: &mut [u8],
+    stride: usize,
+    pixel_format: PixelFormat,
+) -> PhysicalRegion {
+    fn render<T: TargetPixel>(
+        renderer: &slint::platform::software_renderer::SoftwareRenderer,
+        frame: &mut [u8],
+        stride: usize,
+    ) -> PhysicalRegion {
+        let (_, pixels, _) = unsafe { frame.align_to_mut::<T>() };
+        renderer.render(pixels, stride)
+    }
+
+    match pixel_format {
+        PixelFormat::Rgb565 => render::<Rgb565Pixel>(renderer, frame, stride),
+        PixelFormat::Bgr565 => render::<Bgr565Pixel>(renderer, frame, stride),
+        PixelFormat::Rgb888 => render::<Bgr8Pixel>(renderer, frame, stride),
+        PixelFormat::Bgr888 => render::<Rgb8Pixel>(renderer, frame, stride),
+        PixelFormat::Xrgb8888 | PixelFormat::Argb8888 => render::<PremultipliedAbgrColor>(renderer, frame, stride),
+        PixelFormat::Abgr8888 => render::<PremultipliedRgbaColor>(renderer, frame, stride),
+    }
+}
+
+/// Blue, green, red, alpha in memory, i.e. ARGB8888.
+#[derive(Clone, Copy)]
+#[repr(C)]
+pub struct PremultipliedAbgrColor {
+    pub blue: u8,
+    pub green: u8,
+    pub red: u8,
+    pub alpha: u8,
+}
+
+impl TargetPixel for PremultipliedAbgrColor {
+    fn blend(&mut self, color: PremultipliedRgbaColor) {
+        let a = (u8::MAX - color.alpha) as u16;
+        self.red = (self.red as u16 * a / 255) as u8 + color.red;
+        self.green = (self.green as u16 * a / 255) as u8 + color.green;
+        self.blue = (self.blue as u16 * a / 255) as u8 + color.blue;
+        self.alpha = (self.alpha as u16 + color.alpha as u16
+            - (self.alpha as u16 * color.alpha as u16) / 255) as u8;
+    }
+
+    fn from_rgb(r: u8, g: u8, b: u8) -> Self {
+        Self {
+            red: r,
+            green: g,
+            blue: b,
+            alpha: 255,
+        }
+    }
+
+    fn background() -> Self {
+        Self {
+            red: 0,
+            green: 0,
+            blue: 0,
+            alpha: 0,
+        }
+    }
+}
+
+/// Blue, green, red in memory, i.e. RGB888.
+#[derive(Clone, Copy)]
+#[repr(C)]
+pub struct Bgr8Pixel {
+    pub blue: u8,
+    pub green: u8,
+    pub red: u8,
+}
+
+impl TargetPixel for Bgr8Pixel {
+    fn blend(&mut self, color: PremultipliedRgbaColor) {
+        let a = (u8::MAX - color.alpha) as u16;
+        self.red = (self.red as u16 * a / 255) as u8 + color.red;
+        self.green = (self.green as u16 * a / 255) as u8 + color.green;
+        self.blue = (self.blue as u16 * a / 255) as u8 + color.blue;
+    }
+
+    fn from_rgb(r: u8, g: u8, b: u8) -> Self {
+        Self {
+            red: r,
+            green: g,
+            blue: b,
+        }
+    }
+}
+
+/// Like `Rgb565Pixel`, but with blue in the most significant bits.
+#[derive(Clone, Copy)]
+#[repr(transparent)]
+pub struct Bgr565Pixel(pub u16);
+
+impl Bgr565Pixel {
+    fn red(&self) -> u8 {
+        ((self.0 & 0x001f) << 3) as u8
+    }
+
+    fn green(&self) -> u8 {
+        ((self.0 & 0x07e0) >> 3) as u8
+    }
+
+    fn blue(&self) -> u8 {
+        ((self.0 & 0xf800) >> 8) as u8
+    }
+}
+
+impl TargetPixel for Bgr565Pixel {
+    fn blend(&mut self, color: PremultipliedRgbaColor) {
+        let a = (u8::MAX - color.alpha) as u16;
+        let red = (self.red() as u16 * a / 255) as u8 + color.red;
+        let green = (self.green() as u16 * a / 255) as u8 + color.green;
+        let blue = (self.blue() as u16 * a / 255) as u8 + color.blue;
+        *self = Self::from_rgb(red, green, blue);
+    }
+
+    fn from_rgb(r: u8, g: u8, b: u8) -> Self {
+        Self(((b as u16 & 0xf8) << 8) | ((g as u16 & 0xfc) << 3) | (r as u16 >> 3))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use linuxfb::PixelLayoutChannel;
+
+    use super::*;
+
+    /// `(offset, length)` per channel, red, green, blue and alpha.
+    fn layout(channels: [(u32, u32); 4]) -> PixelLayout {
+        let [red, green, blue, alpha] = channels.map(|(offset, length)| PixelLayoutChannel { offset, length, msb_right: false });
+        PixelLayout { red, green, blue, alpha }
+    }
+
+    #[test]
+    fn detects_16_bit_layouts() {
+        let rgb565 = layout([(11, 5), (5, 6), (0, 5), (0, 0)]);
+        let bgr565 = layout([(0, 5), (5, 6), (11, 5), (0, 0)]);
+
+        assert_eq!(PixelFormat::from_layout(2, &rgb565).unwrap(), PixelFormat::Rgb565);
+        assert_eq!(PixelFormat::from_layout(2, &bgr565).unwrap(), PixelFormat::Bgr565);
+    }
+
+    #[test]
+    fn detects_32_bit_layouts_by_their_alpha() {
+        let xrgb8888 = layout([(16, 8), (8, 8), (0, 8), (0, 0)]);
+        let argb8888 = layout([(16, 8), (8, 8), (0, 8), (24, 8)]);
+
+        assert_eq!(PixelFormat::from_layout(4, &xrgb8888).unwrap(), PixelFormat::Xrgb8888);
+        assert_eq!(PixelFormat::from_layout(4, &argb8888).unwrap(), PixelFormat::Argb8888);
+    }
+
+    #[test]
+    fn rejects_unsupported_layouts() {
+        let rgb555 = layout([(10, 5), (5, 5), (0, 5), (15, 1)]);
+        // A 565 layout on a 32 bit framebuffer
+        let mismatched = layout([(11, 5), (5, 6), (0, 5), (0, 0)]);
+
+        for (bytes_per_pixel, layout) in [(2, rgb555), (4, mismatched)] {
+            assert!(matches!(PixelFormat::from_layout(bytes_per_pixel, &layout), Err(DriverError::UnsupportedPixelLayout(_))));
+        }
+    }
+
+    #[test]
+    fn parses_names_case_insensitively() {
+        assert_eq!("ARGB8888".parse::<PixelFormat>().unwrap(), PixelFormat::Argb8888);
+        assert!(matches!("rgb555".parse::<PixelFormat>(), Err(DriverError::UnknownPixelFormat(_))));
+    }
+}
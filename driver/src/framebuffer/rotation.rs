@@ -0,0 +1,87 @@
+use slint::platform::software_renderer::RenderingRotation;
+
+/// Clockwise rotation of the panel content.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Rotation {
+    #[default]
+    Rotate0,
+    Rotate90,
+    Rotate180,
+    Rotate270,
+}
+
+impl Rotation {
+    pub fn from_degrees(degrees: u32) -> Option<Rotation> {
+        match degrees {
+            0 => Some(Rotation::Rotate0),
+            90 => Some(Rotation::Rotate90),
+            180 => Some(Rotation::Rotate180),
+            270 => Some(Rotation::Rotate270),
+            _ => None,
+        }
+    }
+
+    pub fn rendering_rotation(&self) -> RenderingRotation {
+        match self {
+            Rotation::Rotate0 => RenderingRotation::NoRotation,
+            Rotation::Rotate90 => RenderingRotation::Rotate90,
+            Rotation::Rotate180 => RenderingRotation::Rotate180,
+            Rotation::Rotate270 => RenderingRotation::Rotate270,
+        }
+    }
+
+    /// The window size Slint sees for a panel of the given native size.
+    pub fn logical_size(&self, width: u32, height: u32) -> (u32, u32) {
+        match self {
+            Rotation::Rotate0 | Rotation::Rotate180 => (width, height),
+            Rotation::Rotate90 | Rotation::Rotate270 => (height, width),
+        }
+    }
+
+    /// Maps a point on the native panel of `width` x `height` to window coordinates.
+    pub fn to_logical(&self, x: f32, y: f32, width: f32, height: f32) -> (f32, f32) {
+        match self {
+            Rotation::Rotate0 => (x, y),
+            Rotation::Rotate90 => (y, width - 1.0 - x),
+            Rotation::Rotate180 => (width - 1.0 - x, height - 1.0 - y),
+            Rotation::Rotate270 => (height - 1.0 - y, x),
+        }
+    }
+
+    /// Maps a non-empty `rect_width` x `rect_height` rectangle at `x`, `y` on the
+    /// native panel to window coordinates, returned as x, y, width, height.
+    pub fn rect_to_logical(&self, x: u32, y: u32, rect_width: u32, rect_height: u32, width: u32, height: u32) -> (u32, u32, u32, u32) {
+        let (width, height) = (width as f32, height as f32);
+        let (x1, y1) = self.to_logical(x as f32, y as f32, width, height);
+        let (x2, y2) = self.to_logical((x + rect_width - 1) as f32, (y + rect_height - 1) as f32, width, height);
+
+        (x1.min(x2) as u32, y1.min(y2) as u32, (x1 - x2).abs() as u32 + 1, (y1 - y2).abs() as u32 + 1)
+    }
+
+    /// Inverse of `to_logical`.
+    pub fn to_native(&self, x: f32, y: f32, width: f32, height: f32) -> (f32, f32) {
+        match self {
+            Rotation::Rotate0 => (x, y),
+            Rotation::Rotate90 => (width - 1.0 - y, x),
+            Rotation::Rotate180 => (width - 1.0 - x, height - 1.0 - y),
+            Rotation::Rotate270 => (y, height - 1.0 - x),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rects_map_to_window_coordinates() {
+        // A 4x2 rect in the top left corner of an 800x480 panel
+        let rect = (0, 0, 4, 2);
+        let map = |rotation: Rotation| rotation.rect_to_logical(rect.0, rect.1, rect.2, rect.3, 800, 480);
+
+        assert_eq!(map(Rotation::Rotate0), (0, 0, 4, 2));
+        assert_eq!(map(Rotation::Rotate90), (0, 796, 2, 4));
+        assert_eq!(map(Rotation::Rotate180), (796, 478, 4, 2));
+        assert_eq!(map(Rotation::Rotate270), (478, 0, 2, 4));
+    }
+}
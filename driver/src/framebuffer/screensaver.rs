@@ -0,0 +1,249 @@
+use std::{
+    fs,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use linuxfb::{BlankingLevel, Framebuffer};
+use slint::platform::WindowEvent;
+
+use crate::framebuffer::event_wait::Wakeup;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScreenState {
+    On,
+    Dimmed,
+    Off,
+}
+
+pub struct ScreensaverOptions {
+    pub dim_timeout: Option<Duration>,
+    pub off_timeout: Option<Duration>,
+    /// Brightness while dimmed, in percent of the maximum brightness.
+    pub dim_brightness: u32,
+    /// Backlight device, e.g. `/sys/class/backlight/backlight`. Picks the first one in `/sys/class/backlight` if unset.
+    pub backlight_path: Option<String>,
+}
+
+struct Backlight {
+    brightness_path: PathBuf,
+    max_brightness: u32,
+    on_brightness: u32,
+}
+
+impl Backlight {
+    fn open(path: Option<&str>) -> Option<Backlight> {
+        let path = match path {
+            Some(path) => PathBuf::from(path),
+            None => fs::read_dir("/sys/class/backlight")
+                .ok()?
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.path())
+                .next()?,
+        };
+
+        let read_value = |name: &str| -> Option<u32> {
+            fs::read_to_string(path.join(name)).ok()?.trim().parse().ok()
+        };
+
+        let max_brightness = read_value("max_brightness")?;
+        // Keep whatever brightness was set before we started
+        let on_brightness = read_value("brightness")
+            .filter(|brightness| *brightness > 0)
+            .unwrap_or(max_brightness);
+
+        println!("Backlight device: {:?}", path);
+
+        Some(Backlight {
+            brightness_path: path.join("brightness"),
+            max_brightness,
+            on_brightness,
+        })
+    }
+
+    fn set_brightness(&self, brightness: u32) {
+        if let Err(e) = fs::write(&self.brightness_path, brightness.to_string()) {
+            eprintln!("Failed to set backlight brightness: {}", e);
+        }
+    }
+}
+
+pub struct Screensaver {
+    options: ScreensaverOptions,
+    backlight: Option<Backlight>,
+    framebuffer: Option<Framebuffer>,
+    state: ScreenState,
+    last_activity: Instant,
+    inhibited: bool,
+    swallowing_touch: bool,
+    /// Interrupts the event loop, so it picks up deadlines changed from other threads.
+    wakeup: Option<Arc<Wakeup>>,
+}
+
+pub type ScreensaverHandle = Arc<Mutex<Screensaver>>;
+
+impl Screensaver {
+    pub fn new(fb_path: &str, options: ScreensaverOptions) -> ScreensaverHandle {
+        let backlight = Backlight::open(options.backlight_path.as_deref());
+        if backlight.is_none() {
+            println!("No backlight device found, the screensaver will only blank the framebuffer");
+        }
+
+        // A separate handle, as the rendering one is owned by the (double) buffer
+        let framebuffer = match Framebuffer::new(fb_path.to_string()) {
+            Ok(framebuffer) => Some(framebuffer),
+            Err(e) => {
+                eprintln!("Failed to open framebuffer for blanking: {:?}", e);
+                None
+            }
+        };
+
+        Arc::new(Mutex::new(Screensaver {
+            options,
+            backlight,
+            framebuffer,
+            state: ScreenState::On,
+            last_activity: Instant::now(),
+            inhibited: false,
+            swallowing_touch: false,
+            wakeup: None,
+        }))
+    }
+
+    pub fn state(&self) -> ScreenState {
+        self.state
+    }
+
+    pub(crate) fn set_wakeup(&mut self, wakeup: Arc<Wakeup>) {
+        self.wakeup = Some(wakeup);
+    }
+
+    /// Turns the screen back on and restarts the inactivity timeouts.
+    pub fn wake(&mut self) {
+        self.last_activity = Instant::now();
+        self.set_state(ScreenState::On);
+        self.wake_event_loop();
+    }
+
+    /// While inhibited the screen never dims or turns off.
+    pub fn set_inhibited(&mut self, inhibited: bool) {
+        let lifted = self.inhibited && !inhibited;
+        self.inhibited = inhibited;
+
+        if inhibited {
+            self.wake();
+        } else if lifted {
+            // The timeouts count from now, the loop has no deadline to wake up for yet
+            self.last_activity = Instant::now();
+            self.wake_event_loop();
+        }
+    }
+
+    fn wake_event_loop(&self) {
+        if let Some(wakeup) = &self.wakeup {
+            wakeup.wake();
+        }
+    }
+
+    /// Registers input activity and drops the events belonging to the touch
+    /// that woke the screen up.
+    pub fn on_input(&mut self, events: &mut Vec<WindowEvent>) {
+        if events.is_empty() {
+            return;
+        }
+
+        self.last_activity = Instant::now();
+
+        if self.state != ScreenState::On {
+            self.set_state(ScreenState::On);
+
+            // Keys and mouse moves only wake the screen, a press is swallowed up to its release
+            if !events.iter().any(|event| matches!(event, WindowEvent::PointerPressed { .. })) {
+                events.clear();
+                return;
+            }
+
+            self.swallowing_touch = true;
+        }
+
+        if self.swallowing_touch {
+            match events.iter().position(|event| matches!(event, WindowEvent::PointerReleased { .. })) {
+                Some(release) => {
+                    self.swallowing_touch = false;
+                    events.drain(..=release);
+                }
+                None => events.clear(),
+            }
+        }
+    }
+
+    /// When `update` has to run next to dim or turn off the screen.
+    pub fn next_deadline(&self) -> Option<Instant> {
+        // Nothing to count down, lifting the inhibit wakes the loop
+        if self.inhibited {
+            return None;
+        }
+
+        let now = Instant::now();
+
+        [self.options.dim_timeout, self.options.off_timeout]
+            .into_iter()
+            .flatten()
+            .map(|timeout| self.last_activity + timeout)
+            .filter(|deadline| *deadline > now)
+            .min()
+    }
+
+    /// Dims or turns off the screen once the timeouts have passed.
+    pub fn update(&mut self) {
+        if self.inhibited {
+            self.last_activity = Instant::now();
+            return;
+        }
+
+        let idle = self.last_activity.elapsed();
+
+        let state = if self.options.off_timeout.is_some_and(|timeout| idle >= timeout) {
+            ScreenState::Off
+        } else if self.options.dim_timeout.is_some_and(|timeout| idle >= timeout) {
+            ScreenState::Dimmed
+        } else {
+            ScreenState::On
+        };
+
+        self.set_state(state);
+    }
+
+    fn set_state(&mut self, state: ScreenState) {
+        if self.state == state {
+            return;
+        }
+
+        if let Some(backlight) = &self.backlight {
+            let brightness = match state {
+                ScreenState::On => backlight.on_brightness,
+                ScreenState::Dimmed => (backlight.max_brightness * self.options.dim_brightness / 100).max(1),
+                ScreenState::Off => 0,
+            };
+
+            backlight.set_brightness(brightness);
+        }
+
+        if let Some(framebuffer) = &self.framebuffer {
+            let blanking_level = match state {
+                ScreenState::Off => BlankingLevel::Powerdown,
+                _ => BlankingLevel::Unblank,
+            };
+
+            // Only switch blanking when entering or leaving the off state
+            if state == ScreenState::Off || self.state == ScreenState::Off {
+                if let Err(e) = framebuffer.blank(blanking_level) {
+                    eprintln!("Failed to change framebuffer blanking: {:?}", e);
+                }
+            }
+        }
+
+        self.state = state;
+    }
+}
@@ -0,0 +1,210 @@
+use evdev::{AbsoluteAxisCode, EventSummary, KeyCode, SynchronizationCode};
+use slint::{
+    platform::{PointerEventButton, WindowEvent},
+    LogicalPosition,
+};
+
+/// Single-touch protocol state machine. Events are accumulated and applied per
+/// `SYN_REPORT` frame; `map` turns raw device coordinates into window
+/// coordinates, which the jitter filter works in.
+#[derive(Debug)]
+pub struct SingleTouchState {
+    /// Touch is down at or above this `ABS_PRESSURE`, `BTN_TOUCH` is used if unset.
+    pressure_threshold: Option<i32>,
+    /// Moves shorter than this in pixels are dropped while touching.
+    jitter_distance: f32,
+    raw: (i32, i32),
+    pressure: i32,
+    button: bool,
+    pressed: bool,
+    last_position: LogicalPosition,
+}
+
+impl SingleTouchState {
+    pub fn new(pressure_threshold: Option<i32>, jitter_distance: f32) -> Self {
+        Self {
+            pressure_threshold,
+            jitter_distance,
+            raw: (0, 0),
+            pressure: 0,
+            button: false,
+            pressed: false,
+            last_position: LogicalPosition::new(0.0, 0.0),
+        }
+    }
+
+    pub fn process(&mut self, event: EventSummary, map: impl Fn(i32, i32) -> LogicalPosition) -> Option<WindowEvent> {
+        match event {
+            EventSummary::AbsoluteAxis(_, AbsoluteAxisCode::ABS_X, value) => self.raw.0 = value,
+            EventSummary::AbsoluteAxis(_, AbsoluteAxisCode::ABS_Y, value) => self.raw.1 = value,
+            EventSummary::AbsoluteAxis(_, AbsoluteAxisCode::ABS_PRESSURE, value) => self.pressure = value,
+            EventSummary::Key(_, KeyCode::BTN_TOUCH, value) => self.button = value != 0,
+            EventSummary::Synchronization(_, SynchronizationCode::SYN_REPORT, _) => return self.end_frame(map),
+            _ => {}
+        }
+
+        None
+    }
+
+    fn end_frame(&mut self, map: impl Fn(i32, i32) -> LogicalPosition) -> Option<WindowEvent> {
+        let down = match self.pressure_threshold {
+            Some(threshold) => self.pressure >= threshold,
+            None => self.button,
+        };
+
+        match (self.pressed, down) {
+            (false, true) => {
+                let position = map(self.raw.0, self.raw.1);
+                self.pressed = true;
+                self.last_position = position;
+
+                Some(WindowEvent::PointerPressed { position, button: PointerEventButton::Left })
+            }
+            (true, true) => {
+                let position = map(self.raw.0, self.raw.1);
+                let distance = (position.x - self.last_position.x).hypot(position.y - self.last_position.y);
+
+                if distance < self.jitter_distance {
+                    return None;
+                }
+
+                self.last_position = position;
+                Some(WindowEvent::PointerMoved { position })
+            }
+            // Coordinates in the lift-off frame are unreliable on resistive panels
+            (true, false) => {
+                self.pressed = false;
+
+                Some(WindowEvent::PointerReleased { position: self.last_position, button: PointerEventButton::Left })
+            }
+            (false, false) => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use evdev::{EventType, InputEvent};
+
+    use super::*;
+
+    const SYN: u16 = EventType::SYNCHRONIZATION.0;
+    const KEY: u16 = EventType::KEY.0;
+    const ABS: u16 = EventType::ABSOLUTE.0;
+
+    const X: u16 = AbsoluteAxisCode::ABS_X.0;
+    const Y: u16 = AbsoluteAxisCode::ABS_Y.0;
+    const PRESSURE: u16 = AbsoluteAxisCode::ABS_PRESSURE.0;
+    const TOUCH: u16 = KeyCode::BTN_TOUCH.0;
+    const REPORT: u16 = SynchronizationCode::SYN_REPORT.0;
+
+    /// Feeds `(type, code, value)` triples as printed by evtest, with raw
+    /// coordinates used as window coordinates.
+    fn replay(mut state: SingleTouchState, recording: &[(u16, u16, i32)]) -> Vec<WindowEvent> {
+        recording
+            .iter()
+            .filter_map(|(type_, code, value)| {
+                state.process(InputEvent::new(*type_, *code, *value).destructure(), |x, y| LogicalPosition::new(x as f32, y as f32))
+            })
+            .collect()
+    }
+
+    fn pressed(x: f32, y: f32) -> WindowEvent {
+        WindowEvent::PointerPressed { position: LogicalPosition::new(x, y), button: PointerEventButton::Left }
+    }
+
+    fn moved(x: f32, y: f32) -> WindowEvent {
+        WindowEvent::PointerMoved { position: LogicalPosition::new(x, y) }
+    }
+
+    fn released(x: f32, y: f32) -> WindowEvent {
+        WindowEvent::PointerReleased { position: LogicalPosition::new(x, y), button: PointerEventButton::Left }
+    }
+
+    #[test]
+    fn tap_with_btn_touch() {
+        let events = replay(SingleTouchState::new(None, 0.0), &[
+            (ABS, X, 100),
+            (ABS, Y, 200),
+            (KEY, TOUCH, 1),
+            (SYN, REPORT, 0),
+            (KEY, TOUCH, 0),
+            (SYN, REPORT, 0),
+        ]);
+
+        assert_eq!(events, vec![pressed(100.0, 200.0), released(100.0, 200.0)]);
+    }
+
+    #[test]
+    fn pressure_below_the_threshold_is_not_a_touch() {
+        let events = replay(SingleTouchState::new(Some(50), 0.0), &[
+            (ABS, X, 100),
+            (ABS, Y, 200),
+            (ABS, PRESSURE, 20),
+            (KEY, TOUCH, 1),
+            (SYN, REPORT, 0),
+            (ABS, X, 110),
+            (ABS, PRESSURE, 49),
+            (SYN, REPORT, 0),
+            (ABS, PRESSURE, 50),
+            (SYN, REPORT, 0),
+            (ABS, PRESSURE, 10),
+            (SYN, REPORT, 0),
+        ]);
+
+        assert_eq!(events, vec![pressed(110.0, 200.0), released(110.0, 200.0)]);
+    }
+
+    #[test]
+    fn small_moves_are_dropped_as_jitter() {
+        let events = replay(SingleTouchState::new(None, 5.0), &[
+            (ABS, X, 100),
+            (ABS, Y, 100),
+            (KEY, TOUCH, 1),
+            (SYN, REPORT, 0),
+            (ABS, X, 102),
+            (ABS, Y, 103),
+            (SYN, REPORT, 0),
+            (ABS, X, 98),
+            (SYN, REPORT, 0),
+            // Measured from the last reported position, so slow drags still get through
+            (ABS, X, 104),
+            (ABS, Y, 104),
+            (SYN, REPORT, 0),
+            (ABS, X, 120),
+            (SYN, REPORT, 0),
+        ]);
+
+        assert_eq!(events, vec![pressed(100.0, 100.0), moved(104.0, 104.0), moved(120.0, 104.0)]);
+    }
+
+    #[test]
+    fn release_keeps_the_last_reported_position() {
+        let events = replay(SingleTouchState::new(Some(50), 5.0), &[
+            (ABS, X, 100),
+            (ABS, Y, 100),
+            (ABS, PRESSURE, 80),
+            (SYN, REPORT, 0),
+            (ABS, X, 102),
+            (SYN, REPORT, 0),
+            // Resistive panels report garbage coordinates as the pressure drops
+            (ABS, X, 4000),
+            (ABS, Y, 0),
+            (ABS, PRESSURE, 0),
+            (SYN, REPORT, 0),
+        ]);
+
+        assert_eq!(events, vec![pressed(100.0, 100.0), released(100.0, 100.0)]);
+    }
+
+    #[test]
+    fn nothing_is_reported_before_a_frame_ends() {
+        let events = replay(SingleTouchState::new(None, 0.0), &[
+            (ABS, X, 100),
+            (ABS, Y, 100),
+            (KEY, TOUCH, 1),
+        ]);
+
+        assert!(events.is_empty());
+    }
+}
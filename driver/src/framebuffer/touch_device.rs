@@ -0,0 +1,84 @@
+use std::str::FromStr;
+
+use evdev::{AbsoluteAxisCode, Device};
+
+use crate::{
+    error::DriverError,
+    framebuffer::{
+        evdev_mt_touch_platform::EvdevMtTouchPlatform,
+        evdev_st_touch_platform::EvdevStTouchPlatform,
+        input_device::InputPlatform,
+        rotation::Rotation,
+        touch_mapping::{AxisRange, TouchMapping, TouchMappingOptions},
+    },
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TouchKind {
+    /// Picked from the axes the device reports.
+    #[default]
+    Auto,
+    Multitouch,
+    SingleTouch,
+}
+
+impl FromStr for TouchKind {
+    type Err = DriverError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "auto" => Ok(TouchKind::Auto),
+            "multitouch" => Ok(TouchKind::Multitouch),
+            "singletouch" | "single_touch" => Ok(TouchKind::SingleTouch),
+            _ => Err(DriverError::UnsupportedInputDevice(format!("Unknown touch type {}", s))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct TouchOptions {
+    pub kind: TouchKind,
+    /// Single-touch only. Defaults to a tenth of the pressure range if the device reports pressure.
+    pub pressure_threshold: Option<i32>,
+    /// Single-touch only, in pixels.
+    pub jitter_distance: f32,
+    pub mapping: TouchMappingOptions,
+}
+
+pub(crate) fn open_touch_platform(device: Device, options: TouchOptions, rotation: Rotation, width: u32, height: u32) -> Result<Box<dyn InputPlatform>, DriverError> {
+    let has_axis = |axis| device.supported_absolute_axes().is_some_and(|axes| axes.contains(axis));
+
+    let kind = match options.kind {
+        TouchKind::Auto if has_axis(AbsoluteAxisCode::ABS_MT_POSITION_X) => TouchKind::Multitouch,
+        TouchKind::Auto if has_axis(AbsoluteAxisCode::ABS_X) => TouchKind::SingleTouch,
+        TouchKind::Auto => {
+            return Err(DriverError::UnsupportedInputDevice(format!(
+                "{:?} reports neither ABS_MT_POSITION_X nor ABS_X",
+                device.name()
+            )));
+        }
+        kind => kind,
+    };
+
+    println!("Touch type: {:?}", kind);
+
+    match kind {
+        TouchKind::SingleTouch => {
+            let pressure_threshold = match options.pressure_threshold {
+                Some(threshold) => Some(threshold),
+                None if has_axis(AbsoluteAxisCode::ABS_PRESSURE) => AxisRange::from_device(&device, AbsoluteAxisCode::ABS_PRESSURE)
+                    .map(|range| range.min + ((range.max - range.min) / 10).max(1)),
+                None => None,
+            };
+
+            println!("Pressure threshold: {:?}", pressure_threshold);
+
+            let mapping = TouchMapping::from_device(&device, AbsoluteAxisCode::ABS_X, AbsoluteAxisCode::ABS_Y, options.mapping, rotation, width, height);
+            Ok(Box::new(EvdevStTouchPlatform::new(device, mapping, pressure_threshold, options.jitter_distance)))
+        }
+        _ => {
+            let mapping = TouchMapping::from_device(&device, AbsoluteAxisCode::ABS_MT_POSITION_X, AbsoluteAxisCode::ABS_MT_POSITION_Y, options.mapping, rotation, width, height);
+            Ok(Box::new(EvdevMtTouchPlatform::new(device, mapping)))
+        }
+    }
+}
@@ -0,0 +1,231 @@
+use std::sync::{Arc, Mutex};
+
+use evdev::{AbsoluteAxisCode, Device};
+use slint::LogicalPosition;
+
+use crate::framebuffer::rotation::Rotation;
+
+/// Affine transform `[a, b, c, d, e, f]` applied in window coordinates:
+/// `x' = a * x + b * y + c`, `y' = d * x + e * y + f`.
+pub type CalibrationMatrix = [f32; 6];
+
+/// A `(touched, target)` pair in window coordinates.
+pub type CalibrationSample = ((f32, f32), (f32, f32));
+
+/// Shared so the app can swap the matrix at runtime, e.g. while calibrating.
+pub type TouchCalibrationHandle = Arc<Mutex<Option<CalibrationMatrix>>>;
+
+#[derive(Debug, Clone, Default)]
+pub struct TouchMappingOptions {
+    pub swap_xy: bool,
+    pub invert_x: bool,
+    pub invert_y: bool,
+    pub calibration: TouchCalibrationHandle,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct AxisRange {
+    pub min: i32,
+    pub max: i32,
+}
+
+impl AxisRange {
+    pub fn from_device(device: &Device, axis: AbsoluteAxisCode) -> Option<AxisRange> {
+        match device.get_absinfo() {
+            Ok(mut absinfo) => absinfo
+                .find(|(code, _)| *code == axis)
+                .map(|(_, info)| AxisRange { min: info.minimum(), max: info.maximum() }),
+            Err(e) => {
+                eprintln!("Warn on touch_mapping: Failed to read the range of {:?}: {}", axis, e);
+                None
+            }
+        }
+    }
+
+    /// Maps a raw value onto 0.0 - 1.0.
+    fn normalise(&self, value: i32) -> f32 {
+        if self.max <= self.min {
+            return 0.0;
+        }
+
+        (value - self.min) as f32 / (self.max - self.min) as f32
+    }
+}
+
+/// Turns raw touch coordinates into window coordinates.
+pub struct TouchMapping {
+    x_range: AxisRange,
+    y_range: AxisRange,
+    options: TouchMappingOptions,
+    rotation: Rotation,
+    width: f32,
+    height: f32,
+}
+
+impl TouchMapping {
+    /// `width` and `height` are the native, unrotated size of the panel.
+    pub fn new(x_range: AxisRange, y_range: AxisRange, options: TouchMappingOptions, rotation: Rotation, width: u32, height: u32) -> Self {
+        Self {
+            x_range,
+            y_range,
+            options,
+            rotation,
+            width: width as f32,
+            height: height as f32,
+        }
+    }
+
+    /// Reads the axis ranges the device reports, falling back to the panel size.
+    pub fn from_device(device: &Device, x_axis: AbsoluteAxisCode, y_axis: AbsoluteAxisCode, options: TouchMappingOptions, rotation: Rotation, width: u32, height: u32) -> Self {
+        let x_range = AxisRange::from_device(device, x_axis).unwrap_or(AxisRange { min: 0, max: width as i32 - 1 });
+        let y_range = AxisRange::from_device(device, y_axis).unwrap_or(AxisRange { min: 0, max: height as i32 - 1 });
+
+        println!("Touch axis range: x {:?}, y {:?}", x_range, y_range);
+
+        Self::new(x_range, y_range, options, rotation, width, height)
+    }
+
+    pub fn map(&self, raw_x: i32, raw_y: i32) -> LogicalPosition {
+        let (mut x, mut y) = (self.x_range.normalise(raw_x), self.y_range.normalise(raw_y));
+
+        if self.options.swap_xy {
+            std::mem::swap(&mut x, &mut y);
+        }
+
+        if self.options.invert_x {
+            x = 1.0 - x;
+        }
+
+        if self.options.invert_y {
+            y = 1.0 - y;
+        }
+
+        let (x, y) = self.rotation.to_logical(x * (self.width - 1.0), y * (self.height - 1.0), self.width, self.height);
+
+        let (x, y) = match *self.options.calibration.lock().unwrap() {
+            Some([a, b, c, d, e, f]) => (a * x + b * y + c, d * x + e * y + f),
+            None => (x, y),
+        };
+
+        LogicalPosition::new(x, y)
+    }
+}
+
+/// Least squares fit of a calibration matrix. Needs at least three points
+/// that are not on a line.
+pub fn compute_calibration_matrix(samples: &[CalibrationSample]) -> Option<CalibrationMatrix> {
+    if samples.len() < 3 {
+        return None;
+    }
+
+    // Normal equations, the same left hand side is shared by both output axes.
+    let mut m = [[0.0f64; 3]; 3];
+    let mut rhs_x = [0.0f64; 3];
+    let mut rhs_y = [0.0f64; 3];
+
+    for ((touch_x, touch_y), (target_x, target_y)) in samples {
+        let row = [*touch_x as f64, *touch_y as f64, 1.0];
+
+        for (i, m_row) in m.iter_mut().enumerate() {
+            for (j, value) in m_row.iter_mut().enumerate() {
+                *value += row[i] * row[j];
+            }
+            rhs_x[i] += row[i] * *target_x as f64;
+            rhs_y[i] += row[i] * *target_y as f64;
+        }
+    }
+
+    let [a, b, c] = solve_3x3(&m, &rhs_x)?;
+    let [d, e, f] = solve_3x3(&m, &rhs_y)?;
+
+    Some([a as f32, b as f32, c as f32, d as f32, e as f32, f as f32])
+}
+
+fn determinant(m: &[[f64; 3]; 3]) -> f64 {
+    m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+        - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+        + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+}
+
+// Cramer's rule
+fn solve_3x3(m: &[[f64; 3]; 3], rhs: &[f64; 3]) -> Option<[f64; 3]> {
+    let det = determinant(m);
+
+    // Relative to the diagonal, the sums grow with the screen size
+    if det.abs() <= 1e-9 * (m[0][0] * m[1][1] * m[2][2]).abs() {
+        return None;
+    }
+
+    let mut result = [0.0; 3];
+
+    for (column, value) in result.iter_mut().enumerate() {
+        let mut replaced = *m;
+        for (replaced_row, rhs_value) in replaced.iter_mut().zip(rhs) {
+            replaced_row[column] = *rhs_value;
+        }
+        *value = determinant(&replaced) / det;
+    }
+
+    Some(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CORNERS: [(f32, f32); 4] = [(20.0, 20.0), (460.0, 20.0), (460.0, 252.0), (20.0, 252.0)];
+
+    fn assert_matrix(actual: Option<CalibrationMatrix>, expected: CalibrationMatrix) {
+        let actual = actual.expect("no matrix");
+
+        for (actual, expected) in actual.iter().zip(expected) {
+            assert!((actual - expected).abs() < 1e-3, "{:?} != {:?}", actual, expected);
+        }
+    }
+
+    fn samples(transform: impl Fn(f32, f32) -> (f32, f32)) -> Vec<CalibrationSample> {
+        CORNERS.iter().map(|&(x, y)| (transform(x, y), (x, y))).collect()
+    }
+
+    #[test]
+    fn accurate_touches_give_the_identity() {
+        assert_matrix(compute_calibration_matrix(&samples(|x, y| (x, y))), [1.0, 0.0, 0.0, 0.0, 1.0, 0.0]);
+    }
+
+    #[test]
+    fn undoes_scale_and_offset() {
+        // The panel reports half the distance, shifted by 10 and 5 pixels
+        let matrix = compute_calibration_matrix(&samples(|x, y| (x * 0.5 + 10.0, y * 0.5 + 5.0)));
+
+        assert_matrix(matrix, [2.0, 0.0, -20.0, 0.0, 2.0, -10.0]);
+    }
+
+    #[test]
+    fn undoes_rotation() {
+        // Touches come in rotated by 90 degrees around the origin
+        let matrix = compute_calibration_matrix(&samples(|x, y| (-y, x)));
+
+        assert_matrix(matrix, [0.0, 1.0, 0.0, -1.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn fits_noisy_samples() {
+        let mut samples = samples(|x, y| (x, y));
+        samples[0].0 .0 += 1.0;
+        samples[2].0 .1 -= 1.0;
+
+        let [a, _, c, _, e, f] = compute_calibration_matrix(&samples).unwrap();
+
+        assert!((a - 1.0).abs() < 0.01 && (e - 1.0).abs() < 0.01);
+        assert!(c.abs() < 2.0 && f.abs() < 2.0);
+    }
+
+    #[test]
+    fn needs_three_points_off_a_line() {
+        let on_a_line = [((0.0, 0.0), (0.0, 0.0)), ((10.0, 10.0), (10.0, 10.0)), ((20.0, 20.0), (20.0, 20.0))];
+
+        assert!(compute_calibration_matrix(&on_a_line[..2]).is_none());
+        assert!(compute_calibration_matrix(&on_a_line).is_none());
+        assert!(compute_calibration_matrix(&[((5.0, 5.0), (0.0, 0.0)); 4]).is_none());
+    }
+}
@@ -0,0 +1,315 @@
+use std::{
+    cell::RefCell,
+    collections::VecDeque,
+    path::{Path, PathBuf},
+    rc::Rc,
+    sync::{Arc, Condvar, Mutex},
+    time::{Duration, Instant},
+};
+
+use slint::{
+    platform::{
+        software_renderer::{MinimalSoftwareWindow, RepaintBufferType},
+        EventLoopProxy, Platform,
+    },
+    EventLoopError, PhysicalSize, PlatformError, Rgb8Pixel,
+};
+
+use crate::{
+    error::DriverError,
+    headless::{
+        input_script::ScriptStep,
+        screenshot::{compare_with_golden, save_screenshot, to_image, ScreenshotRequests},
+    },
+    vnc::{Rect, VncOptions, VncServer},
+};
+
+pub struct HeadlessOptions {
+    pub width: u32,
+    pub height: u32,
+    /// Where script and interval screenshots are written.
+    pub screenshot_dir: PathBuf,
+    pub screenshot_interval: Option<Duration>,
+    pub script: Vec<ScriptStep>,
+    /// Script screenshots are compared with the file of the same name in
+    /// here. The event loop fails on `quit` if any of them differ.
+    pub golden_dir: Option<PathBuf>,
+    pub screenshots: Option<ScreenshotRequests>,
+    /// Serves the screen to VNC viewers, which makes this a remote display.
+    pub vnc: Option<VncOptions>,
+}
+
+impl HeadlessOptions {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            screenshot_dir: PathBuf::from("screenshots"),
+            screenshot_interval: None,
+            script: vec![],
+            golden_dir: None,
+            screenshots: None,
+            vnc: None,
+        }
+    }
+}
+
+struct ScriptState {
+    steps: VecDeque<ScriptStep>,
+    resume_at: Option<Instant>,
+    /// Script screenshots that did not match their golden image.
+    mismatches: Vec<String>,
+}
+
+/// Renders with the software renderer into memory instead of a display, for
+/// screenshots and golden-image tests without any display hardware.
+pub struct HeadlessPlatform {
+    window: Rc<MinimalSoftwareWindow>,
+    buffer: RefCell<Vec<Rgb8Pixel>>,
+    options: HeadlessOptions,
+    script: RefCell<ScriptState>,
+    next_interval_screenshot: RefCell<Option<Instant>>,
+    vnc: Option<Arc<VncServer>>,
+    queue: Queue,
+}
+
+impl HeadlessPlatform {
+    pub fn new(options: HeadlessOptions) -> Result<Self, DriverError> {
+        let window = MinimalSoftwareWindow::new(RepaintBufferType::ReusedBuffer);
+        window.set_size(PhysicalSize::new(options.width, options.height));
+
+        println!("Headless size in pixels: {}x{}", options.width, options.height);
+
+        let vnc = match &options.vnc {
+            Some(vnc) => Some(VncServer::start(vnc, options.width, options.height)?),
+            None => None,
+        };
+
+        Ok(Self {
+            window,
+            buffer: RefCell::new(vec![Rgb8Pixel::default(); (options.width * options.height) as usize]),
+            script: RefCell::new(ScriptState {
+                steps: options.script.iter().cloned().collect(),
+                resume_at: None,
+                mismatches: vec![],
+            }),
+            next_interval_screenshot: RefCell::new(options.screenshot_interval.map(|interval| Instant::now() + interval)),
+            options,
+            vnc,
+            queue: Queue(Default::default()),
+        })
+    }
+
+    /// Runs script steps up to the next wait or screenshot. Returns the
+    /// screenshot to take after the next frame and whether to quit.
+    fn run_script(&self, now: Instant) -> (Option<String>, bool) {
+        let mut script = self.script.borrow_mut();
+
+        if script.resume_at.is_some_and(|resume_at| resume_at > now) {
+            return (None, false);
+        }
+        script.resume_at = None;
+
+        while let Some(step) = script.steps.pop_front() {
+            match step {
+                ScriptStep::Wait(duration) => {
+                    script.resume_at = Some(now + duration);
+                    break;
+                }
+                ScriptStep::Screenshot(name) => return (Some(name), false),
+                ScriptStep::Quit => return (None, true),
+                step => {
+                    for event in step.window_events() {
+                        self.window.try_dispatch_event(event).unwrap();
+                    }
+                }
+            }
+        }
+
+        (None, false)
+    }
+
+    fn screenshot(&self, path: &Path) -> Result<image::RgbImage, DriverError> {
+        let image = to_image(&self.buffer.borrow(), self.options.width, self.options.height);
+        save_screenshot(&image, path)?;
+        Ok(image)
+    }
+
+    fn script_screenshot(&self, name: &str) -> Result<(), DriverError> {
+        let file_name = format!("{}.png", name);
+        let image = self.screenshot(&self.options.screenshot_dir.join(&file_name))?;
+
+        if let Some(golden_dir) = &self.options.golden_dir {
+            let golden_path = golden_dir.join(&file_name);
+            let differing = match compare_with_golden(&image, &golden_path) {
+                Ok(differing) => differing,
+                Err(e) => {
+                    println!("Failed to read golden image {}: {}", golden_path.display(), e);
+                    usize::MAX
+                }
+            };
+
+            if differing > 0 {
+                println!("Screenshot {} differs from {}", name, golden_path.display());
+                self.script.borrow_mut().mismatches.push(name.to_string());
+            }
+        }
+
+        Ok(())
+    }
+
+    fn next_timeout(&self, now: Instant) -> Option<Duration> {
+        let deadlines = [self.script.borrow().resume_at, *self.next_interval_screenshot.borrow()];
+
+        deadlines
+            .into_iter()
+            .flatten()
+            .map(|deadline| deadline.saturating_duration_since(now))
+            .chain(slint::platform::duration_until_next_timer_update())
+            .min()
+    }
+}
+
+impl Platform for HeadlessPlatform {
+    fn create_window_adapter(&self) -> Result<Rc<dyn slint::platform::WindowAdapter>, PlatformError> {
+        Ok(self.window.clone())
+    }
+
+    fn new_event_loop_proxy(&self) -> Option<Box<dyn EventLoopProxy>> {
+        Some(Box::new(self.queue.clone()))
+    }
+
+    fn run_event_loop(&self) -> Result<(), PlatformError> {
+        let to_platform_error = |e: DriverError| PlatformError::Other(e.to_string());
+
+        loop {
+            slint::platform::update_timers_and_animations();
+
+            let events: Vec<Event> = self.queue.0.0.lock().unwrap().drain(..).collect();
+            for event in events {
+                match event {
+                    Event::Quit => return Ok(()),
+                    Event::Event(event) => event(),
+                }
+            }
+
+            let now = Instant::now();
+            let (script_screenshot, quit) = self.run_script(now);
+
+            if let Some(vnc) = &self.vnc {
+                for event in vnc.take_events() {
+                    self.window.try_dispatch_event(event).unwrap();
+                }
+            }
+
+            let mut dirty = None;
+            self.window.draw_if_needed(|renderer| {
+                let region = renderer.render(self.buffer.borrow_mut().as_mut_slice(), self.options.width as usize);
+                dirty = Some(
+                    region
+                        .iter()
+                        .map(|(origin, size)| Rect { x: origin.x as u16, y: origin.y as u16, width: size.width as u16, height: size.height as u16 })
+                        .collect::<Vec<_>>(),
+                );
+            });
+
+            // The whole buffer is kept, so a new viewer gets a copy without a redraw
+            if let Some(vnc) = &self.vnc
+                && vnc.has_clients()
+                && (dirty.is_some() || vnc.is_stale())
+            {
+                vnc.update(&self.buffer.borrow(), dirty);
+            }
+
+            if let Some(name) = script_screenshot {
+                self.script_screenshot(&name).map_err(to_platform_error)?;
+            }
+
+            if let Some(screenshots) = &self.options.screenshots {
+                for path in screenshots.take() {
+                    if let Err(e) = self.screenshot(&path) {
+                        println!("Failed to save screenshot {}: {}", path.display(), e);
+                    }
+                }
+            }
+
+            let mut next_interval_screenshot = self.next_interval_screenshot.borrow_mut();
+            if let (Some(deadline), Some(interval)) = (*next_interval_screenshot, self.options.screenshot_interval)
+                && deadline <= now
+            {
+                let name = timestamp();
+                self.screenshot(&self.options.screenshot_dir.join(format!("{}.png", name))).map_err(to_platform_error)?;
+                *next_interval_screenshot = Some(now + interval);
+            }
+            drop(next_interval_screenshot);
+
+            if quit {
+                let script = self.script.borrow();
+                return match script.mismatches.is_empty() {
+                    true => Ok(()),
+                    false => Err(PlatformError::Other(format!("Screenshots differ from their golden images: {}", script.mismatches.join(", ")))),
+                };
+            }
+
+            // Keep going while the script has steps that don't wait
+            let script = self.script.borrow();
+            let script_ready = !script.steps.is_empty() && script.resume_at.is_none();
+            drop(script);
+
+            if !self.window.has_active_animations() && !script_ready {
+                self.queue.wait(self.next_timeout(now));
+            }
+        }
+    }
+}
+
+/// Seconds since the epoch, for interval screenshot names.
+fn timestamp() -> String {
+    let elapsed = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default();
+    format!("{}-{:03}", elapsed.as_secs(), elapsed.subsec_millis())
+}
+
+enum Event {
+    Quit,
+    Event(Box<dyn FnOnce() + Send>),
+}
+
+#[derive(Clone)]
+struct Queue(Arc<(Mutex<VecDeque<Event>>, Condvar)>);
+
+impl Queue {
+    /// Blocks until an event is queued or `timeout` has passed.
+    fn wait(&self, timeout: Option<Duration>) {
+        let (queue, condvar) = &*self.0;
+        let queue = queue.lock().unwrap();
+
+        if !queue.is_empty() {
+            return;
+        }
+
+        match timeout {
+            Some(timeout) => drop(condvar.wait_timeout(queue, timeout).unwrap()),
+            None => drop(condvar.wait(queue).unwrap()),
+        }
+    }
+
+    fn push(&self, event: Event) {
+        let (queue, condvar) = &*self.0;
+        queue.lock().unwrap().push_back(event);
+        condvar.notify_one();
+    }
+}
+
+impl EventLoopProxy for Queue {
+    fn quit_event_loop(&self) -> Result<(), EventLoopError> {
+        self.push(Event::Quit);
+        Ok(())
+    }
+
+    fn invoke_from_event_loop(&self, event: Box<dyn FnOnce() + Send>) -> Result<(), EventLoopError> {
+        self.push(Event::Event(event));
+        Ok(())
+    }
+}
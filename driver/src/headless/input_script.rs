@@ -0,0 +1,238 @@
+use std::time::Duration;
+
+use slint::{
+    platform::{Key, PointerEventButton, WindowEvent},
+    LogicalPosition, SharedString,
+};
+
+use crate::error::DriverError;
+
+// Moves sent for a drag, so flickables see a gesture rather than a jump
+const DRAG_STEPS: u32 = 10;
+
+/// One line of an input script.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScriptStep {
+    /// `wait <milliseconds>`
+    Wait(Duration),
+    /// `tap <x> <y>`
+    Tap(f32, f32),
+    /// `long_press <x> <y>`, a right click like the touch long-press gesture.
+    LongPress(f32, f32),
+    /// `drag <x1> <y1> <x2> <y2>`
+    Drag(f32, f32, f32, f32),
+    /// `scroll <x> <y> <delta_x> <delta_y>`
+    Scroll(f32, f32, f32, f32),
+    /// `key <name>`, e.g. `key Return` or `key a`.
+    Key(SharedString),
+    /// `text <text>`, typed one character at a time.
+    Text(String),
+    /// `screenshot <name>`, written as `<name>.png`.
+    Screenshot(String),
+    /// `quit`
+    Quit,
+}
+
+impl ScriptStep {
+    /// Window events the step sends, empty for the ones that are not input.
+    pub fn window_events(&self) -> Vec<WindowEvent> {
+        let click = |x: f32, y: f32, button: PointerEventButton| {
+            let position = LogicalPosition::new(x, y);
+            vec![
+                WindowEvent::PointerMoved { position },
+                WindowEvent::PointerPressed { position, button },
+                WindowEvent::PointerReleased { position, button },
+                WindowEvent::PointerExited,
+            ]
+        };
+
+        let key = |text: SharedString| vec![WindowEvent::KeyPressed { text: text.clone() }, WindowEvent::KeyReleased { text }];
+
+        match self {
+            ScriptStep::Tap(x, y) => click(*x, *y, PointerEventButton::Left),
+            ScriptStep::LongPress(x, y) => click(*x, *y, PointerEventButton::Right),
+            ScriptStep::Drag(x1, y1, x2, y2) => {
+                let button = PointerEventButton::Left;
+                let start = LogicalPosition::new(*x1, *y1);
+                let end = LogicalPosition::new(*x2, *y2);
+
+                let mut events = vec![WindowEvent::PointerPressed { position: start, button }];
+                events.extend((1..=DRAG_STEPS).map(|step| {
+                    let t = step as f32 / DRAG_STEPS as f32;
+                    WindowEvent::PointerMoved { position: LogicalPosition::new(x1 + (x2 - x1) * t, y1 + (y2 - y1) * t) }
+                }));
+                events.push(WindowEvent::PointerReleased { position: end, button });
+                events.push(WindowEvent::PointerExited);
+                events
+            }
+            ScriptStep::Scroll(x, y, delta_x, delta_y) => vec![WindowEvent::PointerScrolled {
+                position: LogicalPosition::new(*x, *y),
+                delta_x: *delta_x,
+                delta_y: *delta_y,
+            }],
+            ScriptStep::Key(text) => key(text.clone()),
+            ScriptStep::Text(text) => text.chars().flat_map(|c| key(SharedString::from(c.to_string()))).collect(),
+            ScriptStep::Wait(_) | ScriptStep::Screenshot(_) | ScriptStep::Quit => vec![],
+        }
+    }
+}
+
+fn named_key(name: &str) -> SharedString {
+    let key = match name.to_lowercase().as_str() {
+        "return" | "enter" => Key::Return,
+        "escape" | "esc" => Key::Escape,
+        "tab" => Key::Tab,
+        "backtab" => Key::Backtab,
+        "backspace" => Key::Backspace,
+        "delete" => Key::Delete,
+        "space" => Key::Space,
+        "up" => Key::UpArrow,
+        "down" => Key::DownArrow,
+        "left" => Key::LeftArrow,
+        "right" => Key::RightArrow,
+        "home" => Key::Home,
+        "end" => Key::End,
+        "pageup" => Key::PageUp,
+        "pagedown" => Key::PageDown,
+        _ => return SharedString::from(name),
+    };
+
+    key.into()
+}
+
+/// Parses an input script. One step per line, `#` starts a comment and
+/// coordinates are in logical window pixels.
+pub fn parse_input_script(script: &str) -> Result<Vec<ScriptStep>, DriverError> {
+    let mut steps = vec![];
+
+    for (index, line) in script.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or_default().trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let error = |message: &str| DriverError::InvalidScript(format!("line {}: {} in {:?}", index + 1, message, line));
+        let (command, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let rest = rest.trim();
+
+        let numbers = |count: usize| -> Result<Vec<f32>, DriverError> {
+            let numbers = rest
+                .split_whitespace()
+                .map(|value| value.parse::<f32>())
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|_| error("expected numbers"))?;
+
+            match numbers.len() == count {
+                true => Ok(numbers),
+                false => Err(error(&format!("expected {} numbers", count))),
+            }
+        };
+
+        let step = match command.to_lowercase().as_str() {
+            "wait" => ScriptStep::Wait(Duration::from_millis(rest.parse().map_err(|_| error("expected milliseconds"))?)),
+            "tap" => {
+                let n = numbers(2)?;
+                ScriptStep::Tap(n[0], n[1])
+            }
+            "long_press" => {
+                let n = numbers(2)?;
+                ScriptStep::LongPress(n[0], n[1])
+            }
+            "drag" => {
+                let n = numbers(4)?;
+                ScriptStep::Drag(n[0], n[1], n[2], n[3])
+            }
+            "scroll" => {
+                let n = numbers(4)?;
+                ScriptStep::Scroll(n[0], n[1], n[2], n[3])
+            }
+            "key" if !rest.is_empty() => ScriptStep::Key(named_key(rest)),
+            "text" => ScriptStep::Text(rest.to_string()),
+            "screenshot" if !rest.is_empty() => ScriptStep::Screenshot(rest.to_string()),
+            "quit" => ScriptStep::Quit,
+            _ => return Err(error("unknown step")),
+        };
+
+        steps.push(step);
+    }
+
+    Ok(steps)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error_message(script: &str) -> String {
+        match parse_input_script(script) {
+            Err(DriverError::InvalidScript(message)) => message,
+            other => panic!("expected an invalid script, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parses_every_step() {
+        let script = "
+            wait 250
+            tap 10 20
+            long_press 1.5 2.5
+            drag 0 0 100 50
+            scroll 5 6 0 -120
+            key a
+            text hello world
+            screenshot home
+            quit
+        ";
+
+        assert_eq!(
+            parse_input_script(script).unwrap(),
+            vec![
+                ScriptStep::Wait(Duration::from_millis(250)),
+                ScriptStep::Tap(10.0, 20.0),
+                ScriptStep::LongPress(1.5, 2.5),
+                ScriptStep::Drag(0.0, 0.0, 100.0, 50.0),
+                ScriptStep::Scroll(5.0, 6.0, 0.0, -120.0),
+                ScriptStep::Key("a".into()),
+                ScriptStep::Text("hello world".to_string()),
+                ScriptStep::Screenshot("home".to_string()),
+                ScriptStep::Quit,
+            ]
+        );
+    }
+
+    #[test]
+    fn skips_comments_and_blank_lines() {
+        let script = "# open the files page\n\n   \ntap 30 30 # first sidebar button\n";
+
+        assert_eq!(parse_input_script(script).unwrap(), vec![ScriptStep::Tap(30.0, 30.0)]);
+    }
+
+    #[test]
+    fn steps_and_key_names_ignore_case() {
+        let steps = parse_input_script("TAP 1 2\nKey ESCAPE\nkey enter\nkey PageDown").unwrap();
+
+        assert_eq!(
+            steps,
+            vec![
+                ScriptStep::Tap(1.0, 2.0),
+                ScriptStep::Key(Key::Escape.into()),
+                ScriptStep::Key(Key::Return.into()),
+                ScriptStep::Key(Key::PageDown.into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn errors_name_the_line() {
+        assert_eq!(error_message("wait 10\n\njump 1 2"), "line 3: unknown step in \"jump 1 2\"");
+        assert_eq!(error_message("tap 1"), "line 1: expected 2 numbers in \"tap 1\"");
+        assert_eq!(error_message("drag 1 2 three 4"), "line 1: expected numbers in \"drag 1 2 three 4\"");
+        assert_eq!(error_message("wait soon"), "line 1: expected milliseconds in \"wait soon\"");
+    }
+
+    #[test]
+    fn key_and_screenshot_need_an_argument() {
+        assert_eq!(error_message("key"), "line 1: unknown step in \"key\"");
+        assert_eq!(error_message("screenshot   # unnamed"), "line 1: unknown step in \"screenshot\"");
+    }
+}
@@ -0,0 +1,7 @@
+mod headless_platform;
+mod input_script;
+mod screenshot;
+
+pub use headless_platform::{HeadlessOptions, HeadlessPlatform};
+pub use input_script::{parse_input_script, ScriptStep};
+pub use screenshot::ScreenshotRequests;
\ No newline at end of file
@@ -0,0 +1,52 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use image::RgbImage;
+use slint::Rgb8Pixel;
+
+use crate::error::DriverError;
+
+/// Screenshots asked for from other threads, taken after the next frame.
+#[derive(Clone, Default)]
+pub struct ScreenshotRequests(Arc<Mutex<Vec<PathBuf>>>);
+
+impl ScreenshotRequests {
+    /// Writes the window as PNG to `path`. Needs the event loop to be running.
+    pub fn request(&self, path: PathBuf) {
+        self.0.lock().unwrap().push(path);
+        // Wakes the event loop
+        let _ = slint::invoke_from_event_loop(|| {});
+    }
+
+    pub(crate) fn take(&self) -> Vec<PathBuf> {
+        std::mem::take(&mut *self.0.lock().unwrap())
+    }
+}
+
+pub(crate) fn to_image(pixels: &[Rgb8Pixel], width: u32, height: u32) -> RgbImage {
+    let bytes = pixels.iter().flat_map(|pixel| [pixel.r, pixel.g, pixel.b]).collect();
+    RgbImage::from_raw(width, height, bytes).unwrap()
+}
+
+pub(crate) fn save_screenshot(image: &RgbImage, path: &Path) -> Result<(), DriverError> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    image.save(path)?;
+    println!("Saved screenshot {}", path.display());
+    Ok(())
+}
+
+/// Number of pixels that differ from the golden image, all of them if the size differs.
+pub(crate) fn compare_with_golden(image: &RgbImage, golden_path: &Path) -> Result<usize, DriverError> {
+    let golden = image::open(golden_path)?.to_rgb8();
+
+    if golden.dimensions() != image.dimensions() {
+        return Ok((image.width() * image.height()) as usize);
+    }
+
+    Ok(image.pixels().zip(golden.pixels()).filter(|(pixel, golden)| pixel != golden).count())
+}
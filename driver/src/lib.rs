@@ -1,3 +1,9 @@
+mod error;
 mod framebuffer;
+mod headless;
+mod vnc;
 
-pub use framebuffer::*;
\ No newline at end of file
+pub use error::*;
+pub use framebuffer::*;
+pub use headless::*;
+pub use vnc::VncOptions;
\ No newline at end of file
@@ -0,0 +1,7 @@
+mod des;
+mod rfb;
+mod vnc_server;
+
+pub(crate) use rfb::Rect;
+pub(crate) use vnc_server::VncServer;
+pub use vnc_server::VncOptions;
\ No newline at end of file
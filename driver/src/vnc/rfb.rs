@@ -0,0 +1,295 @@
+// Just enough of RFB 3.3 to 3.8 (RFC 6143) for viewing and pointer/key
+// input: no or VNC authentication, raw encoding only.
+
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    io::{self, Read, Write},
+    time::SystemTime,
+};
+
+use slint::Rgb8Pixel;
+
+use crate::vnc::des;
+
+pub const PROTOCOL_VERSION: &[u8; 12] = b"RFB 003.008\n";
+
+pub const SECURITY_NONE: u8 = 1;
+pub const SECURITY_VNC_AUTH: u8 = 2;
+
+pub const CLIENT_SET_PIXEL_FORMAT: u8 = 0;
+pub const CLIENT_SET_ENCODINGS: u8 = 2;
+pub const CLIENT_UPDATE_REQUEST: u8 = 3;
+pub const CLIENT_KEY_EVENT: u8 = 4;
+pub const CLIENT_POINTER_EVENT: u8 = 5;
+pub const CLIENT_CUT_TEXT: u8 = 6;
+
+const SERVER_FRAMEBUFFER_UPDATE: u8 = 0;
+const ENCODING_RAW: i32 = 0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rect {
+    pub x: u16,
+    pub y: u16,
+    pub width: u16,
+    pub height: u16,
+}
+
+impl Rect {
+    /// The smallest rectangle covering both.
+    pub fn union(&self, other: &Rect) -> Rect {
+        let x = self.x.min(other.x);
+        let y = self.y.min(other.y);
+        let right = (self.x + self.width).max(other.x + other.width);
+        let bottom = (self.y + self.height).max(other.y + other.height);
+
+        Rect { x, y, width: right - x, height: bottom - y }
+    }
+}
+
+/// How the client wants pixels encoded. Colour maps are not supported.
+#[derive(Debug, Clone, Copy)]
+pub struct PixelFormat {
+    pub bits_per_pixel: u8,
+    pub depth: u8,
+    pub big_endian: bool,
+    pub true_color: bool,
+    pub red_max: u16,
+    pub green_max: u16,
+    pub blue_max: u16,
+    pub red_shift: u8,
+    pub green_shift: u8,
+    pub blue_shift: u8,
+}
+
+impl Default for PixelFormat {
+    /// 32 bit little endian xRGB, what the server announces.
+    fn default() -> Self {
+        Self {
+            bits_per_pixel: 32,
+            depth: 24,
+            big_endian: false,
+            true_color: true,
+            red_max: 255,
+            green_max: 255,
+            blue_max: 255,
+            red_shift: 16,
+            green_shift: 8,
+            blue_shift: 0,
+        }
+    }
+}
+
+impl PixelFormat {
+    pub fn read(bytes: &[u8; 16]) -> Self {
+        Self {
+            bits_per_pixel: bytes[0],
+            depth: bytes[1],
+            big_endian: bytes[2] != 0,
+            true_color: bytes[3] != 0,
+            red_max: u16::from_be_bytes([bytes[4], bytes[5]]),
+            green_max: u16::from_be_bytes([bytes[6], bytes[7]]),
+            blue_max: u16::from_be_bytes([bytes[8], bytes[9]]),
+            red_shift: bytes[10],
+            green_shift: bytes[11],
+            blue_shift: bytes[12],
+        }
+    }
+
+    /// Formats `encode` can produce. Anything else would overflow its shifts.
+    pub fn validate(&self) -> io::Result<()> {
+        let invalid = |message: String| Err(io::Error::new(io::ErrorKind::InvalidData, message));
+
+        if !self.true_color {
+            return invalid(String::from("Colour maps are not supported"));
+        }
+        if ![8, 16, 32].contains(&self.bits_per_pixel) {
+            return invalid(format!("Unsupported bits per pixel {}", self.bits_per_pixel));
+        }
+        if self.depth > self.bits_per_pixel {
+            return invalid(format!("Depth {} exceeds {} bits per pixel", self.depth, self.bits_per_pixel));
+        }
+        if [self.red_shift, self.green_shift, self.blue_shift].iter().any(|&shift| shift >= self.bits_per_pixel) {
+            return invalid(format!("Colour shifts must be below {} bits per pixel", self.bits_per_pixel));
+        }
+
+        Ok(())
+    }
+
+    pub fn write(&self, out: &mut Vec<u8>) {
+        out.extend([self.bits_per_pixel, self.depth, self.big_endian as u8, self.true_color as u8]);
+        out.extend(self.red_max.to_be_bytes());
+        out.extend(self.green_max.to_be_bytes());
+        out.extend(self.blue_max.to_be_bytes());
+        out.extend([self.red_shift, self.green_shift, self.blue_shift, 0, 0, 0]);
+    }
+
+    fn encode(&self, pixel: Rgb8Pixel, out: &mut Vec<u8>) {
+        let scale = |value: u8, max: u16| (value as u32 * max as u32 + 127) / 255;
+        let value = scale(pixel.r, self.red_max) << self.red_shift
+            | scale(pixel.g, self.green_max) << self.green_shift
+            | scale(pixel.b, self.blue_max) << self.blue_shift;
+
+        let bytes = (self.bits_per_pixel / 8).clamp(1, 4) as usize;
+        match self.big_endian {
+            true => out.extend(&value.to_be_bytes()[4 - bytes..]),
+            false => out.extend(&value.to_le_bytes()[..bytes]),
+        }
+    }
+}
+
+/// Version and security handshake, up to the ClientInit message. With a
+/// `password` the client has to pass VNC authentication. The shared flag is
+/// ignored, every client shares the screen.
+pub fn handshake(stream: &mut (impl Read + Write), password: Option<&str>) -> io::Result<()> {
+    stream.write_all(PROTOCOL_VERSION)?;
+
+    let mut version = [0u8; 12];
+    stream.read_exact(&mut version)?;
+    let minor: u32 = std::str::from_utf8(&version[8..11]).ok().and_then(|minor| minor.parse().ok()).unwrap_or(3);
+
+    let security = match password {
+        Some(_) => SECURITY_VNC_AUTH,
+        None => SECURITY_NONE,
+    };
+
+    if minor < 7 {
+        // 3.3: the server decides
+        stream.write_all(&(security as u32).to_be_bytes())?;
+    } else {
+        stream.write_all(&[1, security])?;
+
+        let mut chosen = [0u8; 1];
+        stream.read_exact(&mut chosen)?;
+        if chosen[0] != security {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "Unsupported security type"));
+        }
+    }
+
+    match password {
+        Some(password) => {
+            let challenge = random_challenge();
+            stream.write_all(&challenge)?;
+
+            let mut response = [0u8; 16];
+            stream.read_exact(&mut response)?;
+
+            if response != des::vnc_auth_response(password, &challenge) {
+                let reason = "Wrong password";
+                stream.write_all(&1u32.to_be_bytes())?;
+                // Only 3.8 explains failures
+                if minor >= 8 {
+                    stream.write_all(&(reason.len() as u32).to_be_bytes())?;
+                    stream.write_all(reason.as_bytes())?;
+                }
+                return Err(io::Error::new(io::ErrorKind::PermissionDenied, reason));
+            }
+
+            stream.write_all(&0u32.to_be_bytes())?;
+        }
+        // Without authentication 3.7 only sends a result for failures
+        None if minor >= 8 => stream.write_all(&0u32.to_be_bytes())?,
+        None => {}
+    }
+
+    // ClientInit, the shared flag
+    let mut shared = [0u8; 1];
+    stream.read_exact(&mut shared)?;
+
+    Ok(())
+}
+
+/// `RandomState` is seeded randomly per instance, there is no RNG dependency.
+fn random_challenge() -> [u8; 16] {
+    let mut challenge = [0u8; 16];
+
+    for chunk in challenge.chunks_exact_mut(8) {
+        let mut hasher = RandomState::new().build_hasher();
+        hasher.write_u128(SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap_or_default().as_nanos());
+        chunk.copy_from_slice(&hasher.finish().to_be_bytes());
+    }
+
+    challenge
+}
+
+pub fn server_init(stream: &mut impl Write, width: u16, height: u16, name: &str) -> io::Result<()> {
+    let mut out = vec![];
+    out.extend(width.to_be_bytes());
+    out.extend(height.to_be_bytes());
+    PixelFormat::default().write(&mut out);
+    out.extend((name.len() as u32).to_be_bytes());
+    out.extend(name.as_bytes());
+
+    stream.write_all(&out)
+}
+
+/// A FramebufferUpdate message with the given rectangles of `frame`.
+pub fn framebuffer_update(frame: &[Rgb8Pixel], frame_width: u16, rects: &[Rect], pixel_format: &PixelFormat) -> Vec<u8> {
+    let mut out = vec![SERVER_FRAMEBUFFER_UPDATE, 0];
+    out.extend((rects.len() as u16).to_be_bytes());
+
+    for rect in rects {
+        out.extend(rect.x.to_be_bytes());
+        out.extend(rect.y.to_be_bytes());
+        out.extend(rect.width.to_be_bytes());
+        out.extend(rect.height.to_be_bytes());
+        out.extend(ENCODING_RAW.to_be_bytes());
+
+        for y in rect.y..rect.y + rect.height {
+            let row = y as usize * frame_width as usize;
+            for pixel in &frame[row + rect.x as usize..row + (rect.x + rect.width) as usize] {
+                pixel_format.encode(*pixel, &mut out);
+            }
+        }
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_common_formats() {
+        let rgb565 = PixelFormat {
+            bits_per_pixel: 16,
+            depth: 16,
+            red_max: 31,
+            green_max: 63,
+            blue_max: 31,
+            red_shift: 11,
+            green_shift: 5,
+            blue_shift: 0,
+            ..PixelFormat::default()
+        };
+
+        assert!(PixelFormat::default().validate().is_ok());
+        assert!(rgb565.validate().is_ok());
+    }
+
+    #[test]
+    fn rejects_formats_encode_cannot_produce() {
+        let invalid = [
+            PixelFormat { true_color: false, ..PixelFormat::default() },
+            PixelFormat { bits_per_pixel: 24, ..PixelFormat::default() },
+            PixelFormat { bits_per_pixel: 0, depth: 0, ..PixelFormat::default() },
+            PixelFormat { depth: 33, ..PixelFormat::default() },
+            PixelFormat { red_shift: 32, ..PixelFormat::default() },
+            PixelFormat { bits_per_pixel: 16, depth: 16, green_shift: 16, red_shift: 0, ..PixelFormat::default() },
+        ];
+
+        for pixel_format in invalid {
+            assert!(pixel_format.validate().is_err(), "{:?} was accepted", pixel_format);
+        }
+    }
+
+    #[test]
+    fn union_covers_both_rects() {
+        let a = Rect { x: 10, y: 20, width: 5, height: 5 };
+        let b = Rect { x: 0, y: 22, width: 4, height: 10 };
+
+        assert_eq!(a.union(&b), Rect { x: 0, y: 20, width: 15, height: 12 });
+        assert_eq!(a.union(&a), a);
+    }
+}
@@ -0,0 +1,442 @@
+use std::{
+    collections::HashMap,
+    io::{self, Read, Write},
+    net::{TcpListener, TcpStream},
+    sync::{Arc, Condvar, Mutex, MutexGuard},
+    thread,
+};
+
+use slint::{
+    platform::{Key, PointerEventButton, WindowEvent},
+    LogicalPosition, Rgb8Pixel, SharedString,
+};
+
+use crate::vnc::rfb::{self, PixelFormat, Rect};
+
+// Frame diffing granularity when the caller has no dirty region
+const TILE_SIZE: u16 = 16;
+// Pixels scrolled per wheel click
+const WHEEL_STEP: f32 = 60.0;
+
+#[derive(Debug, Clone)]
+pub struct VncOptions {
+    /// e.g. `127.0.0.1:5900`. Other than loopback addresses need a `password`
+    /// or `view_only`.
+    pub listen: String,
+    /// Shown in the title bar of the viewer.
+    pub name: String,
+    /// Asked for with VNC authentication, only the first 8 characters count.
+    pub password: Option<String>,
+    /// Ignore the viewers' pointer and keyboard.
+    pub view_only: bool,
+}
+
+impl Default for VncOptions {
+    fn default() -> Self {
+        Self {
+            listen: String::from("127.0.0.1:5900"),
+            name: String::from("Atomscreen"),
+            password: None,
+            view_only: false,
+        }
+    }
+}
+
+struct UpdateRequest {
+    incremental: bool,
+}
+
+struct Client {
+    pixel_format: PixelFormat,
+    /// Bounds of everything changed since the last update sent.
+    dirty: Option<Rect>,
+    request: Option<UpdateRequest>,
+    closed: bool,
+}
+
+struct State {
+    frame: Vec<Rgb8Pixel>,
+    /// Set while nothing was copied into `frame` since a client connected.
+    stale: bool,
+    clients: HashMap<u64, Client>,
+    next_client_id: u64,
+    events: Vec<WindowEvent>,
+}
+
+/// Serves the rendered frame over RFB and collects the input of the
+/// viewers. Frames are only copied in while someone is connected.
+pub(crate) struct VncServer {
+    state: Mutex<State>,
+    changed: Condvar,
+    width: u16,
+    height: u16,
+    name: String,
+    password: Option<String>,
+    view_only: bool,
+}
+
+impl VncServer {
+    /// `width` and `height` are the window size.
+    pub fn start(options: &VncOptions, width: u32, height: u32) -> io::Result<Arc<VncServer>> {
+        let listener = TcpListener::bind(&options.listen)?;
+
+        // Anyone who can reach it could start prints and heaters
+        let address = listener.local_addr()?;
+        if !address.ip().is_loopback() && options.password.is_none() && !options.view_only {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                format!("Refusing to serve VNC on {} without a password, set one or view_only", address),
+            ));
+        }
+
+        println!("VNC server listening on {}", address);
+
+        let server = Arc::new(VncServer {
+            state: Mutex::new(State {
+                frame: vec![Rgb8Pixel::default(); (width * height) as usize],
+                stale: true,
+                clients: HashMap::new(),
+                next_client_id: 0,
+                events: vec![],
+            }),
+            changed: Condvar::new(),
+            width: width as u16,
+            height: height as u16,
+            name: options.name.clone(),
+            password: options.password.clone(),
+            view_only: options.view_only,
+        });
+
+        {
+            let server = server.clone();
+            thread::spawn(move || {
+                for stream in listener.incoming().flatten() {
+                    let server = server.clone();
+                    thread::spawn(move || {
+                        let peer = stream.peer_addr().map(|peer| peer.to_string()).unwrap_or_default();
+                        println!("VNC client {} connected", peer);
+
+                        if let Err(e) = VncServer::serve(&server, stream) {
+                            println!("VNC client {} disconnected: {}", peer, e);
+                        }
+                    });
+                }
+            });
+        }
+
+        Ok(server)
+    }
+
+    pub fn has_clients(&self) -> bool {
+        !self.state.lock().unwrap().clients.is_empty()
+    }
+
+    /// Whether a client is waiting for a first frame, even if nothing was redrawn.
+    pub fn is_stale(&self) -> bool {
+        self.state.lock().unwrap().stale
+    }
+
+    /// Copies in a new window sized frame. Without `dirty` the changed parts are
+    /// found by comparing with the previous frame.
+    pub fn update(&self, frame: &[Rgb8Pixel], dirty: Option<Vec<Rect>>) {
+        let state = self.state.lock().unwrap();
+
+        let dirty = match dirty {
+            Some(dirty) => dirty,
+            None => self.diff(&state.frame, frame),
+        };
+
+        self.publish(state, frame, dirty, true);
+    }
+
+    /// Like `update`, for a frame that is only current within `dirty` unless
+    /// `complete`. A viewer joining in between stays stale until a complete
+    /// frame arrives.
+    pub fn update_partial(&self, frame: &[Rgb8Pixel], dirty: Vec<Rect>, complete: bool) {
+        let state = self.state.lock().unwrap();
+        self.publish(state, frame, dirty, complete);
+    }
+
+    fn publish(&self, mut state: MutexGuard<State>, frame: &[Rgb8Pixel], dirty: Vec<Rect>, complete: bool) {
+        state.frame.copy_from_slice(frame);
+
+        if complete {
+            state.stale = false;
+        }
+
+        if let Some(bounds) = dirty.iter().copied().reduce(|bounds, rect| bounds.union(&rect)) {
+            for client in state.clients.values_mut() {
+                client.dirty = Some(client.dirty.map_or(bounds, |dirty| dirty.union(&bounds)));
+            }
+            self.changed.notify_all();
+        }
+    }
+
+    /// Input from the viewers since the last call.
+    pub fn take_events(&self) -> Vec<WindowEvent> {
+        std::mem::take(&mut self.state.lock().unwrap().events)
+    }
+
+    fn diff(&self, old: &[Rgb8Pixel], new: &[Rgb8Pixel]) -> Vec<Rect> {
+        let mut rects = vec![];
+
+        for tile_y in (0..self.height).step_by(TILE_SIZE as usize) {
+            let height = TILE_SIZE.min(self.height - tile_y);
+
+            for tile_x in (0..self.width).step_by(TILE_SIZE as usize) {
+                let width = TILE_SIZE.min(self.width - tile_x);
+                let changed = (tile_y..tile_y + height).any(|y| {
+                    let start = y as usize * self.width as usize + tile_x as usize;
+                    old[start..start + width as usize] != new[start..start + width as usize]
+                });
+
+                if !changed {
+                    continue;
+                }
+
+                // Merge with the tile to the left
+                match rects.last_mut() {
+                    Some(Rect { x, y, width: last_width, .. }) if *y == tile_y && *x + *last_width == tile_x => *last_width += width,
+                    _ => rects.push(Rect { x: tile_x, y: tile_y, width, height }),
+                }
+            }
+        }
+
+        rects
+    }
+
+    fn serve(server: &Arc<VncServer>, mut stream: TcpStream) -> io::Result<()> {
+        stream.set_nodelay(true)?;
+        rfb::handshake(&mut stream, server.password.as_deref())?;
+        rfb::server_init(&mut stream, server.width, server.height, &server.name)?;
+
+        let id = {
+            let mut state = server.state.lock().unwrap();
+            let id = state.next_client_id;
+            state.next_client_id += 1;
+            state.stale = true;
+            state.clients.insert(id, Client { pixel_format: PixelFormat::default(), dirty: None, request: None, closed: false });
+            id
+        };
+
+        // Ask the event loop for a frame
+        let _ = slint::invoke_from_event_loop(|| {});
+
+        let writer = {
+            let server = server.clone();
+            let stream = stream.try_clone()?;
+            thread::spawn(move || server.write_updates(id, stream))
+        };
+
+        let result = server.read_messages(id, &mut stream);
+
+        {
+            let mut state = server.state.lock().unwrap();
+            if let Some(client) = state.clients.get_mut(&id) {
+                client.closed = true;
+            }
+            server.changed.notify_all();
+        }
+
+        let _ = writer.join();
+        server.state.lock().unwrap().clients.remove(&id);
+        let _ = stream.shutdown(std::net::Shutdown::Both);
+
+        result
+    }
+
+    fn write_updates(&self, id: u64, mut stream: TcpStream) {
+        loop {
+            let update = {
+                let mut state = self.state.lock().unwrap();
+
+                let mut state = loop {
+                    let client = &state.clients[&id];
+                    let ready = match &client.request {
+                        Some(request) => !request.incremental || client.dirty.is_some(),
+                        None => false,
+                    };
+
+                    if client.closed || (ready && !state.stale) {
+                        break state;
+                    }
+
+                    state = self.changed.wait(state).unwrap();
+                };
+
+                let frame = &state.frame;
+                let client = state.clients.get(&id).unwrap();
+                if client.closed {
+                    return;
+                }
+
+                let rects = match client.request.as_ref().is_some_and(|request| request.incremental) {
+                    true => client.dirty.into_iter().collect(),
+                    false => vec![Rect { x: 0, y: 0, width: self.width, height: self.height }],
+                };
+                let update = rfb::framebuffer_update(frame, self.width, &rects, &client.pixel_format);
+
+                let client = state.clients.get_mut(&id).unwrap();
+                client.dirty = None;
+                client.request = None;
+                update
+            };
+
+            if stream.write_all(&update).is_err() {
+                // The reader notices as well and cleans up
+                return;
+            }
+        }
+    }
+
+    fn read_messages(&self, id: u64, stream: &mut TcpStream) -> io::Result<()> {
+        let mut buttons = 0u8;
+        let mut position = LogicalPosition::default();
+
+        loop {
+            let mut message_type = [0u8; 1];
+            stream.read_exact(&mut message_type)?;
+
+            match message_type[0] {
+                rfb::CLIENT_SET_PIXEL_FORMAT => {
+                    let mut message = [0u8; 19];
+                    stream.read_exact(&mut message)?;
+                    let pixel_format = PixelFormat::read(message[3..19].try_into().unwrap());
+                    pixel_format.validate()?;
+
+                    let mut state = self.state.lock().unwrap();
+                    if let Some(client) = state.clients.get_mut(&id) {
+                        client.pixel_format = pixel_format;
+                    }
+                }
+                rfb::CLIENT_SET_ENCODINGS => {
+                    // Raw is always supported, so the list does not matter
+                    let mut header = [0u8; 3];
+                    stream.read_exact(&mut header)?;
+                    let count = u16::from_be_bytes([header[1], header[2]]) as usize;
+                    stream.read_exact(&mut vec![0u8; count * 4])?;
+                }
+                rfb::CLIENT_UPDATE_REQUEST => {
+                    let mut message = [0u8; 9];
+                    stream.read_exact(&mut message)?;
+
+                    let mut state = self.state.lock().unwrap();
+                    if let Some(client) = state.clients.get_mut(&id) {
+                        client.request = Some(UpdateRequest { incremental: message[0] != 0 });
+                    }
+                    self.changed.notify_all();
+                }
+                rfb::CLIENT_KEY_EVENT => {
+                    let mut message = [0u8; 7];
+                    stream.read_exact(&mut message)?;
+
+                    if self.view_only {
+                        continue;
+                    }
+
+                    if let Some(text) = keysym_text(u32::from_be_bytes([message[3], message[4], message[5], message[6]])) {
+                        self.push_events(vec![match message[0] != 0 {
+                            true => WindowEvent::KeyPressed { text },
+                            false => WindowEvent::KeyReleased { text },
+                        }]);
+                    }
+                }
+                rfb::CLIENT_POINTER_EVENT => {
+                    let mut message = [0u8; 5];
+                    stream.read_exact(&mut message)?;
+
+                    if self.view_only {
+                        continue;
+                    }
+
+                    let mask = message[0];
+                    let x = u16::from_be_bytes([message[1], message[2]]).min(self.width.saturating_sub(1));
+                    let y = u16::from_be_bytes([message[3], message[4]]).min(self.height.saturating_sub(1));
+                    let events = pointer_events(&mut buttons, &mut position, mask, LogicalPosition::new(x as f32, y as f32));
+                    self.push_events(events);
+                }
+                rfb::CLIENT_CUT_TEXT => {
+                    let mut header = [0u8; 7];
+                    stream.read_exact(&mut header)?;
+                    let length = u32::from_be_bytes([header[3], header[4], header[5], header[6]]) as u64;
+                    io::copy(&mut (&mut *stream).take(length), &mut io::sink())?;
+                }
+                other => {
+                    return Err(io::Error::new(io::ErrorKind::InvalidData, format!("Unknown message type {}", other)));
+                }
+            }
+        }
+    }
+
+    fn push_events(&self, events: Vec<WindowEvent>) {
+        if events.is_empty() {
+            return;
+        }
+
+        self.state.lock().unwrap().events.extend(events);
+        let _ = slint::invoke_from_event_loop(|| {});
+    }
+}
+
+/// Turns an RFB pointer event into window events. Bits 0 to 2 of the mask are
+/// the left, middle and right button, 3 to 6 the wheel up, down, left and right.
+fn pointer_events(buttons: &mut u8, position: &mut LogicalPosition, mask: u8, new_position: LogicalPosition) -> Vec<WindowEvent> {
+    let mut events = vec![];
+
+    if *position != new_position {
+        *position = new_position;
+        events.push(WindowEvent::PointerMoved { position: new_position });
+    }
+
+    for (bit, button) in [(0, PointerEventButton::Left), (1, PointerEventButton::Middle), (2, PointerEventButton::Right)] {
+        let was_pressed = *buttons & (1 << bit) != 0;
+        let pressed = mask & (1 << bit) != 0;
+
+        match (was_pressed, pressed) {
+            (false, true) => events.push(WindowEvent::PointerPressed { position: new_position, button }),
+            (true, false) => events.push(WindowEvent::PointerReleased { position: new_position, button }),
+            _ => {}
+        }
+    }
+
+    // Wheel "buttons" are pressed and released for every click
+    for (bit, delta_x, delta_y) in [(3, 0.0, WHEEL_STEP), (4, 0.0, -WHEEL_STEP), (5, WHEEL_STEP, 0.0), (6, -WHEEL_STEP, 0.0)] {
+        if mask & (1 << bit) != 0 && *buttons & (1 << bit) == 0 {
+            events.push(WindowEvent::PointerScrolled { position: new_position, delta_x, delta_y });
+        }
+    }
+
+    *buttons = mask;
+    events
+}
+
+fn keysym_text(keysym: u32) -> Option<SharedString> {
+    let key = match keysym {
+        // Latin-1 keysyms are the character itself
+        0x20..=0x7e | 0xa0..=0xff => return char::from_u32(keysym).map(|c| SharedString::from(c.to_string())),
+        0xff08 => Key::Backspace,
+        0xff09 => Key::Tab,
+        0xfe20 => Key::Backtab,
+        0xff0d | 0xff8d => Key::Return,
+        0xff1b => Key::Escape,
+        0xffff => Key::Delete,
+        0xff50 => Key::Home,
+        0xff51 => Key::LeftArrow,
+        0xff52 => Key::UpArrow,
+        0xff53 => Key::RightArrow,
+        0xff54 => Key::DownArrow,
+        0xff55 => Key::PageUp,
+        0xff56 => Key::PageDown,
+        0xff57 => Key::End,
+        0xff63 => Key::Insert,
+        0xffe1 => Key::Shift,
+        0xffe2 => Key::ShiftR,
+        0xffe3 => Key::Control,
+        0xffe4 => Key::ControlR,
+        0xffe9 => Key::Alt,
+        0xfe03 => Key::AltGr,
+        _ => return None,
+    };
+
+    Some(key.into())
+}
@@ -0,0 +1,49 @@
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    time::Duration,
+};
+
+/// How long `connection_loop` waits between reconnect attempts. The delay
+/// grows exponentially up to `max_delay`, and is randomly shortened by up to
+/// `jitter` of itself so several screens don't hammer a restarting Moonraker
+/// in lockstep.
+#[derive(Debug, Clone)]
+pub struct BackoffPolicy {
+    pub initial_delay: Duration,
+    pub max_delay: Duration,
+    pub multiplier: f64,
+    /// 0.0 for none, 1.0 for anywhere between zero and the full delay.
+    pub jitter: f64,
+}
+
+impl Default for BackoffPolicy {
+    fn default() -> Self {
+        Self {
+            initial_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(60),
+            multiplier: 2.0,
+            jitter: 0.3,
+        }
+    }
+}
+
+impl BackoffPolicy {
+    /// Delay before retrying after `attempt` consecutive failures, counting from 1.
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(i32::MAX as u32) as i32;
+        let delay = (self.initial_delay.as_secs_f64() * self.multiplier.powi(exponent))
+            .min(self.max_delay.as_secs_f64());
+        let jitter = delay * self.jitter.clamp(0.0, 1.0) * random_fraction();
+
+        Duration::from_secs_f64((delay - jitter).max(0.0))
+    }
+}
+
+/// Between 0 and 1. `RandomState` is seeded randomly per instance, which is
+/// plenty for spreading out retries without pulling in an RNG.
+fn random_fraction() -> f64 {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u8(0);
+    hasher.finish() as f64 / u64::MAX as f64
+}
@@ -1,3 +1,7 @@
+use std::collections::HashMap;
+
+use tokio::sync::watch;
+
 use crate::{
     connector::{read_deserialize::OptionalPrinterEvent, websocket_read::PrinterEvent},
     printer_objects::*,
@@ -10,8 +14,9 @@ pub struct Cache {
     pub motion_report: MotionReport,
     pub gcode_move: GcodeMove,
     pub toolhead: Toolhead,
-    pub extruder: Extruder,
+    pub extruders: Vec<NamedExtruder>,
     pub heater_bed: HeaterBed,
+    pub heater_generics: Vec<NamedHeaterGeneric>,
     pub fan: Fan,
     pub idle_timeout: IdleTimeout,
     pub virtual_sdcard: VirtualSdcard,
@@ -22,6 +27,141 @@ pub struct Cache {
     pub filament_switch_sensors: Vec<NamedFilamentSwitchSensor>,
     pub output_pins: Vec<NamedOutputPin>,
     pub exclude_object: ExcludeObject,
+    pub configfile: Configfile,
+    /// Heater presets by object name, applied to heaters as they show up.
+    pub heater_presets: HashMap<String, TemperatureConfiguration>,
+}
+
+/// A watch channel per object in the cache, kept by the connection. They
+/// outlive the cache of a single connection, so a receiver always holds the
+/// latest value and can't lag behind like the event channel can.
+#[derive(Debug, Clone)]
+pub(crate) struct CacheWatch {
+    pub(crate) webhooks: watch::Sender<Webhooks>,
+    pub(crate) motion_report: watch::Sender<MotionReport>,
+    pub(crate) gcode_move: watch::Sender<GcodeMove>,
+    pub(crate) toolhead: watch::Sender<Toolhead>,
+    pub(crate) extruders: watch::Sender<Vec<NamedExtruder>>,
+    pub(crate) heater_bed: watch::Sender<HeaterBed>,
+    pub(crate) heater_generics: watch::Sender<Vec<NamedHeaterGeneric>>,
+    pub(crate) fan: watch::Sender<Fan>,
+    pub(crate) idle_timeout: watch::Sender<IdleTimeout>,
+    pub(crate) virtual_sdcard: watch::Sender<VirtualSdcard>,
+    pub(crate) print_stats: watch::Sender<PrintStats>,
+    pub(crate) display_status: watch::Sender<DisplayStatus>,
+    pub(crate) temperature_sensors: watch::Sender<Vec<NamedTemperatureSensor>>,
+    pub(crate) temperature_fans: watch::Sender<Vec<NamedTemperatureFan>>,
+    pub(crate) filament_switch_sensors: watch::Sender<Vec<NamedFilamentSwitchSensor>>,
+    pub(crate) output_pins: watch::Sender<Vec<NamedOutputPin>>,
+    pub(crate) exclude_object: watch::Sender<ExcludeObject>,
+    pub(crate) configfile: watch::Sender<Configfile>,
+}
+
+impl CacheWatch {
+    pub(crate) fn new(cache: &Cache) -> Self {
+        Self {
+            webhooks: watch::Sender::new(cache.webhooks.clone()),
+            motion_report: watch::Sender::new(cache.motion_report.clone()),
+            gcode_move: watch::Sender::new(cache.gcode_move.clone()),
+            toolhead: watch::Sender::new(cache.toolhead.clone()),
+            extruders: watch::Sender::new(cache.extruders.clone()),
+            heater_bed: watch::Sender::new(cache.heater_bed.clone()),
+            heater_generics: watch::Sender::new(cache.heater_generics.clone()),
+            fan: watch::Sender::new(cache.fan.clone()),
+            idle_timeout: watch::Sender::new(cache.idle_timeout.clone()),
+            virtual_sdcard: watch::Sender::new(cache.virtual_sdcard.clone()),
+            print_stats: watch::Sender::new(cache.print_stats.clone()),
+            display_status: watch::Sender::new(cache.display_status.clone()),
+            temperature_sensors: watch::Sender::new(cache.temperature_sensors.clone()),
+            temperature_fans: watch::Sender::new(cache.temperature_fans.clone()),
+            filament_switch_sensors: watch::Sender::new(cache.filament_switch_sensors.clone()),
+            output_pins: watch::Sender::new(cache.output_pins.clone()),
+            exclude_object: watch::Sender::new(cache.exclude_object.clone()),
+            configfile: watch::Sender::new(cache.configfile.clone()),
+        }
+    }
+
+    /// Sends every object, e.g. once a new connection filled a fresh cache.
+    pub(crate) fn publish_all(&self, cache: &Cache) {
+        self.webhooks.send_replace(cache.webhooks.clone());
+        self.motion_report.send_replace(cache.motion_report.clone());
+        self.gcode_move.send_replace(cache.gcode_move.clone());
+        self.toolhead.send_replace(cache.toolhead.clone());
+        self.extruders.send_replace(cache.extruders.clone());
+        self.heater_bed.send_replace(cache.heater_bed.clone());
+        self.heater_generics.send_replace(cache.heater_generics.clone());
+        self.fan.send_replace(cache.fan.clone());
+        self.idle_timeout.send_replace(cache.idle_timeout.clone());
+        self.virtual_sdcard.send_replace(cache.virtual_sdcard.clone());
+        self.print_stats.send_replace(cache.print_stats.clone());
+        self.display_status.send_replace(cache.display_status.clone());
+        self.temperature_sensors.send_replace(cache.temperature_sensors.clone());
+        self.temperature_fans.send_replace(cache.temperature_fans.clone());
+        self.filament_switch_sensors.send_replace(cache.filament_switch_sensors.clone());
+        self.output_pins.send_replace(cache.output_pins.clone());
+        self.exclude_object.send_replace(cache.exclude_object.clone());
+        self.configfile.send_replace(cache.configfile.clone());
+    }
+
+    /// Sends the object `event` completed. Named objects send the whole list.
+    pub(crate) fn publish(&self, event: &PrinterEvent, cache: &Cache) {
+        match event {
+            PrinterEvent::Webhooks(webhooks) => {
+                self.webhooks.send_replace(webhooks.clone());
+            }
+            PrinterEvent::MotionReport(motion_report) => {
+                self.motion_report.send_replace(motion_report.clone());
+            }
+            PrinterEvent::GcodeMove(gcode_move) => {
+                self.gcode_move.send_replace(gcode_move.clone());
+            }
+            PrinterEvent::Toolhead(toolhead) => {
+                self.toolhead.send_replace(toolhead.clone());
+            }
+            PrinterEvent::Extruder(_) => {
+                self.extruders.send_replace(cache.extruders.clone());
+            }
+            PrinterEvent::HeaterBed(heater_bed) => {
+                self.heater_bed.send_replace(heater_bed.clone());
+            }
+            PrinterEvent::HeaterGeneric(_) => {
+                self.heater_generics.send_replace(cache.heater_generics.clone());
+            }
+            PrinterEvent::Fan(fan) => {
+                self.fan.send_replace(fan.clone());
+            }
+            PrinterEvent::IdleTimeout(idle_timeout) => {
+                self.idle_timeout.send_replace(idle_timeout.clone());
+            }
+            PrinterEvent::VirtualSdcard(virtual_sdcard) => {
+                self.virtual_sdcard.send_replace(virtual_sdcard.clone());
+            }
+            PrinterEvent::PrintStats(print_stats) => {
+                self.print_stats.send_replace(print_stats.clone());
+            }
+            PrinterEvent::DisplayStatus(display_status) => {
+                self.display_status.send_replace(display_status.clone());
+            }
+            PrinterEvent::TemperatureSensor(_) => {
+                self.temperature_sensors.send_replace(cache.temperature_sensors.clone());
+            }
+            PrinterEvent::TemperatureFan(_) => {
+                self.temperature_fans.send_replace(cache.temperature_fans.clone());
+            }
+            PrinterEvent::FilamentSwitchSensor(_) => {
+                self.filament_switch_sensors.send_replace(cache.filament_switch_sensors.clone());
+            }
+            PrinterEvent::OutputPin(_) => {
+                self.output_pins.send_replace(cache.output_pins.clone());
+            }
+            PrinterEvent::ExcludeObject(exclude_object) => {
+                self.exclude_object.send_replace(exclude_object.clone());
+            }
+            PrinterEvent::Configfile(configfile) => {
+                self.configfile.send_replace(configfile.clone());
+            }
+        }
+    }
 }
 
 impl Cache {
@@ -29,6 +169,34 @@ impl Cache {
         Self::default()
     }
 
+    pub fn set_heater_presets(&mut self, name: &str, configuration: TemperatureConfiguration) {
+        if name == "heater_bed" {
+            self.heater_bed.configuration = configuration.clone();
+        }
+
+        for extruder in self.extruders.iter_mut().filter(|extruder| extruder.name == name) {
+            extruder.extruder.configuration = configuration.clone();
+        }
+
+        for heater in self.heater_generics.iter_mut().filter(|heater| heater.name == name) {
+            heater.heater.configuration = configuration.clone();
+        }
+
+        for fan in self.temperature_fans.iter_mut().filter(|fan| fan.name == name) {
+            fan.fan.configuration = configuration.clone();
+        }
+
+        self.heater_presets.insert(name.to_string(), configuration);
+    }
+
+    /// The extruder the toolhead is currently using, as reported by `toolhead.extruder`.
+    pub fn active_extruder(&self) -> Option<&NamedExtruder> {
+        self.extruders
+            .iter()
+            .find(|extruder| extruder.name == self.toolhead.extruder)
+    }
+
+    /// Applies a status update, returning the whole object it updated.
     pub fn complete_event(&mut self, event: OptionalPrinterEvent) -> PrinterEvent {
         match event {
             OptionalPrinterEvent::Webhooks(webhooks) => {
@@ -47,14 +215,70 @@ impl Cache {
                 self.toolhead.overlay(toolhead);
                 PrinterEvent::Toolhead(self.toolhead.clone())
             }
-            OptionalPrinterEvent::Extruder(extruder) => {
-                self.extruder.overlay(extruder);
-                PrinterEvent::Extruder(self.extruder.clone())
+            OptionalPrinterEvent::Extruder(named_extruder) => {
+                let index = self
+                    .extruders
+                    .iter()
+                    .position(|extruder| extruder.name == named_extruder.name);
+
+                let extruder = match index {
+                    Some(index) => {
+                        self.extruders[index]
+                            .extruder
+                            .overlay(named_extruder.extruder);
+                        self.extruders[index].clone()
+                    }
+                    None => {
+                        let mut new_extruder = Extruder::default();
+                        if let Some(configuration) = self.heater_presets.get(&named_extruder.name) {
+                            new_extruder.configuration = configuration.clone();
+                        }
+                        new_extruder.overlay(named_extruder.extruder);
+                        let new_named_extruder = NamedExtruder {
+                            name: named_extruder.name,
+                            extruder: new_extruder,
+                        };
+                        self.extruders.push(new_named_extruder.clone());
+                        new_named_extruder
+                    }
+                };
+
+                PrinterEvent::Extruder(extruder)
             }
             OptionalPrinterEvent::HeaterBed(heater_bed) => {
                 self.heater_bed.overlay(heater_bed);
                 PrinterEvent::HeaterBed(self.heater_bed.clone())
             }
+            OptionalPrinterEvent::HeaterGeneric(named_heater) => {
+                let index = self
+                    .heater_generics
+                    .iter()
+                    .position(|heater| heater.name == named_heater.name);
+
+                let heater = match index {
+                    Some(index) => {
+                        self.heater_generics[index]
+                            .heater
+                            .overlay(named_heater.heater);
+                        self.heater_generics[index].clone()
+                    }
+                    None => {
+                        let mut new_heater = HeaterGeneric::default();
+                        if let Some(configuration) = self.heater_presets.get(&named_heater.name) {
+                            new_heater.configuration = configuration.clone();
+                        }
+                        new_heater.overlay(named_heater.heater);
+                        let new_named_heater = NamedHeaterGeneric {
+                            name: named_heater.name,
+                            heater: new_heater,
+                        };
+                        self.heater_generics.push(new_named_heater.clone());
+                        new_named_heater
+                    }
+                };
+
+                PrinterEvent::HeaterGeneric(heater)
+            }
             OptionalPrinterEvent::Fan(fan) => {
                 self.fan.overlay(fan);
                 PrinterEvent::Fan(self.fan.clone())
@@ -115,6 +339,9 @@ impl Cache {
                     }
                     None => {
                         let mut new_fan = TemperatureFan::default();
+                        if let Some(configuration) = self.heater_presets.get(&named_fan.name) {
+                            new_fan.configuration = configuration.clone();
+                        }
                         new_fan.overlay(named_fan.fan);
                         let new_named_fan = NamedTemperatureFan {
                             name: named_fan.name,
@@ -183,6 +410,10 @@ impl Cache {
                 self.exclude_object.overlay(exclude_object);
                 PrinterEvent::ExcludeObject(self.exclude_object.clone())
             }
+            OptionalPrinterEvent::Configfile(configfile) => {
+                self.configfile.overlay(configfile);
+                PrinterEvent::Configfile(self.configfile.clone())
+            }
         }
     }
 }
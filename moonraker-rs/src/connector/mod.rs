@@ -1,3 +1,5 @@
 pub mod read_deserialize;
+pub mod recording;
+pub mod replay;
 pub mod websocket_read;
 pub mod websocket_write;
@@ -1,6 +1,9 @@
 use serde::Deserialize;
 
-use crate::printer_objects::*;
+use crate::{
+    printer_objects::*,
+    requests::{HistoryChangedNotification, JobQueueChangedNotification},
+};
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct MoonrakerErrorReplyRaw
@@ -75,6 +78,16 @@ impl<'de> Deserialize<'de> for JsonRpcNotification {
                 MoonrakerEventParameters::NotifyProcessStatisticsUpdate(parsed_params)
             }
             "notify_klippy_disconnected" => MoonrakerEventParameters::NotifyKlippyDisconnect,
+            "notify_history_changed" => {
+                let parsed_params = serde_json::from_value(helper.params.unwrap()[0].take())
+                    .map_err(serde::de::Error::custom)?;
+                MoonrakerEventParameters::NotifyHistoryChanged(parsed_params)
+            }
+            "notify_job_queue_changed" => {
+                let parsed_params = serde_json::from_value(helper.params.unwrap()[0].take())
+                    .map_err(serde::de::Error::custom)?;
+                MoonrakerEventParameters::NotifyJobQueueChanged(parsed_params)
+            }
             _ => return Err(serde::de::Error::custom("Unknown method")),
         };
 
@@ -91,6 +104,8 @@ pub enum MoonrakerEventParameters {
     NotifyStatusUpdate(MoonrakerEventNotifyStatusUpdate),
     NotifyProcessStatisticsUpdate(MoonrakerNotifyProcStatUpdate),
     NotifyKlippyDisconnect,
+    NotifyHistoryChanged(HistoryChangedNotification),
+    NotifyJobQueueChanged(JobQueueChangedNotification),
 }
 
 #[derive(Debug)]
@@ -164,9 +179,14 @@ impl<'de> Deserialize<'de> for MoonrakerEventNotifyStatusUpdate {
                 "toolhead" => OptionalPrinterEvent::Toolhead(
                     serde_json::from_value(object_value).map_err(serde::de::Error::custom)?,
                 ),
-                "extruder" => OptionalPrinterEvent::Extruder(
-                    serde_json::from_value(object_value).map_err(serde::de::Error::custom)?,
-                ),
+                // extruder, extruder1, extruder2, ... but not extruder_stepper
+                name if name.strip_prefix("extruder").is_some_and(|index| index.chars().all(|c| c.is_ascii_digit())) => {
+                    OptionalPrinterEvent::Extruder(NamedOptionalExtruder {
+                        name: name.to_string(),
+                        extruder: serde_json::from_value(object_value)
+                            .map_err(serde::de::Error::custom)?,
+                    })
+                }
                 "heater_bed" => OptionalPrinterEvent::HeaterBed(
                     serde_json::from_value(object_value).map_err(serde::de::Error::custom)?,
                 ),
@@ -185,6 +205,13 @@ impl<'de> Deserialize<'de> for MoonrakerEventNotifyStatusUpdate {
                 "display_status" => OptionalPrinterEvent::DisplayStatus(
                     serde_json::from_value(object_value).map_err(serde::de::Error::custom)?,
                 ),
+                "heater_generic" => {
+                    OptionalPrinterEvent::HeaterGeneric(NamedOptionalHeaterGeneric {
+                        name: last_part_of_name.to_string(),
+                        heater: serde_json::from_value(object_value)
+                            .map_err(serde::de::Error::custom)?,
+                    })
+                }
                 "temperature_sensor" => {
                     OptionalPrinterEvent::TemperatureSensor(NamedOptionalTemperatureSensor {
                         name: last_part_of_name.to_string(),
@@ -213,6 +240,9 @@ impl<'de> Deserialize<'de> for MoonrakerEventNotifyStatusUpdate {
                 "exclude_object" => OptionalPrinterEvent::ExcludeObject(
                     serde_json::from_value(object_value).map_err(serde::de::Error::custom)?,
                 ),
+                "configfile" => OptionalPrinterEvent::Configfile(
+                    serde_json::from_value(object_value).map_err(serde::de::Error::custom)?,
+                ),
                 _ => {
                     //eprintln!("Unknown object name: {}", object_name);
                     continue; // Skip unknown object names
@@ -232,8 +262,9 @@ pub enum OptionalPrinterEvent {
     MotionReport(OptionalMotionReport),
     GcodeMove(OptionalGcodeMove),
     Toolhead(OptionalToolhead),
-    Extruder(OptionalExtruder),
+    Extruder(NamedOptionalExtruder),
     HeaterBed(OptionalHeaterBed),
+    HeaterGeneric(NamedOptionalHeaterGeneric),
     Fan(OptionalFan),
     IdleTimeout(OptionalIdleTimeout),
     VirtualSdcard(OptionalVirtualSdcard),
@@ -244,4 +275,5 @@ pub enum OptionalPrinterEvent {
     FilamentSwitchSensor(NamedOptionalFilamentSwitchSensor),
     OutputPin(NamedOptionalOutputPin),
     ExcludeObject(OptionalExcludeObject),
+    Configfile(OptionalConfigfile),
 }
@@ -0,0 +1,128 @@
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, BufWriter, Write},
+    path::Path,
+    sync::mpsc::{self, Receiver, Sender},
+    thread::{self, JoinHandle},
+    time::Instant,
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::error::Error;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    /// Moonraker -> us
+    Inbound,
+    /// Us -> Moonraker
+    Outbound,
+}
+
+/// One websocket message in a recording, stored as a line of JSON.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RecordedFrame {
+    /// Seconds since the recording started.
+    pub time: f64,
+    pub direction: Direction,
+    pub message: Value,
+}
+
+/// Appends every websocket message to a JSONL file, so a session can be
+/// played back later with `ReplayTransport`. The file is written on its own
+/// thread, dropping the recorder waits for everything to be written.
+pub struct Recorder {
+    sender: Option<Sender<RecordedFrame>>,
+    writer: Option<JoinHandle<()>>,
+    started: Instant,
+}
+
+impl Recorder {
+    pub fn create(path: &Path) -> Result<Self, Error> {
+        let file = File::create(path)?;
+        let (sender, receiver) = mpsc::channel();
+
+        let writer = thread::Builder::new()
+            .name(String::from("recorder"))
+            .spawn(move || write_frames(receiver, BufWriter::new(file)))?;
+
+        Ok(Self {
+            sender: Some(sender),
+            writer: Some(writer),
+            started: Instant::now(),
+        })
+    }
+
+    pub fn record(&self, direction: Direction, payload: &str) {
+        let frame = RecordedFrame {
+            time: self.started.elapsed().as_secs_f64(),
+            direction,
+            // Keep what we couldn't parse too, that's usually the interesting part
+            message: serde_json::from_str(payload).unwrap_or_else(|_| Value::String(payload.to_string())),
+        };
+
+        if let Some(sender) = &self.sender {
+            // Only fails once the writer gave up, which it already reported
+            let _ = sender.send(frame);
+        }
+    }
+}
+
+impl Drop for Recorder {
+    fn drop(&mut self) {
+        // Closing the channel ends the writer once it wrote what is queued
+        self.sender.take();
+
+        if let Some(writer) = self.writer.take() {
+            let _ = writer.join();
+        }
+    }
+}
+
+/// Flushes whenever it caught up with the queue, so little is lost on a crash
+/// without flushing for every frame.
+fn write_frames(receiver: Receiver<RecordedFrame>, mut writer: BufWriter<File>) {
+    let write_frame = |writer: &mut BufWriter<File>, frame: &RecordedFrame| {
+        serde_json::to_writer(&mut *writer, frame)
+            .map_err(std::io::Error::from)
+            .and_then(|_| writer.write_all(b"\n"))
+    };
+
+    while let Ok(frame) = receiver.recv() {
+        let result = std::iter::once(frame)
+            .chain(receiver.try_iter())
+            .try_for_each(|frame| write_frame(&mut writer, &frame))
+            .and_then(|_| writer.flush());
+
+        if let Err(e) = result {
+            eprintln!("Failed to record websocket messages, stopping the recording: {}", e);
+            return;
+        }
+    }
+}
+
+/// A session written by `Recorder`.
+#[derive(Debug, Clone, Default)]
+pub struct Recording {
+    pub frames: Vec<RecordedFrame>,
+}
+
+impl Recording {
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        let reader = BufReader::new(File::open(path)?);
+        let mut frames = Vec::new();
+
+        for line in reader.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            frames.push(serde_json::from_str(&line)?);
+        }
+
+        Ok(Self { frames })
+    }
+}
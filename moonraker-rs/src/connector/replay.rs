@@ -0,0 +1,166 @@
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::Duration,
+};
+
+use serde_json::{Value, json};
+use tokio::{
+    sync::{
+        Mutex,
+        broadcast::{Receiver, Sender, error::RecvError},
+    },
+    time::{Instant, sleep_until},
+};
+
+use crate::{
+    cache::{Cache, CacheWatch},
+    connector::{
+        recording::{Direction, RecordedFrame, Recording},
+        websocket_read::handle_text_message,
+        websocket_write::{MoonrakerRequest, OutboundMessage},
+    },
+    error::Error,
+    moonraker_connection::WebsocketEvent,
+};
+
+/// Plays a recording back in place of a websocket. Notifications are replayed
+/// on the recorded timeline, requests are answered with the recorded reply to
+/// the same request.
+pub struct ReplayTransport {
+    recording: Recording,
+    speed: f64,
+    /// Index of the next frame on the timeline. Kept across reconnects, so a
+    /// recorded Klippy disconnect resumes where it left off.
+    position: AtomicUsize,
+}
+
+impl ReplayTransport {
+    pub fn new(recording: Recording, speed: f32) -> Self {
+        Self {
+            recording,
+            speed: speed.max(0.01) as f64,
+            position: AtomicUsize::new(0),
+        }
+    }
+
+    /// The recorded reply to the request most like `request`, re-addressed to
+    /// its id. Recorded requests at the current position are preferred, so
+    /// replies follow the session along.
+    fn reply_to(&self, request: &MoonrakerRequest) -> Value {
+        let params = request.args.clone().unwrap_or(Value::Null);
+        let same_method = |frame: &RecordedFrame| {
+            frame.direction == Direction::Outbound && frame.message["method"] == request.method.as_str()
+        };
+
+        let reply = self
+            .find_reply(|frame| same_method(frame) && frame.message["params"] == params)
+            .or_else(|| self.find_reply(same_method));
+
+        match reply {
+            Some(mut reply) => {
+                reply["id"] = json!(request.id);
+                reply
+            }
+            None => json!({
+                "jsonrpc": "2.0",
+                "error": { "code": 404, "message": format!("{} is not in the recording", request.method) },
+                "id": request.id,
+            }),
+        }
+    }
+
+    fn find_reply(&self, matches: impl Fn(&RecordedFrame) -> bool) -> Option<Value> {
+        let frames = &self.recording.frames;
+        let position = self.position.load(Ordering::Relaxed).min(frames.len());
+
+        let request_index = (position..frames.len())
+            .find(|&index| matches(&frames[index]))
+            .or_else(|| (0..position).rev().find(|&index| matches(&frames[index])))?;
+        let id = &frames[request_index].message["id"];
+
+        frames[request_index..]
+            .iter()
+            .find(|frame| {
+                frame.direction == Direction::Inbound
+                    && frame.message.get("method").is_none()
+                    && &frame.message["id"] == id
+            })
+            .map(|frame| frame.message.clone())
+    }
+}
+
+/// Replays recorded notifications from the current position, until the
+/// recording ends or Klippy disconnects in it. Returns why it stopped.
+pub(crate) async fn replay_reader_loop(
+    replay: Arc<ReplayTransport>,
+    inbound_sender: Sender<Arc<WebsocketEvent>>,
+    outbound_sender: Sender<Arc<OutboundMessage>>,
+    cache: Arc<Mutex<Cache>>,
+    cache_watch: CacheWatch,
+) -> String {
+    let frames = &replay.recording.frames;
+    let start_position = replay.position.load(Ordering::Relaxed);
+    let Some(start_time) = frames.get(start_position).map(|frame| frame.time) else {
+        return "Replay finished".to_string();
+    };
+    let started = Instant::now();
+
+    for (index, frame) in frames.iter().enumerate().skip(start_position) {
+        // Replies are handed out when they are asked for
+        if frame.direction != Direction::Inbound || frame.message.get("method").is_none() {
+            continue;
+        }
+
+        let offset = ((frame.time - start_time) / replay.speed).max(0.0);
+        sleep_until(started + Duration::from_secs_f64(offset)).await;
+        replay.position.store(index + 1, Ordering::Relaxed);
+
+        if let Err(e) = handle_text_message(&frame.message.to_string(), &inbound_sender, &outbound_sender, &cache, &cache_watch).await {
+            eprintln!("Failed to replay websocket event: {:?}", e);
+
+            if let Error::BreakError = e {
+                return "Klippy disconnected in the recording".to_string();
+            }
+        }
+    }
+
+    replay.position.store(frames.len(), Ordering::Relaxed);
+    println!("Replay finished");
+    "Replay finished".to_string()
+}
+
+/// Answers requests from the recording instead of sending them.
+pub(crate) async fn replay_writer_loop(
+    replay: Arc<ReplayTransport>,
+    mut outbound_receiver: Receiver<Arc<OutboundMessage>>,
+    inbound_sender: Sender<Arc<WebsocketEvent>>,
+    outbound_sender: Sender<Arc<OutboundMessage>>,
+    cache: Arc<Mutex<Cache>>,
+    cache_watch: CacheWatch,
+) {
+    loop {
+        let message = match outbound_receiver.recv().await {
+            Ok(message) => message,
+            Err(RecvError::Lagged(skipped)) => {
+                eprintln!("Replay fell behind, {} requests go unanswered", skipped);
+                continue;
+            }
+            Err(RecvError::Closed) => break,
+        };
+
+        let request = match &*message {
+            OutboundMessage::EndLoop => break,
+            // Pongs and close replies have nowhere to go
+            OutboundMessage::RawFrame(_) => continue,
+            OutboundMessage::MoonrakerRequest(request) => request,
+        };
+
+        let reply = replay.reply_to(request).to_string();
+        if let Err(e) = handle_text_message(&reply, &inbound_sender, &outbound_sender, &cache, &cache_watch).await {
+            eprintln!("Failed to replay reply to {}: {:?}", request.method, e);
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
 use fastwebsockets::{FragmentCollectorRead, Frame, OpCode};
 use hyper::upgrade::Upgraded;
@@ -6,19 +6,22 @@ use hyper_util::rt::TokioIo;
 use tokio::{
     io::ReadHalf,
     sync::{Mutex, broadcast::Sender},
+    time::timeout,
 };
 
 use crate::{
-    cache::Cache,
+    cache::{Cache, CacheWatch},
     connector::{
         read_deserialize::{
             JsonRpcResponse, MoonrakerEventParameters, MoonrakerNotifyProcStatUpdate,
         },
+        recording::{Direction, Recorder},
         websocket_write::OutboundMessage,
     },
     error::Error,
     moonraker_connection::{MoonrakerErrorReply, MoonrakerReply, WebsocketEvent},
     printer_objects::*,
+    requests::{HistoryChangedNotification, JobQueueChangedNotification},
 };
 
 pub(crate) async fn moonraker_reader_connection_loop(
@@ -26,10 +29,20 @@ pub(crate) async fn moonraker_reader_connection_loop(
     outbound_sender: Sender<Arc<OutboundMessage>>,
     websocket_reader: FragmentCollectorRead<ReadHalf<TokioIo<Upgraded>>>,
     cache: Arc<Mutex<Cache>>,
-) {
-    let mut data =
-        MoonrakerConnectionReadLoop::new(inbound_sender, outbound_sender, websocket_reader, cache);
-    data.connection_loop().await;
+    cache_watch: CacheWatch,
+    recorder: Option<Arc<Recorder>>,
+    keepalive_timeout: Duration,
+) -> String {
+    let mut data = MoonrakerConnectionReadLoop::new(
+        inbound_sender,
+        outbound_sender,
+        websocket_reader,
+        cache,
+        cache_watch,
+        recorder,
+        keepalive_timeout,
+    );
+    data.connection_loop().await
 }
 
 struct MoonrakerConnectionReadLoop {
@@ -37,6 +50,10 @@ struct MoonrakerConnectionReadLoop {
     outbound_sender: Sender<Arc<OutboundMessage>>,
     websocket_reader: FragmentCollectorRead<ReadHalf<TokioIo<Upgraded>>>,
     cache: Arc<Mutex<Cache>>,
+    cache_watch: CacheWatch,
+    recorder: Option<Arc<Recorder>>,
+    keepalive_timeout: Duration,
+    stop_reason: Option<String>,
 }
 
 impl MoonrakerConnectionReadLoop {
@@ -45,48 +62,56 @@ impl MoonrakerConnectionReadLoop {
         outbound_sender: Sender<Arc<OutboundMessage>>,
         websocket_reader: FragmentCollectorRead<ReadHalf<TokioIo<Upgraded>>>,
         cache: Arc<Mutex<Cache>>,
+        cache_watch: CacheWatch,
+        recorder: Option<Arc<Recorder>>,
+        keepalive_timeout: Duration,
     ) -> Self {
         Self {
             inbound_sender,
             outbound_sender,
             websocket_reader,
             cache,
+            cache_watch,
+            recorder,
+            keepalive_timeout,
+            stop_reason: None,
         }
     }
 
-    pub async fn connection_loop(&mut self) {
+    /// Reads until the connection is lost, returning why.
+    pub async fn connection_loop(&mut self) -> String {
         loop {
             let outbound_sender = self.outbound_sender.clone();
-            let mut frame = match self
-                .websocket_reader
-                .read_frame(&mut move |x| {
+            let read = timeout(
+                self.keepalive_timeout,
+                self.websocket_reader.read_frame(&mut move |x| {
                     outbound_sender
                         .send(Arc::new(OutboundMessage::RawFrame(Mutex::new(Some(x)))))
                         .expect("Failed to internally send a raw frame event");
 
                     async move { Ok::<(), std::io::Error>(()) }
-                })
-                .await
-            {
-                Ok(frame) => frame,
-                Err(e) => {
-                    // Assume connection lost
-                    self.inbound_sender
-                        .send(Arc::new(WebsocketEvent::ApplicationError(e.to_string())))
-                        .expect("Failed to internally send an error event");
-                    self.inbound_sender
-                        .send(Arc::new(WebsocketEvent::Disconnected))
-                        .expect("Failed to internally send a disconnect event");
-                    self.outbound_sender
-                        .send(Arc::new(OutboundMessage::EndLoop))
-                        .expect("Failed to internally send an endloop event");
-                    break;
+                }),
+            )
+            .await;
+
+            let mut frame = match read {
+                Ok(Ok(frame)) => frame,
+                // Assume connection lost
+                Ok(Err(e)) => return self.connection_lost(e.to_string()),
+                // Not even a pong, the connection is most likely half-open
+                Err(_) => {
+                    return self.connection_lost(format!(
+                        "No reply from Moonraker in {:?}",
+                        self.keepalive_timeout
+                    ));
                 }
             };
 
             if let Err(e) = match frame.opcode {
                 OpCode::Close => self.on_frame_close(&mut frame).await,
                 OpCode::Text => self.on_frame_text(&mut frame).await,
+                // Pings are answered by the reader, pongs only show the connection is alive
+                OpCode::Ping | OpCode::Pong => Ok(()),
                 _ => Err(Error::Unknown(format!(
                     "Received unsupported websocket frame {:?}",
                     frame.opcode
@@ -102,6 +127,24 @@ impl MoonrakerConnectionReadLoop {
                 }
             }
         }
+
+        self.stop_reason
+            .take()
+            .unwrap_or_else(|| "Klippy disconnected from Moonraker".to_string())
+    }
+
+    fn connection_lost(&mut self, reason: String) -> String {
+        self.inbound_sender
+            .send(Arc::new(WebsocketEvent::ApplicationError(reason.clone())))
+            .expect("Failed to internally send an error event");
+        self.inbound_sender
+            .send(Arc::new(WebsocketEvent::Disconnected))
+            .expect("Failed to internally send a disconnect event");
+        self.outbound_sender
+            .send(Arc::new(OutboundMessage::EndLoop))
+            .expect("Failed to internally send an endloop event");
+
+        reason
     }
 
     pub async fn on_frame_close(&mut self, _: &mut Frame<'static>) -> Result<(), Error> {
@@ -112,7 +155,8 @@ impl MoonrakerConnectionReadLoop {
             .send(Arc::new(OutboundMessage::EndLoop))
             .expect("Failed to internally send an endloop event");
 
-        Ok(())
+        self.stop_reason = Some("Moonraker closed the connection".to_string());
+        Err(Error::BreakError)
     }
 
     pub async fn on_frame_text(&mut self, frame: &mut Frame<'static>) -> Result<(), Error> {
@@ -121,72 +165,102 @@ impl MoonrakerConnectionReadLoop {
         //#[cfg(debug_assertions)]
         //println!("Received text frame: {}", payload);
 
-        let data = serde_json::from_str::<JsonRpcResponse>(&payload)?;
-
-        match data {
-            JsonRpcResponse::MethodResponse(method_response) => {
-                if let Some(error) = &method_response.error {
-                    eprintln!(
-                        "Received error in method response: {:?}",
-                        method_response.error
-                    );
-
-                    let reply = MoonrakerErrorReply {
-                        code: error.code,
-                        message: error.message.clone(),
-                        id: method_response.id,
-                    };
-
-                    self.inbound_sender
-                        .send(Arc::new(WebsocketEvent::MoonrakerErrorReply(reply)))
-                        .expect("Failed to internally send a moonraker error reply event");
-                } else {
-                    let reply = MoonrakerReply {
-                        id: method_response.id,
-                        result: method_response.result.unwrap_or(serde_json::json!(null)),
-                    };
-                    self
-                        .inbound_sender
-                        .send(Arc::new(WebsocketEvent::MoonrakerReply(reply)))
-                        .expect("Failed to internally send a moonraker reply event");
-                }
+        if let Some(recorder) = &self.recorder {
+            recorder.record(Direction::Inbound, &payload);
+        }
+
+        handle_text_message(
+            &payload,
+            &self.inbound_sender,
+            &self.outbound_sender,
+            &self.cache,
+            &self.cache_watch,
+        )
+        .await
+    }
+}
+
+/// Handles one JSON-RPC message from Moonraker, whether it came from the
+/// websocket or a replayed recording.
+pub(crate) async fn handle_text_message(
+    payload: &str,
+    inbound_sender: &Sender<Arc<WebsocketEvent>>,
+    outbound_sender: &Sender<Arc<OutboundMessage>>,
+    cache: &Mutex<Cache>,
+    cache_watch: &CacheWatch,
+) -> Result<(), Error> {
+    let data = serde_json::from_str::<JsonRpcResponse>(payload)?;
+
+    match data {
+        JsonRpcResponse::MethodResponse(method_response) => {
+            if let Some(error) = &method_response.error {
+                eprintln!(
+                    "Received error in method response: {:?}",
+                    method_response.error
+                );
+
+                let reply = MoonrakerErrorReply {
+                    code: error.code,
+                    message: error.message.clone(),
+                    id: method_response.id,
+                };
+
+                inbound_sender
+                    .send(Arc::new(WebsocketEvent::MoonrakerErrorReply(reply)))
+                    .expect("Failed to internally send a moonraker error reply event");
+            } else {
+                let reply = MoonrakerReply {
+                    id: method_response.id,
+                    result: method_response.result.unwrap_or(serde_json::json!(null)),
+                };
+                inbound_sender
+                    .send(Arc::new(WebsocketEvent::MoonrakerReply(reply)))
+                    .expect("Failed to internally send a moonraker reply event");
             }
-            JsonRpcResponse::Notification(notification) => {
-                match notification.params {
-                    MoonrakerEventParameters::NotifyStatusUpdate(status_update) => {
-                        for event in status_update.events {
-                            let mut unlocked_cache = self.cache.lock().await;
-                            self.inbound_sender
-                                .send(Arc::new(WebsocketEvent::MoonrakerEvent(
-                                    MoonrakerEvent::NotifyStatusUpdate(
-                                        unlocked_cache.complete_event(event),
-                                    ),
-                                )))
-                                .expect(
-                                    "Failed to internally send a moonraker status update event",
-                                );
-                        }
-                    }
-                    MoonrakerEventParameters::NotifyProcessStatisticsUpdate(proc_stat_update) => {
-                        self.inbound_sender.send(Arc::new(WebsocketEvent::MoonrakerEvent(MoonrakerEvent::NotifyProcessStatisticsUpdate(proc_stat_update)))).expect("Failed to internally send a moonraker process statistics update event");
-                    },
-                    MoonrakerEventParameters::NotifyKlippyDisconnect => {
-                        self.inbound_sender.send(Arc::new(WebsocketEvent::Disconnected)).expect("Failed to internally send a disconnect event");
-                        self.outbound_sender.send(Arc::new(OutboundMessage::EndLoop)).expect("Failed to internally send an endloop event");
-                        return Err(Error::BreakError);
+        }
+        JsonRpcResponse::Notification(notification) => {
+            match notification.params {
+                MoonrakerEventParameters::NotifyStatusUpdate(status_update) => {
+                    for event in status_update.events {
+                        let mut unlocked_cache = cache.lock().await;
+                        let event = unlocked_cache.complete_event(event);
+                        cache_watch.publish(&event, &unlocked_cache);
+                        inbound_sender
+                            .send(Arc::new(WebsocketEvent::MoonrakerEvent(
+                                MoonrakerEvent::NotifyStatusUpdate(event),
+                            )))
+                            .expect(
+                                "Failed to internally send a moonraker status update event",
+                            );
                     }
                 }
+                MoonrakerEventParameters::NotifyProcessStatisticsUpdate(proc_stat_update) => {
+                    inbound_sender.send(Arc::new(WebsocketEvent::MoonrakerEvent(MoonrakerEvent::NotifyProcessStatisticsUpdate(proc_stat_update)))).expect("Failed to internally send a moonraker process statistics update event");
+                },
+                MoonrakerEventParameters::NotifyKlippyDisconnect => {
+                    inbound_sender.send(Arc::new(WebsocketEvent::Disconnected)).expect("Failed to internally send a disconnect event");
+                    outbound_sender.send(Arc::new(OutboundMessage::EndLoop)).expect("Failed to internally send an endloop event");
+                    return Err(Error::BreakError);
+                }
+                MoonrakerEventParameters::NotifyHistoryChanged(history_changed) => {
+                    inbound_sender.send(Arc::new(WebsocketEvent::MoonrakerEvent(MoonrakerEvent::NotifyHistoryChanged(history_changed)))).expect("Failed to internally send a moonraker history changed event");
+                }
+                MoonrakerEventParameters::NotifyJobQueueChanged(job_queue_changed) => {
+                    inbound_sender.send(Arc::new(WebsocketEvent::MoonrakerEvent(MoonrakerEvent::NotifyJobQueueChanged(job_queue_changed)))).expect("Failed to internally send a moonraker job queue changed event");
+                }
             }
         }
-
-        Ok(())
     }
+
+    Ok(())
 }
 
 #[derive(Debug)]
 pub enum MoonrakerEvent {
     NotifyStatusUpdate(PrinterEvent),
     NotifyProcessStatisticsUpdate(MoonrakerNotifyProcStatUpdate),
+    NotifyHistoryChanged(HistoryChangedNotification),
+    NotifyJobQueueChanged(JobQueueChangedNotification),
 }
 
 #[derive(Debug)]
@@ -195,8 +269,9 @@ pub enum PrinterEvent {
     MotionReport(MotionReport),
     GcodeMove(GcodeMove),
     Toolhead(Toolhead),
-    Extruder(Extruder),
+    Extruder(NamedExtruder),
     HeaterBed(HeaterBed),
+    HeaterGeneric(NamedHeaterGeneric),
     Fan(Fan),
     IdleTimeout(IdleTimeout),
     VirtualSdcard(VirtualSdcard),
@@ -207,4 +282,5 @@ pub enum PrinterEvent {
     FilamentSwitchSensor(NamedFilamentSwitchSensor),
     OutputPin(NamedOutputPin),
     ExcludeObject(ExcludeObject),
+    Configfile(Configfile),
 }
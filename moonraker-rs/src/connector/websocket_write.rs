@@ -1,16 +1,20 @@
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
-use fastwebsockets::{Frame, Payload, WebSocketWrite};
+use fastwebsockets::{Frame, OpCode, Payload, WebSocketWrite};
 use hyper::upgrade::Upgraded;
 use hyper_util::rt::TokioIo;
 use serde::Serialize;
 use std::fmt::Debug;
 use tokio::{
     io::WriteHalf,
-    sync::{Mutex, broadcast::Receiver},
+    sync::{Mutex, broadcast::{Receiver, error::RecvError}},
+    time::{MissedTickBehavior, interval},
 };
 
-use crate::error::Error;
+use crate::{
+    connector::recording::{Direction, Recorder},
+    error::Error,
+};
 
 #[derive(Debug, Serialize)]
 pub struct JsonRpcRequest {
@@ -50,30 +54,63 @@ impl Debug for OutboundMessage {
 pub(crate) async fn moonraker_writer_connection_loop(
     outbound_receiver: Receiver<Arc<OutboundMessage>>,
     websocket_writer: WebSocketWrite<WriteHalf<TokioIo<Upgraded>>>,
+    recorder: Option<Arc<Recorder>>,
+    keepalive_interval: Duration,
 ) {
-    let mut data = MoonrakerConnectionWriteLoop::new(outbound_receiver, websocket_writer);
+    let mut data = MoonrakerConnectionWriteLoop::new(
+        outbound_receiver,
+        websocket_writer,
+        recorder,
+        keepalive_interval,
+    );
     data.connection_loop().await;
 }
 
 struct MoonrakerConnectionWriteLoop {
     outbound_receiver: Receiver<Arc<OutboundMessage>>,
     websocket_writer: WebSocketWrite<WriteHalf<TokioIo<Upgraded>>>,
+    recorder: Option<Arc<Recorder>>,
+    keepalive_interval: Duration,
 }
 
 impl MoonrakerConnectionWriteLoop {
     pub fn new(
         outbound_receiver: Receiver<Arc<OutboundMessage>>,
         websocket_writer: WebSocketWrite<WriteHalf<TokioIo<Upgraded>>>,
+        recorder: Option<Arc<Recorder>>,
+        keepalive_interval: Duration,
     ) -> Self {
         Self {
             outbound_receiver,
             websocket_writer,
+            recorder,
+            keepalive_interval,
         }
     }
 
     pub async fn connection_loop(&mut self) {
+        let mut keepalive = interval(self.keepalive_interval);
+        keepalive.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        // The first tick completes immediately
+        keepalive.tick().await;
+
         loop {
-            let message = self.outbound_receiver.recv().await.unwrap();
+            let message = tokio::select! {
+                message = self.outbound_receiver.recv() => match message {
+                    Ok(message) => message,
+                    Err(RecvError::Lagged(skipped)) => {
+                        eprintln!("Writer fell behind, {} outbound messages were dropped", skipped);
+                        continue;
+                    }
+                    Err(RecvError::Closed) => break,
+                },
+                _ = keepalive.tick() => {
+                    if let Err(e) = self.send_ping().await {
+                        eprintln!("Failed to send keepalive ping: {:?}", e);
+                    }
+                    continue;
+                }
+            };
 
             if let Err(e) = match &*message {
                 OutboundMessage::EndLoop => break,
@@ -101,6 +138,15 @@ impl MoonrakerConnectionWriteLoop {
         Ok(())
     }
 
+    /// Keeps the reader's keepalive timeout from firing while Moonraker is
+    /// quiet, and notices a half-open connection when it isn't answered.
+    pub async fn send_ping(&mut self) -> Result<(), Error> {
+        self.websocket_writer
+            .write_frame(Frame::new(true, OpCode::Ping, None, Payload::Owned(vec![])))
+            .await?;
+        Ok(())
+    }
+
     pub async fn handle_moonraker_request(
         &mut self,
         request: &MoonrakerRequest,
@@ -117,6 +163,10 @@ impl MoonrakerConnectionWriteLoop {
         #[cfg(debug_assertions)]
         println!("Sending request: {}", data);
 
+        if let Some(recorder) = &self.recorder {
+            recorder.record(Direction::Outbound, &data);
+        }
+
         let bytes = data.as_bytes().to_vec();
 
         self.websocket_writer
@@ -7,7 +7,7 @@ pub enum Error {
     UnsupportedMessage(#[from] serde_json::Error),
     #[error("Failed to write message to websocket")]
     WebsocketWriteError(#[from] WebSocketError),
-    #[error("Moonraker returned an error reply")]
+    #[error("Moonraker returned an error: {1}")]
     MoonrakerErrorReply(i32, String),
     #[error("Unknown error")]
     Unknown(String),
@@ -15,4 +15,6 @@ pub enum Error {
     BreakError,
     #[error("Timeout")]
     Timeout,
+    #[error("Failed to read or write a recording")]
+    RecordingError(#[from] std::io::Error),
 }
@@ -1,21 +1,7 @@
+pub mod backoff;
 pub mod cache;
 pub mod connector;
 pub mod error;
 pub mod moonraker_connection;
 pub mod printer_objects;
 pub mod requests;
-
-pub fn add(left: u64, right: u64) -> u64 {
-    left + right
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn it_works() {
-        let result = add(2, 2);
-        assert_eq!(result, 4);
-    }
-}
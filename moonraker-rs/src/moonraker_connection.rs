@@ -1,8 +1,14 @@
-use crate::cache::Cache;
-use crate::connector::websocket_read::{MoonrakerEvent, moonraker_reader_connection_loop};
+use crate::backoff::BackoffPolicy;
+use crate::cache::{Cache, CacheWatch};
+use crate::connector::recording::Recorder;
+use crate::connector::replay::{ReplayTransport, replay_reader_loop, replay_writer_loop};
+use crate::connector::websocket_read::{
+    MoonrakerEvent, PrinterEvent, moonraker_reader_connection_loop,
+};
 use crate::connector::websocket_write::{
     MoonrakerRequest, OutboundMessage, moonraker_writer_connection_loop,
 };
+use crate::printer_objects::*;
 use crate::requests::PrinterAdministrationRequestHandler;
 use fastwebsockets::handshake;
 use fastwebsockets::{FragmentCollectorRead, WebSocketWrite};
@@ -14,15 +20,24 @@ use serde_json::Value;
 use std::error::Error;
 use std::fmt::Debug;
 use std::sync::Arc;
-use std::time::{Duration, Instant};
+use std::time::Duration;
 use tokio::io::{ReadHalf, WriteHalf};
 use tokio::net::TcpStream;
-use tokio::sync::broadcast::{Receiver, Sender};
-use tokio::sync::{Mutex, broadcast};
-use tokio::time::sleep;
+use tokio::sync::broadcast::{Receiver, Sender, error::RecvError};
+use tokio::sync::{Mutex, broadcast, watch};
+use tokio::task::JoinHandle;
+use tokio::time::{sleep, timeout};
 
 use hyper_util::rt::TokioIo;
 
+/// How long `send_request` waits for Moonraker's reply unless changed with
+/// `set_request_timeout`.
+pub const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// How often Moonraker is pinged unless changed with
+/// `set_keepalive_interval`.
+pub const DEFAULT_KEEPALIVE_INTERVAL: Duration = Duration::from_secs(10);
+
 struct SpawnExecutor;
 
 impl<Fut> hyper::rt::Executor<Fut> for SpawnExecutor
@@ -49,10 +64,44 @@ pub struct MoonrakerErrorReply
     pub id: u32,
 }
 
+/// Where `connection_loop` is, for showing the user why the printer isn't
+/// available yet.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConnectionState {
+    Connecting {
+        attempt: u32,
+    },
+    Connected,
+    /// Moonraker is up, but Klippy is starting, shut down or in an error state.
+    KlippyNotReady {
+        message: String,
+    },
+    BackingOff {
+        attempt: u32,
+        next_attempt: std::time::Instant,
+        last_error: String,
+    },
+}
+
+impl ConnectionState {
+    fn from_webhooks(webhooks: &Webhooks) -> Self {
+        match webhooks.state {
+            KlippyState::Ready => ConnectionState::Connected,
+            _ if webhooks.state_message.is_empty() => ConnectionState::KlippyNotReady {
+                message: format!("Klippy state: {}", webhooks.state),
+            },
+            _ => ConnectionState::KlippyNotReady {
+                message: webhooks.state_message.clone(),
+            },
+        }
+    }
+}
+
 #[derive(Debug)]
 pub enum WebsocketEvent {
     Connected,
     Disconnected,
+    ConnectionStateChanged(ConnectionState),
     ApplicationError(String),
     MoonrakerEvent(MoonrakerEvent),
     MoonrakerReply(MoonrakerReply),
@@ -81,12 +130,22 @@ pub struct MoonrakerConnection {
     outbound_event_sender: Sender<Arc<OutboundMessage>>,
     outbound_event_listener: Receiver<Arc<OutboundMessage>>,
     incrementing_id: Mutex<u32>,
-    preconfigured_cache: Option<Cache>,
+    /// What the cache starts as on every connection.
+    initial_cache: Cache,
+    cache: Arc<Mutex<Cache>>,
+    cache_watch: CacheWatch,
+    request_timeout: Duration,
+    recorder: Option<Arc<Recorder>>,
+    replay: Option<Arc<ReplayTransport>>,
+    backoff_policy: BackoffPolicy,
+    keepalive_interval: Duration,
+    connection_state: std::sync::Mutex<ConnectionState>,
 }
 
 impl MoonrakerConnection {
     pub fn new(host: &str, port: u16, preconfigured_cache : Option<Cache>) -> Self {
         let host = format!("{}:{}", host, port);
+        let initial_cache = preconfigured_cache.unwrap_or_default();
 
         let req = Request::builder()
             .method("GET")
@@ -118,10 +177,42 @@ impl MoonrakerConnection {
             outbound_event_sender: outbound_event_sender,
             outbound_event_listener: outbound_event_listener,
             incrementing_id: Mutex::new(1),
-            preconfigured_cache
+            cache: Arc::new(Mutex::new(initial_cache.clone())),
+            cache_watch: CacheWatch::new(&initial_cache),
+            initial_cache,
+            request_timeout: DEFAULT_REQUEST_TIMEOUT,
+            recorder: None,
+            replay: None,
+            backoff_policy: BackoffPolicy::default(),
+            keepalive_interval: DEFAULT_KEEPALIVE_INTERVAL,
+            connection_state: std::sync::Mutex::new(ConnectionState::Connecting { attempt: 1 }),
         }
     }
 
+    pub fn set_request_timeout(&mut self, request_timeout: Duration) {
+        self.request_timeout = request_timeout;
+    }
+
+    pub fn set_backoff_policy(&mut self, backoff_policy: BackoffPolicy) {
+        self.backoff_policy = backoff_policy;
+    }
+
+    /// How often to ping Moonraker. The connection is dropped when nothing
+    /// arrives for three intervals.
+    pub fn set_keepalive_interval(&mut self, keepalive_interval: Duration) {
+        self.keepalive_interval = keepalive_interval;
+    }
+
+    /// Records every message sent and received from now on.
+    pub fn set_recorder(&mut self, recorder: Recorder) {
+        self.recorder = Some(Arc::new(recorder));
+    }
+
+    /// Plays back a recording instead of connecting to Moonraker.
+    pub fn set_replay(&mut self, replay: ReplayTransport) {
+        self.replay = Some(Arc::new(replay));
+    }
+
     pub async fn new_id(&self) -> u32 {
         let mut id = self.incrementing_id.lock().await;
         let current_id = *id;
@@ -130,50 +221,37 @@ impl MoonrakerConnection {
     }
 
     pub async fn connection_loop(&self) {
+        // Consecutive failed attempts, reset once a subscription succeeds
+        let mut failures = 0;
+
         loop {
             // TODO: Kill old threads if they exist
+            self.set_connection_state(ConnectionState::Connecting {
+                attempt: failures + 1,
+            });
+            let mut listener = self.get_listener();
             let inbound_sender = self.inbound_event_sender.clone();
             inbound_sender
                 .send(Arc::new(WebsocketEvent::Disconnected))
                 .expect("Failed to internally send a disconnect event");
-            let reader;
-            let writer;
-            let cache = Arc::new(Mutex::new(self.preconfigured_cache.clone().unwrap_or_default()));
-
-            match self.reconnect().await {
-                Ok((r, w)) => {
-                    reader = r;
-                    writer = w;
-                    let _ = inbound_sender.send(Arc::new(WebsocketEvent::Connected));
-                }
-                Err(e) => {
-                    eprintln!("Error connecting to Moonraker: {}", e);
-                    sleep(std::time::Duration::from_secs(2)).await;
-                    continue;
-                }
-            }
-
-            let reader_handle = {
-                let inbound_sender = self.inbound_event_sender.clone();
-                let outbound_sender = self.outbound_event_sender.clone();
-                let cache = cache.clone();
-                tokio::spawn(async move {
-                    moonraker_reader_connection_loop(
-                        inbound_sender,
-                        outbound_sender,
-                        reader,
-                        cache,
-                    )
-                    .await;
-                })
-            };
-
-            let writer_handle = {
-                let outbound_receiver = self.outbound_event_listener.resubscribe();
-                tokio::spawn(async move {
-                    moonraker_writer_connection_loop(outbound_receiver, writer).await;
-                })
+            // Watchers keep the last values until the subscription fills the fresh cache
+            let cache = self.cache.clone();
+            *cache.lock().await = self.initial_cache.clone();
+
+            let (mut reader_handle, writer_handle) = match &self.replay {
+                Some(replay) => self.spawn_replay(replay.clone(), cache.clone()),
+                None => match self.reconnect().await {
+                    Ok((reader, writer)) => self.spawn_websocket(reader, writer, cache.clone()),
+                    Err(e) => {
+                        eprintln!("Error connecting to Moonraker: {}", e);
+                        failures += 1;
+                        self.back_off(failures, format!("Failed to connect to Moonraker: {}", e))
+                            .await;
+                        continue;
+                    }
+                },
             };
+            let _ = inbound_sender.send(Arc::new(WebsocketEvent::Connected));
 
             let object_list = match self.list_printer_objects().await {
                 Ok(object_list) => object_list,
@@ -181,12 +259,13 @@ impl MoonrakerConnection {
                     eprintln!("Error getting printer object list: {}", e);
                     reader_handle.abort();
                     writer_handle.abort();
+                    failures += 1;
+                    self.back_off(failures, format!("Failed to list printer objects: {}", e))
+                        .await;
                     continue;
                 }
             };
 
-            // TODO: Wait until Klippy is ready
-
             // TOOD: Don't subscribe to objects we don't have a use for.
             let initial_objects = self
                 .subscribe_to_printer_objects(object_list.objects.clone())
@@ -195,6 +274,9 @@ impl MoonrakerConnection {
                 eprintln!("Error subscribing to printer objects: {}", e);
                 reader_handle.abort();
                 writer_handle.abort();
+                failures += 1;
+                self.back_off(failures, format!("Failed to subscribe to printer objects: {}", e))
+                    .await;
                 continue;
             }
 
@@ -205,12 +287,145 @@ impl MoonrakerConnection {
                 )));
             }
 
-            reader_handle.await.unwrap();
+            // Also clears objects the new connection doesn't have
+            self.cache_watch.publish_all(&cache.lock().await);
+
+            failures = 0;
+            let webhooks = cache.lock().await.webhooks.clone();
+            self.set_connection_state(ConnectionState::from_webhooks(&webhooks));
+
+            // Follow Klippy's state until the session ends
+            let stop_reason = loop {
+                tokio::select! {
+                    stop_reason = &mut reader_handle => {
+                        break stop_reason.unwrap_or_else(|e| e.to_string());
+                    }
+                    event = listener.recv() => {
+                        if let Ok(event) = event
+                            && let WebsocketEvent::MoonrakerEvent(MoonrakerEvent::NotifyStatusUpdate(
+                                PrinterEvent::Webhooks(webhooks),
+                            )) = &*event
+                        {
+                            self.set_connection_state(ConnectionState::from_webhooks(webhooks));
+                        }
+                    }
+                }
+            };
+
             writer_handle.await.unwrap();
-            sleep(Duration::from_secs(2)).await;
+            eprintln!("Lost connection to Moonraker: {}", stop_reason);
+            failures = 1;
+            self.back_off(failures, stop_reason).await;
         }
     }
 
+    /// Reports the failure and waits before the next attempt.
+    async fn back_off(&self, attempt: u32, last_error: String) {
+        let delay = self.backoff_policy.delay(attempt);
+
+        self.set_connection_state(ConnectionState::BackingOff {
+            attempt,
+            next_attempt: std::time::Instant::now() + delay,
+            last_error,
+        });
+        sleep(delay).await;
+    }
+
+    fn set_connection_state(&self, state: ConnectionState) {
+        {
+            let mut current = self.connection_state.lock().unwrap();
+            if *current == state {
+                return;
+            }
+            *current = state.clone();
+        }
+
+        let _ = self
+            .inbound_event_sender
+            .send(Arc::new(WebsocketEvent::ConnectionStateChanged(state)));
+    }
+
+    pub fn connection_state(&self) -> ConnectionState {
+        self.connection_state.lock().unwrap().clone()
+    }
+
+    fn spawn_websocket(
+        &self,
+        reader: FragmentCollectorRead<ReadHalf<TokioIo<Upgraded>>>,
+        writer: WebSocketWrite<WriteHalf<TokioIo<Upgraded>>>,
+        cache: Arc<Mutex<Cache>>,
+    ) -> (JoinHandle<String>, JoinHandle<()>) {
+        let reader_handle = {
+            let inbound_sender = self.inbound_event_sender.clone();
+            let outbound_sender = self.outbound_event_sender.clone();
+            let cache_watch = self.cache_watch.clone();
+            let recorder = self.recorder.clone();
+            // Pings go out every interval, so a few missed pongs mean the connection is gone
+            let keepalive_timeout = self.keepalive_interval * 3;
+            tokio::spawn(async move {
+                moonraker_reader_connection_loop(
+                    inbound_sender,
+                    outbound_sender,
+                    reader,
+                    cache,
+                    cache_watch,
+                    recorder,
+                    keepalive_timeout,
+                )
+                .await
+            })
+        };
+
+        let writer_handle = {
+            let outbound_receiver = self.outbound_event_listener.resubscribe();
+            let recorder = self.recorder.clone();
+            let keepalive_interval = self.keepalive_interval;
+            tokio::spawn(async move {
+                moonraker_writer_connection_loop(outbound_receiver, writer, recorder, keepalive_interval)
+                    .await;
+            })
+        };
+
+        (reader_handle, writer_handle)
+    }
+
+    fn spawn_replay(
+        &self,
+        replay: Arc<ReplayTransport>,
+        cache: Arc<Mutex<Cache>>,
+    ) -> (JoinHandle<String>, JoinHandle<()>) {
+        let reader_handle = {
+            let replay = replay.clone();
+            let inbound_sender = self.inbound_event_sender.clone();
+            let outbound_sender = self.outbound_event_sender.clone();
+            let cache = cache.clone();
+            let cache_watch = self.cache_watch.clone();
+            tokio::spawn(async move {
+                replay_reader_loop(replay, inbound_sender, outbound_sender, cache, cache_watch).await
+            })
+        };
+
+        let writer_handle = {
+            let outbound_receiver = self.outbound_event_listener.resubscribe();
+            let inbound_sender = self.inbound_event_sender.clone();
+            let outbound_sender = self.outbound_event_sender.clone();
+            let cache_watch = self.cache_watch.clone();
+            tokio::spawn(async move {
+                replay_writer_loop(
+                    replay,
+                    outbound_receiver,
+                    inbound_sender,
+                    outbound_sender,
+                    cache,
+                    cache_watch,
+                )
+                .await;
+            })
+        };
+
+        (reader_handle, writer_handle)
+    }
+
     pub async fn send_request<T>(
         &self,
         method: &str,
@@ -229,42 +444,42 @@ impl MoonrakerConnection {
         }));
         let _ = self.outbound_event_sender.send(event);
 
-        loop {
-            let event = listener
-                .recv()
-                .await
-                .expect("Failed to retrieve internal event");
-            
-                let now = Instant::now();
-            match &*event {
-                WebsocketEvent::MoonrakerReply(reply) if reply.id == id => {
-                    let parsed_result: Result<T, serde_json::Error> =
-                        serde_json::from_value(reply.result.clone());
-
-                    match parsed_result {
-                        Ok(result) => return Ok(result),
-                        Err(e) => {
-                            
-                            #[cfg(debug_assertions)]
-                            println!("Raw event: {}", serde_json::to_string(&reply.result).unwrap_or_default());
-
-                            return Err(crate::error::Error::UnsupportedMessage(e));
-                        },
+        let reply = timeout(self.request_timeout, async {
+            loop {
+                let event = match listener.recv().await {
+                    Ok(event) => event,
+                    // Skipped events can't be the reply unless it was dropped, then this times out
+                    Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => {
+                        return Err(crate::error::Error::Unknown(String::from("The internal event channel was closed")));
                     }
-                }
+                };
 
-                WebsocketEvent::MoonrakerErrorReply(error) if error.id == id => {
-                    return Err(crate::error::Error::MoonrakerErrorReply(error.code, error.message.clone()));
-                }
-                _ => {
-                    if now.elapsed().as_secs() > 20 
-                    {
-                        return Err(crate::error::Error::Timeout);
+                match &*event {
+                    WebsocketEvent::MoonrakerReply(reply) if reply.id == id => {
+                        return Ok(reply.result.clone());
                     }
-                    else {
-                        continue;
+                    WebsocketEvent::MoonrakerErrorReply(error) if error.id == id => {
+                        return Err(crate::error::Error::MoonrakerErrorReply(error.code, error.message.clone()));
                     }
-                },
+                    _ => continue,
+                }
+            }
+        })
+        .await;
+
+        let result = match reply {
+            Ok(result) => result?,
+            Err(_) => return Err(crate::error::Error::Timeout),
+        };
+
+        match T::deserialize(&result) {
+            Ok(result) => Ok(result),
+            Err(e) => {
+                #[cfg(debug_assertions)]
+                println!("Raw event: {}", serde_json::to_string(&result).unwrap_or_default());
+
+                Err(crate::error::Error::UnsupportedMessage(e))
             }
         }
     }
@@ -301,6 +516,83 @@ impl MoonrakerConnection {
         Ok((reader, tx))
     }
 
+    /// A copy of everything currently known about the printer.
+    pub async fn cache_snapshot(&self) -> Cache {
+        self.cache.lock().await.clone()
+    }
+
+    pub fn watch_webhooks(&self) -> watch::Receiver<Webhooks> {
+        self.cache_watch.webhooks.subscribe()
+    }
+
+    pub fn watch_motion_report(&self) -> watch::Receiver<MotionReport> {
+        self.cache_watch.motion_report.subscribe()
+    }
+
+    pub fn watch_gcode_move(&self) -> watch::Receiver<GcodeMove> {
+        self.cache_watch.gcode_move.subscribe()
+    }
+
+    pub fn watch_toolhead(&self) -> watch::Receiver<Toolhead> {
+        self.cache_watch.toolhead.subscribe()
+    }
+
+    pub fn watch_extruders(&self) -> watch::Receiver<Vec<NamedExtruder>> {
+        self.cache_watch.extruders.subscribe()
+    }
+
+    pub fn watch_heater_bed(&self) -> watch::Receiver<HeaterBed> {
+        self.cache_watch.heater_bed.subscribe()
+    }
+
+    pub fn watch_heater_generics(&self) -> watch::Receiver<Vec<NamedHeaterGeneric>> {
+        self.cache_watch.heater_generics.subscribe()
+    }
+
+    pub fn watch_fan(&self) -> watch::Receiver<Fan> {
+        self.cache_watch.fan.subscribe()
+    }
+
+    pub fn watch_idle_timeout(&self) -> watch::Receiver<IdleTimeout> {
+        self.cache_watch.idle_timeout.subscribe()
+    }
+
+    pub fn watch_virtual_sdcard(&self) -> watch::Receiver<VirtualSdcard> {
+        self.cache_watch.virtual_sdcard.subscribe()
+    }
+
+    pub fn watch_print_stats(&self) -> watch::Receiver<PrintStats> {
+        self.cache_watch.print_stats.subscribe()
+    }
+
+    pub fn watch_display_status(&self) -> watch::Receiver<DisplayStatus> {
+        self.cache_watch.display_status.subscribe()
+    }
+
+    pub fn watch_temperature_sensors(&self) -> watch::Receiver<Vec<NamedTemperatureSensor>> {
+        self.cache_watch.temperature_sensors.subscribe()
+    }
+
+    pub fn watch_temperature_fans(&self) -> watch::Receiver<Vec<NamedTemperatureFan>> {
+        self.cache_watch.temperature_fans.subscribe()
+    }
+
+    pub fn watch_filament_switch_sensors(&self) -> watch::Receiver<Vec<NamedFilamentSwitchSensor>> {
+        self.cache_watch.filament_switch_sensors.subscribe()
+    }
+
+    pub fn watch_output_pins(&self) -> watch::Receiver<Vec<NamedOutputPin>> {
+        self.cache_watch.output_pins.subscribe()
+    }
+
+    pub fn watch_exclude_object(&self) -> watch::Receiver<ExcludeObject> {
+        self.cache_watch.exclude_object.subscribe()
+    }
+
+    pub fn watch_configfile(&self) -> watch::Receiver<Configfile> {
+        self.cache_watch.configfile.subscribe()
+    }
+
     pub fn get_listener(&self) -> Receiver<Arc<WebsocketEvent>> {
         self.inbound_event_listener.resubscribe()
     }
@@ -0,0 +1,38 @@
+use optional_struct::*;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+#[optional_struct]
+#[derive(Debug, Deserialize, Default, Clone)]
+pub struct Configfile {
+    pub settings: Map<String, Value>,
+    pub save_config_pending: bool,
+}
+
+impl Configfile {
+    pub fn overlay(&mut self, configfile: OptionalConfigfile) {
+        if let Some(settings) = configfile.settings {
+            self.settings = settings;
+        }
+        if let Some(save_config_pending) = configfile.save_config_pending {
+            self.save_config_pending = save_config_pending;
+        }
+    }
+
+    /// Looks up a parsed setting, e.g. `get_f32("extruder", "max_temp")`.
+    pub fn get_f32(&self, section: &str, option: &str) -> Option<f32> {
+        self.settings
+            .get(section)
+            .and_then(|section| section.get(option))
+            .and_then(|value| value.as_f64())
+            .map(|value| value as f32)
+    }
+
+    pub fn nozzle_diameter(&self, extruder: &str) -> Option<f32> {
+        self.get_f32(extruder, "nozzle_diameter")
+    }
+
+    pub fn max_temp(&self, heater: &str) -> Option<f32> {
+        self.get_f32(heater, "max_temp")
+    }
+}
\ No newline at end of file
@@ -31,6 +31,18 @@ impl Default for Extruder {
     }
 }
 
+#[derive(Debug, Deserialize, Default, Clone)]
+pub struct NamedExtruder {
+    pub name: String,
+    pub extruder: Extruder,
+}
+
+#[derive(Debug, Deserialize, Default, Clone)]
+pub struct NamedOptionalExtruder {
+    pub name: String,
+    pub extruder: OptionalExtruder,
+}
+
 impl Extruder {
     pub fn overlay(&mut self, extruder: OptionalExtruder) {
         if let Some(temperature) = extruder.temperature {
@@ -0,0 +1,53 @@
+use optional_struct::*;
+use serde::Deserialize;
+
+use crate::printer_objects::TemperatureConfiguration;
+
+#[optional_struct]
+#[derive(Debug, Deserialize, Clone)]
+pub struct HeaterGeneric {
+    pub temperature: f32,
+    pub target: f32,
+    pub power: f32,
+    pub configuration: TemperatureConfiguration,
+}
+
+impl Default for HeaterGeneric {
+    fn default() -> Self {
+        Self {
+            temperature: 0.0,
+            target: 0.0,
+            power: 0.0,
+            configuration: TemperatureConfiguration::default_generic(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Default, Clone)]
+pub struct NamedHeaterGeneric {
+    pub name: String,
+    pub heater: HeaterGeneric,
+}
+
+#[derive(Debug, Deserialize, Default, Clone)]
+pub struct NamedOptionalHeaterGeneric {
+    pub name: String,
+    pub heater: OptionalHeaterGeneric,
+}
+
+impl HeaterGeneric {
+    pub fn overlay(&mut self, heater: OptionalHeaterGeneric) {
+        if let Some(temperature) = heater.temperature {
+            self.temperature = temperature;
+        }
+        if let Some(target) = heater.target {
+            self.target = target;
+        }
+        if let Some(power) = heater.power {
+            self.power = power;
+        }
+        if let Some(configuration) = heater.configuration {
+            self.configuration = configuration;
+        }
+    }
+}
@@ -1,3 +1,4 @@
+pub mod configfile;
 pub mod display_status;
 pub mod exclude_object;
 pub mod extruder;
@@ -6,6 +7,7 @@ pub mod filament_switch_sensor;
 pub mod gcode_macro;
 pub mod gcode_move;
 pub mod heater_bed;
+pub mod heater_generic;
 pub mod idle_timeout;
 pub mod motion_report;
 pub mod output_pin;
@@ -17,6 +19,7 @@ pub mod virtual_sdcard;
 pub mod webhooks;
 pub mod temp_config;
 
+pub use configfile::*;
 pub use display_status::*;
 pub use exclude_object::*;
 pub use extruder::*;
@@ -25,6 +28,7 @@ pub use filament_switch_sensor::*;
 pub use gcode_macro::*;
 pub use gcode_move::*;
 pub use heater_bed::*;
+pub use heater_generic::*;
 pub use idle_timeout::*;
 pub use motion_report::*;
 pub use output_pin::*;
@@ -17,6 +17,11 @@ impl TemperatureConfiguration {
             presets: vec![40, 60, 70],
         }
     }
+    pub fn default_generic() -> Self {
+        Self {
+            presets: vec![40, 50, 60],
+        }
+    }
     pub fn default_fan() -> Self {
         Self {
             presets: vec![40, 50, 60],
@@ -32,7 +32,7 @@ pub struct GcodeMetadata {
     pub slicer: String,
     pub slicer_version: String,
     pub gcode_start_byte: i32,
-    pub gcode_int_byte: i32,
+    pub gcode_end_byte: i32,
     pub object_height: f32,
     pub estimated_time: f32,
     pub nozzle_diameter: f32,
@@ -108,7 +108,7 @@ impl GcodeMetadata {
             slicer: optional.slicer.unwrap_or_default(),
             slicer_version: optional.slicer_version.unwrap_or_default(),
             gcode_start_byte: optional.gcode_start_byte.unwrap_or_default(),
-            gcode_int_byte: optional.gcode_int_byte.unwrap_or_default(),
+            gcode_end_byte: optional.gcode_end_byte.unwrap_or_default(),
             object_height: optional.object_height.unwrap_or_default(),
             estimated_time: optional.estimated_time.unwrap_or_default(),
             nozzle_diameter: optional.nozzle_diameter.unwrap_or_default(),
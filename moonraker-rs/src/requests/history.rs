@@ -0,0 +1,151 @@
+use serde::Deserialize;
+
+use crate::{
+    error::Error, moonraker_connection::MoonrakerConnection, requests::OptionalGcodeMetadata,
+};
+
+pub trait HistoryRequestHandler {
+    async fn list_history(&self, limit: u32, start: u32) -> Result<HistoryListResult, Error>;
+    async fn get_history_job(&self, job_id: &str) -> Result<HistoryJob, Error>;
+    async fn delete_history_job(&self, job_id: &str) -> Result<Vec<String>, Error>;
+    async fn get_history_totals(&self) -> Result<HistoryTotals, Error>;
+    async fn reset_history_totals(&self) -> Result<HistoryTotals, Error>;
+}
+
+impl HistoryRequestHandler for MoonrakerConnection {
+    async fn list_history(&self, limit: u32, start: u32) -> Result<HistoryListResult, Error> {
+        let args = serde_json::json!({
+            "limit": limit,
+            "start": start,
+            "order": "desc",
+        });
+        self.send_request("server.history.list", Some(args)).await
+    }
+
+    async fn get_history_job(&self, job_id: &str) -> Result<HistoryJob, Error> {
+        let args = serde_json::json!({ "uid": job_id });
+        let result: HistoryJobResult = self.send_request("server.history.get_job", Some(args)).await?;
+
+        Ok(result.job)
+    }
+
+    async fn delete_history_job(&self, job_id: &str) -> Result<Vec<String>, Error> {
+        let args = serde_json::json!({ "uid": job_id });
+        let result: HistoryDeleteResult = self
+            .send_request("server.history.delete_job", Some(args))
+            .await?;
+
+        Ok(result.deleted_jobs)
+    }
+
+    async fn get_history_totals(&self) -> Result<HistoryTotals, Error> {
+        let result: HistoryTotalsResult = self.send_request("server.history.totals", None).await?;
+
+        Ok(result.job_totals)
+    }
+
+    async fn reset_history_totals(&self) -> Result<HistoryTotals, Error> {
+        let result: HistoryResetTotalsResult = self
+            .send_request("server.history.reset_totals", None)
+            .await?;
+
+        Ok(result.last_totals)
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum HistoryJobStatus {
+    InProgress,
+    Completed,
+    Cancelled,
+    Error,
+    KlippyShutdown,
+    KlippyDisconnect,
+    Interrupted,
+    ServerExit,
+    #[serde(other)]
+    Unknown,
+}
+
+impl std::fmt::Display for HistoryJobStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let status_str = match self {
+            HistoryJobStatus::InProgress => "In progress",
+            HistoryJobStatus::Completed => "Completed",
+            HistoryJobStatus::Cancelled => "Cancelled",
+            HistoryJobStatus::Error => "Error",
+            HistoryJobStatus::KlippyShutdown => "Klippy shutdown",
+            HistoryJobStatus::KlippyDisconnect => "Klippy disconnected",
+            HistoryJobStatus::Interrupted => "Interrupted",
+            HistoryJobStatus::ServerExit => "Server exit",
+            HistoryJobStatus::Unknown => "Unknown",
+        };
+        write!(f, "{}", status_str)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct HistoryJob {
+    pub job_id: String,
+    pub exists: bool,
+    pub end_time: Option<f64>,
+    pub filament_used: f32,
+    pub filename: String,
+    pub metadata: Option<OptionalGcodeMetadata>,
+    pub print_duration: f32,
+    pub status: HistoryJobStatus,
+    pub start_time: f64,
+    pub total_duration: f32,
+}
+
+#[derive(Debug, Deserialize, Default, Clone)]
+pub struct HistoryTotals {
+    pub total_jobs: u32,
+    pub total_time: f32,
+    pub total_print_time: f32,
+    pub total_filament_used: f32,
+    pub longest_job: f32,
+    pub longest_print: f32,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct HistoryListResult {
+    pub count: u32,
+    pub jobs: Vec<HistoryJob>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct HistoryJobResult {
+    pub job: HistoryJob,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct HistoryDeleteResult {
+    pub deleted_jobs: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct HistoryTotalsResult {
+    pub job_totals: HistoryTotals,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct HistoryResetTotalsResult {
+    pub last_totals: HistoryTotals,
+}
+
+#[derive(Debug, Deserialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum HistoryChangedAction {
+    Added,
+    Finished,
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct HistoryChangedNotification {
+    pub action: HistoryChangedAction,
+    pub job: HistoryJob,
+}
@@ -0,0 +1,108 @@
+use std::fmt::Display;
+
+use serde::Deserialize;
+
+use crate::{error::Error, moonraker_connection::MoonrakerConnection};
+
+pub trait JobQueueRequestHandler {
+    async fn get_job_queue_status(&self) -> Result<JobQueueStatus, Error>;
+    async fn enqueue_jobs(&self, filenames: &[String], reset: bool) -> Result<JobQueueStatus, Error>;
+    async fn remove_queued_jobs(&self, job_ids: &[String]) -> Result<JobQueueStatus, Error>;
+    async fn pause_job_queue(&self) -> Result<JobQueueStatus, Error>;
+    async fn start_job_queue(&self) -> Result<JobQueueStatus, Error>;
+    async fn jump_queued_job(&self, job_id: &str) -> Result<JobQueueStatus, Error>;
+}
+
+impl JobQueueRequestHandler for MoonrakerConnection {
+    async fn get_job_queue_status(&self) -> Result<JobQueueStatus, Error> {
+        self.send_request("server.job_queue.status", None).await
+    }
+
+    async fn enqueue_jobs(&self, filenames: &[String], reset: bool) -> Result<JobQueueStatus, Error> {
+        let args = serde_json::json!({
+            "filenames": filenames,
+            "reset": reset,
+        });
+        self.send_request("server.job_queue.post_job", Some(args)).await
+    }
+
+    async fn remove_queued_jobs(&self, job_ids: &[String]) -> Result<JobQueueStatus, Error> {
+        let args = serde_json::json!({ "job_ids": job_ids });
+        self.send_request("server.job_queue.delete_job", Some(args)).await
+    }
+
+    async fn pause_job_queue(&self) -> Result<JobQueueStatus, Error> {
+        self.send_request("server.job_queue.pause", None).await
+    }
+
+    async fn start_job_queue(&self) -> Result<JobQueueStatus, Error> {
+        self.send_request("server.job_queue.start", None).await
+    }
+
+    async fn jump_queued_job(&self, job_id: &str) -> Result<JobQueueStatus, Error> {
+        let args = serde_json::json!({ "job_id": job_id });
+        self.send_request("server.job_queue.jump", Some(args)).await
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum JobQueueState {
+    Ready,
+    Loading,
+    Starting,
+    Paused,
+    #[serde(other)]
+    Unknown,
+}
+
+impl Default for JobQueueState {
+    fn default() -> Self {
+        JobQueueState::Ready
+    }
+}
+
+impl Display for JobQueueState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let state_str = match self {
+            JobQueueState::Ready => "Ready",
+            JobQueueState::Loading => "Loading",
+            JobQueueState::Starting => "Starting",
+            JobQueueState::Paused => "Paused",
+            JobQueueState::Unknown => "Unknown",
+        };
+        write!(f, "{}", state_str)
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct QueuedJob {
+    pub filename: String,
+    pub job_id: String,
+    pub time_added: f64,
+    pub time_in_queue: f32,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct JobQueueStatus {
+    pub queued_jobs: Vec<QueuedJob>,
+    pub queue_state: JobQueueState,
+}
+
+#[derive(Debug, Deserialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum JobQueueChangedAction {
+    StateChanged,
+    JobsAdded,
+    JobsRemoved,
+    JobLoaded,
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct JobQueueChangedNotification {
+    pub action: JobQueueChangedAction,
+    pub updated_queue: Option<Vec<QueuedJob>>,
+    pub queue_state: JobQueueState,
+}
\ No newline at end of file
@@ -1,7 +1,11 @@
 mod file_management;
+mod history;
+mod job_queue;
 mod printer_administration;
 mod switches_sensors_devices;
 
 pub use file_management::*;
+pub use history::*;
+pub use job_queue::*;
 pub use printer_administration::*;
 pub use switches_sensors_devices::*;
\ No newline at end of file
@@ -0,0 +1,41 @@
+use std::time::Duration;
+
+use moonraker_rs::backoff::BackoffPolicy;
+
+fn without_jitter() -> BackoffPolicy {
+    BackoffPolicy {
+        jitter: 0.0,
+        ..BackoffPolicy::default()
+    }
+}
+
+#[test]
+fn delay_grows_exponentially() {
+    let policy = without_jitter();
+
+    assert_eq!(policy.delay(1), Duration::from_secs(1));
+    assert_eq!(policy.delay(2), Duration::from_secs(2));
+    assert_eq!(policy.delay(3), Duration::from_secs(4));
+    assert_eq!(policy.delay(6), Duration::from_secs(32));
+}
+
+#[test]
+fn delay_is_capped() {
+    let policy = without_jitter();
+
+    assert_eq!(policy.delay(7), policy.max_delay);
+    assert_eq!(policy.delay(u32::MAX), policy.max_delay);
+}
+
+#[test]
+fn jitter_only_shortens_the_delay() {
+    let policy = BackoffPolicy::default();
+
+    for attempt in 1..=10 {
+        let full = without_jitter().delay(attempt).as_secs_f64();
+        let delay = policy.delay(attempt).as_secs_f64();
+
+        assert!(delay <= full, "{} is longer than {}", delay, full);
+        assert!(delay >= full * (1.0 - policy.jitter) - 1e-9, "{} is shorter than the jitter allows", delay);
+    }
+}
@@ -0,0 +1,127 @@
+use moonraker_rs::{
+    cache::Cache,
+    connector::{
+        read_deserialize::{MoonrakerEventNotifyStatusUpdate, OptionalPrinterEvent},
+        websocket_read::PrinterEvent,
+    },
+    printer_objects::{PrintState, TemperatureConfiguration},
+};
+use serde_json::{Value, json};
+
+fn parse(status: Value) -> Vec<OptionalPrinterEvent> {
+    serde_json::from_value::<MoonrakerEventNotifyStatusUpdate>(status)
+        .expect("Failed to parse status update")
+        .events
+}
+
+fn apply(cache: &mut Cache, status: Value) -> Vec<PrinterEvent> {
+    parse(status).into_iter().map(|event| cache.complete_event(event)).collect()
+}
+
+#[test]
+fn updates_keep_fields_they_leave_out() {
+    let mut cache = Cache::new();
+
+    apply(&mut cache, json!({
+        "print_stats": {
+            "filename": "benchy.gcode",
+            "state": "printing",
+            "print_duration": 10.0,
+            "info": { "total_layer": 120, "current_layer": 1 },
+        }
+    }));
+    apply(&mut cache, json!({ "print_stats": { "print_duration": 12.5 } }));
+
+    assert_eq!(cache.print_stats.filename, "benchy.gcode");
+    assert_eq!(cache.print_stats.state, PrintState::Printing);
+    assert_eq!(cache.print_stats.print_duration, 12.5);
+    assert_eq!(cache.print_stats.info.total_layer, Some(120));
+}
+
+#[test]
+fn returns_the_completed_object() {
+    let mut cache = Cache::new();
+
+    apply(&mut cache, json!({ "heater_bed": { "temperature": 25.0, "target": 60.0, "power": 1.0 } }));
+    let events = apply(&mut cache, json!({ "heater_bed": { "temperature": 30.0 } }));
+
+    let [PrinterEvent::HeaterBed(heater_bed)] = &events[..] else {
+        panic!("Expected a heater_bed event, got {:?}", events);
+    };
+    assert_eq!(heater_bed.temperature, 30.0);
+    assert_eq!(heater_bed.target, 60.0);
+    assert_eq!(heater_bed.power, 1.0);
+}
+
+#[test]
+fn named_objects_are_tracked_separately() {
+    let mut cache = Cache::new();
+
+    apply(&mut cache, json!({
+        "extruder": { "temperature": 200.0, "target": 200.0 },
+        "extruder1": { "temperature": 25.0, "target": 0.0 },
+        "temperature_sensor mcu_temp": { "temperature": 40.0 },
+        "temperature_sensor host_temp": { "temperature": 50.0 },
+    }));
+    let events = apply(&mut cache, json!({ "extruder1": { "target": 240.0 } }));
+
+    let [PrinterEvent::Extruder(extruder1)] = &events[..] else {
+        panic!("Expected an extruder event, got {:?}", events);
+    };
+    assert_eq!(extruder1.name, "extruder1");
+    assert_eq!(extruder1.extruder.temperature, 25.0);
+    assert_eq!(extruder1.extruder.target, 240.0);
+
+    assert_eq!(cache.extruders.len(), 2);
+    assert_eq!(cache.extruders[0].extruder.target, 200.0);
+    assert_eq!(cache.temperature_sensors.len(), 2);
+}
+
+#[test]
+fn heater_presets_apply_to_heaters_that_appear_later() {
+    let mut cache = Cache::new();
+    cache.set_heater_presets("extruder1", TemperatureConfiguration::from(vec![215, 250]));
+    cache.set_heater_presets("chamber", TemperatureConfiguration::from(vec![45]));
+    cache.set_heater_presets("heater_bed", TemperatureConfiguration::from(vec![55, 100]));
+
+    apply(&mut cache, json!({
+        "extruder": { "temperature": 25.0 },
+        "extruder1": { "temperature": 25.0 },
+        "heater_generic chamber": { "temperature": 25.0 },
+        "heater_bed": { "temperature": 25.0 },
+    }));
+
+    assert_eq!(cache.extruders[0].extruder.configuration, TemperatureConfiguration::default_hotend());
+    assert_eq!(cache.extruders[1].extruder.configuration.presets, vec![215, 250]);
+    assert_eq!(cache.heater_generics[0].name, "chamber");
+    assert_eq!(cache.heater_generics[0].heater.configuration.presets, vec![45]);
+    assert_eq!(cache.heater_bed.configuration.presets, vec![55, 100]);
+}
+
+#[test]
+fn heater_presets_apply_to_known_heaters() {
+    let mut cache = Cache::new();
+
+    apply(&mut cache, json!({ "temperature_fan exhaust": { "temperature": 30.0, "target": 40.0 } }));
+    cache.set_heater_presets("exhaust", TemperatureConfiguration::from(vec![35, 50]));
+    apply(&mut cache, json!({ "temperature_fan exhaust": { "speed": 0.5 } }));
+
+    assert_eq!(cache.temperature_fans[0].fan.configuration.presets, vec![35, 50]);
+    assert_eq!(cache.temperature_fans[0].fan.target, 40.0);
+    assert_eq!(cache.temperature_fans[0].fan.speed, 0.5);
+}
+
+#[test]
+fn active_extruder_follows_the_toolhead() {
+    let mut cache = Cache::new();
+
+    apply(&mut cache, json!({
+        "toolhead": { "extruder": "extruder" },
+        "extruder": { "target": 200.0 },
+        "extruder1": { "target": 240.0 },
+    }));
+    assert_eq!(cache.active_extruder().unwrap().extruder.target, 200.0);
+
+    apply(&mut cache, json!({ "toolhead": { "extruder": "extruder1" } }));
+    assert_eq!(cache.active_extruder().unwrap().extruder.target, 240.0);
+}
@@ -0,0 +1,206 @@
+// Each test binary only uses part of the harness
+#![allow(dead_code)]
+
+use std::{sync::Arc, time::Duration};
+
+use fastwebsockets::{FragmentCollector, Frame, OpCode, Payload, WebSocketError, upgrade};
+use http_body_util::Empty;
+use hyper::{
+    Request, Response,
+    body::{Bytes, Incoming},
+    server::conn::http1,
+    service::service_fn,
+    upgrade::Upgraded,
+};
+use hyper_util::rt::TokioIo;
+use moonraker_rs::{
+    backoff::BackoffPolicy,
+    moonraker_connection::{MoonrakerConnection, WebsocketEvent},
+};
+use serde_json::{Value, json};
+use tokio::{
+    net::TcpListener,
+    sync::{broadcast::Receiver, mpsc},
+    time::timeout,
+};
+
+/// How long a test waits on the other side before failing.
+pub const TEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// An in-process stand-in for Moonraker. Every websocket client that connects
+/// is handed to the test as a `MockClient`, which scripts the exchange.
+pub struct MockServer {
+    pub port: u16,
+    clients: mpsc::UnboundedReceiver<MockClient>,
+}
+
+impl MockServer {
+    pub async fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let (client_sender, clients) = mpsc::unbounded_channel();
+
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let client_sender = client_sender.clone();
+                tokio::spawn(async move {
+                    let service = service_fn(move |request| upgrade_request(request, client_sender.clone()));
+                    let _ = http1::Builder::new()
+                        .serve_connection(TokioIo::new(stream), service)
+                        .with_upgrades()
+                        .await;
+                });
+            }
+        });
+
+        Self { port, clients }
+    }
+
+    /// Waits for the next websocket client.
+    pub async fn accept(&mut self) -> MockClient {
+        timeout(TEST_TIMEOUT, self.clients.recv())
+            .await
+            .expect("Timed out waiting for a client to connect")
+            .expect("Mock server stopped")
+    }
+}
+
+async fn upgrade_request(
+    mut request: Request<Incoming>,
+    client_sender: mpsc::UnboundedSender<MockClient>,
+) -> Result<Response<Empty<Bytes>>, WebSocketError> {
+    let (response, websocket) = upgrade::upgrade(&mut request)?;
+
+    tokio::spawn(async move {
+        if let Ok(websocket) = websocket.await {
+            let _ = client_sender.send(MockClient {
+                websocket: FragmentCollector::new(websocket),
+            });
+        }
+    });
+
+    Ok(response)
+}
+
+/// The server side of one websocket connection.
+pub struct MockClient {
+    websocket: FragmentCollector<TokioIo<Upgraded>>,
+}
+
+impl MockClient {
+    /// Reads the next request, checks its method and returns its id and params.
+    pub async fn expect_request(&mut self, method: &str) -> (u64, Value) {
+        let request = self.read_json().await;
+        assert_eq!(request["method"], method, "Unexpected request {}", request);
+
+        let id = request["id"].as_u64().expect("Request without an id");
+        (id, request["params"].clone())
+    }
+
+    pub async fn reply(&mut self, id: u64, result: Value) {
+        self.send_json(json!({ "jsonrpc": "2.0", "result": result, "id": id })).await;
+    }
+
+    pub async fn reply_error(&mut self, id: u64, code: i32, message: &str) {
+        self.send_json(json!({ "jsonrpc": "2.0", "error": { "code": code, "message": message }, "id": id }))
+            .await;
+    }
+
+    pub async fn notify(&mut self, method: &str, params: Value) {
+        self.send_json(json!({ "jsonrpc": "2.0", "method": method, "params": params })).await;
+    }
+
+    pub async fn notify_status_update(&mut self, status: Value) {
+        self.notify("notify_status_update", json!([status, 0.0])).await;
+    }
+
+    /// Answers the object list and subscription `connection_loop` sends after
+    /// connecting, reporting `status` as the printer's objects.
+    pub async fn handshake(&mut self, status: Value) {
+        let objects: Vec<String> = status.as_object().expect("Status must be an object").keys().cloned().collect();
+
+        let (id, _) = self.expect_request("printer.objects.list").await;
+        self.reply(id, json!({ "objects": objects })).await;
+
+        let (id, params) = self.expect_request("printer.objects.subscribe").await;
+        let subscribed: Vec<String> = params["objects"].as_object().unwrap().keys().cloned().collect();
+        assert_eq!(subscribed, objects);
+        self.reply(id, json!({ "eventtime": 0.0, "status": status })).await;
+    }
+
+    pub async fn close(mut self) {
+        let _ = self.websocket.write_frame(Frame::close(1000, b"")).await;
+    }
+
+    async fn read_json(&mut self) -> Value {
+        loop {
+            let frame = timeout(TEST_TIMEOUT, self.websocket.read_frame())
+                .await
+                .expect("Timed out waiting for a request")
+                .expect("Client disconnected");
+
+            if frame.opcode == OpCode::Text {
+                return serde_json::from_slice(&frame.payload).expect("Request is not valid JSON");
+            }
+        }
+    }
+
+    async fn send_json(&mut self, value: Value) {
+        self.websocket
+            .write_frame(Frame::text(Payload::Owned(value.to_string().into_bytes())))
+            .await
+            .expect("Failed to write to the client");
+    }
+}
+
+/// Starts a connection loop against `server`, returning a listener that
+/// already sees the first connection's events.
+pub fn connect(
+    server: &MockServer,
+    request_timeout: Duration,
+) -> (Arc<MoonrakerConnection>, Receiver<Arc<WebsocketEvent>>) {
+    connect_with(server.port, |connection| connection.set_request_timeout(request_timeout))
+}
+
+/// Like `connect`, for any port and with `configure` applied before the loop
+/// starts. Reconnects are retried quickly so tests don't wait on the backoff.
+pub fn connect_with(
+    port: u16,
+    configure: impl FnOnce(&mut MoonrakerConnection),
+) -> (Arc<MoonrakerConnection>, Receiver<Arc<WebsocketEvent>>) {
+    let mut connection = MoonrakerConnection::new("127.0.0.1", port, None);
+    connection.set_backoff_policy(BackoffPolicy {
+        initial_delay: Duration::from_millis(50),
+        max_delay: Duration::from_millis(200),
+        ..BackoffPolicy::default()
+    });
+    configure(&mut connection);
+    let connection = Arc::new(connection);
+    let listener = connection.get_listener();
+
+    {
+        let connection = connection.clone();
+        tokio::spawn(async move {
+            connection.connection_loop().await;
+        });
+    }
+
+    (connection, listener)
+}
+
+/// Waits for an event matching `predicate`, skipping everything before it.
+pub async fn wait_for_event(
+    listener: &mut Receiver<Arc<WebsocketEvent>>,
+    predicate: impl Fn(&WebsocketEvent) -> bool,
+) -> Arc<WebsocketEvent> {
+    timeout(TEST_TIMEOUT, async {
+        loop {
+            let event = listener.recv().await.expect("Event channel closed");
+            if predicate(&event) {
+                return event;
+            }
+        }
+    })
+    .await
+    .expect("Timed out waiting for an event")
+}
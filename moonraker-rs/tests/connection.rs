@@ -0,0 +1,209 @@
+mod common;
+
+use std::time::Duration;
+
+use common::{MockServer, connect, wait_for_event};
+use moonraker_rs::{
+    connector::websocket_read::{MoonrakerEvent, PrinterEvent},
+    error::Error,
+    moonraker_connection::{DEFAULT_REQUEST_TIMEOUT, WebsocketEvent},
+    printer_objects::KlippyState,
+    requests::{HistoryRequestHandler, PrinterAdministrationRequestHandler},
+};
+use serde_json::json;
+
+fn printer_status() -> serde_json::Value {
+    json!({
+        "webhooks": { "state": "ready", "state_message": "Printer is ready" },
+        "extruder": { "temperature": 21.5, "target": 0.0, "power": 0.0 },
+    })
+}
+
+fn is_connected(event: &WebsocketEvent) -> bool {
+    matches!(event, WebsocketEvent::Connected)
+}
+
+fn is_disconnected(event: &WebsocketEvent) -> bool {
+    matches!(event, WebsocketEvent::Disconnected)
+}
+
+#[tokio::test]
+async fn subscribes_after_connecting() {
+    let mut server = MockServer::start().await;
+    let (_connection, mut listener) = connect(&server, DEFAULT_REQUEST_TIMEOUT);
+
+    let mut client = server.accept().await;
+    wait_for_event(&mut listener, is_connected).await;
+    client.handshake(printer_status()).await;
+
+    let event = wait_for_event(&mut listener, |event| {
+        matches!(event, WebsocketEvent::MoonrakerEvent(MoonrakerEvent::NotifyStatusUpdate(PrinterEvent::Webhooks(_))))
+    })
+    .await;
+    let WebsocketEvent::MoonrakerEvent(MoonrakerEvent::NotifyStatusUpdate(PrinterEvent::Webhooks(webhooks))) = &*event else {
+        unreachable!();
+    };
+    assert_eq!(webhooks.state, KlippyState::Ready);
+    assert_eq!(webhooks.state_message, "Printer is ready");
+}
+
+#[tokio::test]
+async fn status_updates_are_completed_from_the_cache() {
+    let mut server = MockServer::start().await;
+    let (_connection, mut listener) = connect(&server, DEFAULT_REQUEST_TIMEOUT);
+
+    let mut client = server.accept().await;
+    client.handshake(printer_status()).await;
+    client.notify_status_update(json!({ "extruder": { "target": 215.0 } })).await;
+    client.notify_status_update(json!({ "extruder": { "temperature": 60.25 } })).await;
+
+    let event = wait_for_event(&mut listener, |event| match event {
+        WebsocketEvent::MoonrakerEvent(MoonrakerEvent::NotifyStatusUpdate(PrinterEvent::Extruder(extruder))) => {
+            extruder.extruder.temperature == 60.25
+        }
+        _ => false,
+    })
+    .await;
+    let WebsocketEvent::MoonrakerEvent(MoonrakerEvent::NotifyStatusUpdate(PrinterEvent::Extruder(extruder))) = &*event else {
+        unreachable!();
+    };
+    assert_eq!(extruder.name, "extruder");
+    assert_eq!(extruder.extruder.target, 215.0);
+}
+
+#[tokio::test]
+async fn reconnects_when_the_server_closes_the_connection() {
+    let mut server = MockServer::start().await;
+    let (_connection, mut listener) = connect(&server, DEFAULT_REQUEST_TIMEOUT);
+
+    let mut client = server.accept().await;
+    wait_for_event(&mut listener, is_connected).await;
+    client.handshake(printer_status()).await;
+    client.close().await;
+    wait_for_event(&mut listener, is_disconnected).await;
+
+    let mut client = server.accept().await;
+    wait_for_event(&mut listener, is_connected).await;
+    client.handshake(printer_status()).await;
+}
+
+#[tokio::test]
+async fn reconnects_when_klippy_disconnects() {
+    let mut server = MockServer::start().await;
+    let (_connection, mut listener) = connect(&server, DEFAULT_REQUEST_TIMEOUT);
+
+    let mut client = server.accept().await;
+    wait_for_event(&mut listener, is_connected).await;
+    client.handshake(printer_status()).await;
+    client.notify("notify_klippy_disconnected", json!([])).await;
+    wait_for_event(&mut listener, is_disconnected).await;
+
+    let mut client = server.accept().await;
+    wait_for_event(&mut listener, is_connected).await;
+    client.handshake(printer_status()).await;
+}
+
+#[tokio::test]
+async fn reconnects_when_listing_objects_fails() {
+    let mut server = MockServer::start().await;
+    let (_connection, mut listener) = connect(&server, DEFAULT_REQUEST_TIMEOUT);
+
+    let mut client = server.accept().await;
+    wait_for_event(&mut listener, is_connected).await;
+    let (id, _) = client.expect_request("printer.objects.list").await;
+    client.reply_error(id, 503, "Klippy Host not connected").await;
+
+    let mut client = server.accept().await;
+    wait_for_event(&mut listener, is_connected).await;
+    client.handshake(printer_status()).await;
+}
+
+#[tokio::test]
+async fn send_request_returns_the_result() {
+    let mut server = MockServer::start().await;
+    let (connection, _listener) = connect(&server, DEFAULT_REQUEST_TIMEOUT);
+
+    let mut client = server.accept().await;
+    client.handshake(printer_status()).await;
+
+    let request = tokio::spawn(async move { connection.run_gcode_script("G28").await });
+    let (id, params) = client.expect_request("printer.gcode.script").await;
+    assert_eq!(params, json!({ "script": "G28" }));
+    client.reply(id, json!("ok")).await;
+
+    assert_eq!(request.await.unwrap().unwrap(), "ok");
+}
+
+#[tokio::test]
+async fn replies_are_matched_by_id() {
+    let mut server = MockServer::start().await;
+    let (connection, _listener) = connect(&server, DEFAULT_REQUEST_TIMEOUT);
+
+    let mut client = server.accept().await;
+    client.handshake(printer_status()).await;
+
+    let first = {
+        let connection = connection.clone();
+        tokio::spawn(async move { connection.start_print("first.gcode").await })
+    };
+    let (first_id, _) = client.expect_request("printer.print.start").await;
+
+    let second = tokio::spawn(async move { connection.emergency_stop().await });
+    let (second_id, _) = client.expect_request("printer.emergency_stop").await;
+
+    client.reply_error(second_id, 400, "Printer is shutdown").await;
+    client.reply(first_id, json!("ok")).await;
+
+    assert_eq!(first.await.unwrap().unwrap(), "ok");
+    assert!(matches!(
+        second.await.unwrap(),
+        Err(Error::MoonrakerErrorReply(400, message)) if message == "Printer is shutdown"
+    ));
+}
+
+#[tokio::test]
+async fn send_request_returns_error_replies() {
+    let mut server = MockServer::start().await;
+    let (connection, _listener) = connect(&server, DEFAULT_REQUEST_TIMEOUT);
+
+    let mut client = server.accept().await;
+    client.handshake(printer_status()).await;
+
+    let request = tokio::spawn(async move { connection.run_gcode_script("FOO").await });
+    let (id, _) = client.expect_request("printer.gcode.script").await;
+    client.reply_error(id, 400, "Unknown command:\"FOO\"").await;
+
+    assert!(matches!(
+        request.await.unwrap(),
+        Err(Error::MoonrakerErrorReply(400, message)) if message == "Unknown command:\"FOO\""
+    ));
+}
+
+#[tokio::test]
+async fn send_request_rejects_unexpected_results() {
+    let mut server = MockServer::start().await;
+    let (connection, _listener) = connect(&server, DEFAULT_REQUEST_TIMEOUT);
+
+    let mut client = server.accept().await;
+    client.handshake(printer_status()).await;
+
+    let request = tokio::spawn(async move { connection.get_history_totals().await });
+    let (id, _) = client.expect_request("server.history.totals").await;
+    client.reply(id, json!({ "unexpected": true })).await;
+
+    assert!(matches!(request.await.unwrap(), Err(Error::UnsupportedMessage(_))));
+}
+
+#[tokio::test]
+async fn send_request_times_out() {
+    let mut server = MockServer::start().await;
+    let (connection, _listener) = connect(&server, Duration::from_millis(200));
+
+    let mut client = server.accept().await;
+    client.handshake(printer_status()).await;
+
+    let request = tokio::spawn(async move { connection.restart().await });
+    client.expect_request("printer.restart").await;
+
+    assert!(matches!(request.await.unwrap(), Err(Error::Timeout)));
+}
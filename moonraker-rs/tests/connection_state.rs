@@ -0,0 +1,95 @@
+mod common;
+
+use std::time::Duration;
+
+use common::{MockServer, connect, connect_with, wait_for_event};
+use moonraker_rs::moonraker_connection::{ConnectionState, DEFAULT_REQUEST_TIMEOUT, WebsocketEvent};
+use serde_json::json;
+use tokio::net::TcpListener;
+
+fn state_changed(predicate: impl Fn(&ConnectionState) -> bool) -> impl Fn(&WebsocketEvent) -> bool {
+    move |event| matches!(event, WebsocketEvent::ConnectionStateChanged(state) if predicate(state))
+}
+
+#[tokio::test]
+async fn backs_off_while_moonraker_is_unreachable() {
+    // Nothing listens on a port we just gave back
+    let port = {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        listener.local_addr().unwrap().port()
+    };
+    let (connection, mut listener) = connect_with(port, |_| {});
+
+    for expected_attempt in 1..=3 {
+        let event = wait_for_event(
+            &mut listener,
+            state_changed(|state| matches!(state, ConnectionState::BackingOff { .. })),
+        )
+        .await;
+        let WebsocketEvent::ConnectionStateChanged(ConnectionState::BackingOff { attempt, last_error, .. }) = &*event
+        else {
+            unreachable!();
+        };
+        assert_eq!(*attempt, expected_attempt);
+        assert!(last_error.starts_with("Failed to connect to Moonraker"), "{}", last_error);
+    }
+
+    assert!(matches!(
+        connection.connection_state(),
+        ConnectionState::Connecting { .. } | ConnectionState::BackingOff { .. }
+    ));
+}
+
+#[tokio::test]
+async fn reports_klippy_until_it_is_ready() {
+    let mut server = MockServer::start().await;
+    let (connection, mut listener) = connect(&server, DEFAULT_REQUEST_TIMEOUT);
+
+    let mut client = server.accept().await;
+    client
+        .handshake(json!({ "webhooks": { "state": "startup", "state_message": "Printer is not ready" } }))
+        .await;
+
+    wait_for_event(
+        &mut listener,
+        state_changed(|state| {
+            *state == ConnectionState::KlippyNotReady {
+                message: "Printer is not ready".to_string(),
+            }
+        }),
+    )
+    .await;
+
+    client
+        .notify_status_update(json!({ "webhooks": { "state": "ready", "state_message": "Printer is ready" } }))
+        .await;
+    wait_for_event(&mut listener, state_changed(|state| *state == ConnectionState::Connected)).await;
+    assert_eq!(connection.connection_state(), ConnectionState::Connected);
+}
+
+#[tokio::test]
+async fn reconnects_when_pings_go_unanswered() {
+    let mut server = MockServer::start().await;
+    let (_connection, mut listener) = connect_with(server.port, |connection| {
+        connection.set_keepalive_interval(Duration::from_millis(100));
+    });
+
+    // Pongs are only sent while the mock reads, so it goes quiet after the handshake
+    let mut client = server.accept().await;
+    client.handshake(json!({ "webhooks": { "state": "ready" } })).await;
+    wait_for_event(&mut listener, state_changed(|state| *state == ConnectionState::Connected)).await;
+
+    let event = wait_for_event(
+        &mut listener,
+        state_changed(|state| matches!(state, ConnectionState::BackingOff { .. })),
+    )
+    .await;
+    let WebsocketEvent::ConnectionStateChanged(ConnectionState::BackingOff { last_error, .. }) = &*event else {
+        unreachable!();
+    };
+    assert!(last_error.starts_with("No reply from Moonraker"), "{}", last_error);
+
+    let mut client = server.accept().await;
+    client.handshake(json!({ "webhooks": { "state": "ready" } })).await;
+    wait_for_event(&mut listener, state_changed(|state| *state == ConnectionState::Connected)).await;
+}
@@ -0,0 +1,127 @@
+mod common;
+
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use common::wait_for_event;
+use moonraker_rs::{
+    connector::{
+        recording::{Direction, RecordedFrame, Recorder, Recording},
+        replay::ReplayTransport,
+        websocket_read::{MoonrakerEvent, PrinterEvent},
+    },
+    error::Error,
+    moonraker_connection::{MoonrakerConnection, WebsocketEvent},
+    requests::{HistoryRequestHandler, PrinterAdministrationRequestHandler},
+};
+use serde_json::json;
+
+fn recording_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("moonraker-rs-{}-{}.jsonl", name, std::process::id()))
+}
+
+/// A short session: the subscription the connection loop makes, the history
+/// totals the UI asked for and a status update a second later.
+fn write_session(path: &Path) {
+    let frames = [
+        (0.0, Direction::Outbound, json!({ "jsonrpc": "2.0", "method": "printer.objects.list", "id": 7 })),
+        (0.01, Direction::Inbound, json!({ "jsonrpc": "2.0", "result": { "objects": ["extruder"] }, "id": 7 })),
+        (
+            0.02,
+            Direction::Outbound,
+            json!({ "jsonrpc": "2.0", "method": "printer.objects.subscribe", "params": { "objects": { "extruder": null } }, "id": 8 }),
+        ),
+        (
+            0.03,
+            Direction::Inbound,
+            json!({ "jsonrpc": "2.0", "result": { "eventtime": 1.0, "status": { "extruder": { "temperature": 20.0, "target": 200.0 } } }, "id": 8 }),
+        ),
+        (0.5, Direction::Outbound, json!({ "jsonrpc": "2.0", "method": "server.history.totals", "id": 9 })),
+        (
+            0.51,
+            Direction::Inbound,
+            json!({ "jsonrpc": "2.0", "result": { "job_totals": {
+                "total_jobs": 3, "total_time": 100.0, "total_print_time": 90.0,
+                "total_filament_used": 1000.0, "longest_job": 50.0, "longest_print": 45.0,
+            } }, "id": 9 }),
+        ),
+        (
+            1.0,
+            Direction::Inbound,
+            json!({ "jsonrpc": "2.0", "method": "notify_status_update", "params": [{ "extruder": { "temperature": 150.0 } }, 2.0] }),
+        ),
+    ];
+
+    let lines: Vec<String> = frames
+        .into_iter()
+        .map(|(time, direction, message)| serde_json::to_string(&RecordedFrame { time, direction, message }).unwrap())
+        .collect();
+
+    std::fs::write(path, lines.join("\n")).unwrap();
+}
+
+fn replay(path: &Path) -> Arc<MoonrakerConnection> {
+    let mut connection = MoonrakerConnection::new("127.0.0.1", 0, None);
+    connection.set_replay(ReplayTransport::new(Recording::load(path).unwrap(), 10.0));
+    Arc::new(connection)
+}
+
+#[test]
+fn recordings_round_trip() {
+    let path = recording_path("round-trip");
+    let recorder = Recorder::create(&path).unwrap();
+    recorder.record(Direction::Outbound, r#"{"jsonrpc":"2.0","method":"server.info","id":1}"#);
+    recorder.record(Direction::Inbound, "not json");
+    // Waits for the writer thread
+    drop(recorder);
+
+    let recording = Recording::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(recording.frames.len(), 2);
+    assert_eq!(recording.frames[0].direction, Direction::Outbound);
+    assert_eq!(recording.frames[0].message["method"], "server.info");
+    assert_eq!(recording.frames[1].direction, Direction::Inbound);
+    assert_eq!(recording.frames[1].message, json!("not json"));
+    assert!(recording.frames[0].time <= recording.frames[1].time);
+}
+
+#[tokio::test]
+async fn replays_a_session() {
+    let path = recording_path("session");
+    write_session(&path);
+    let connection = replay(&path);
+    std::fs::remove_file(&path).unwrap();
+
+    let mut listener = connection.get_listener();
+    {
+        let connection = connection.clone();
+        tokio::spawn(async move {
+            connection.connection_loop().await;
+        });
+    }
+
+    wait_for_event(&mut listener, |event| matches!(event, WebsocketEvent::Connected)).await;
+
+    let event = wait_for_event(&mut listener, |event| match event {
+        WebsocketEvent::MoonrakerEvent(MoonrakerEvent::NotifyStatusUpdate(PrinterEvent::Extruder(extruder))) => {
+            extruder.extruder.temperature == 150.0
+        }
+        _ => false,
+    })
+    .await;
+    let WebsocketEvent::MoonrakerEvent(MoonrakerEvent::NotifyStatusUpdate(PrinterEvent::Extruder(extruder))) = &*event else {
+        unreachable!();
+    };
+    assert_eq!(extruder.extruder.target, 200.0);
+
+    let totals = connection.get_history_totals().await.unwrap();
+    assert_eq!(totals.total_jobs, 3);
+
+    assert!(matches!(
+        connection.emergency_stop().await,
+        Err(Error::MoonrakerErrorReply(404, _))
+    ));
+}
@@ -0,0 +1,325 @@
+use moonraker_rs::{
+    cache::Cache,
+    connector::read_deserialize::{MoonrakerEventNotifyStatusUpdate, OptionalPrinterEvent},
+    printer_objects::{ExcludeObjectDefinition, IdleTimeoutState, KlippyState, PrintState},
+};
+use serde_json::{Value, json};
+
+fn parse(status: Value) -> Vec<OptionalPrinterEvent> {
+    serde_json::from_value::<MoonrakerEventNotifyStatusUpdate>(status)
+        .expect("Failed to parse status update")
+        .events
+}
+
+/// Parses `status` and overlays it on an empty cache, to check every field
+/// made it through.
+fn parse_into_cache(status: Value) -> Cache {
+    let mut cache = Cache::new();
+    for event in parse(status) {
+        cache.complete_event(event);
+    }
+    cache
+}
+
+#[test]
+fn parses_webhooks() {
+    let cache = parse_into_cache(json!({
+        "webhooks": { "state": "shutdown", "state_message": "Emergency stop" }
+    }));
+
+    assert_eq!(cache.webhooks.state, KlippyState::Shutdown);
+    assert_eq!(cache.webhooks.state_message, "Emergency stop");
+}
+
+#[test]
+fn parses_motion_report() {
+    let cache = parse_into_cache(json!({
+        "motion_report": {
+            "live_position": [10.0, 20.0, 0.2, 105.5],
+            "live_velocity": 150.0,
+            "live_extruder_velocity": 3.5,
+            "steppers": ["stepper_x", "stepper_y", "stepper_z", "extruder"],
+            "trapq": ["toolhead", "extruder"],
+        }
+    }));
+
+    assert_eq!(cache.motion_report.live_position, [10.0, 20.0, 0.2, 105.5]);
+    assert_eq!(cache.motion_report.live_velocity, 150.0);
+    assert_eq!(cache.motion_report.live_extruder_velocity, 3.5);
+    assert_eq!(cache.motion_report.steppers.len(), 4);
+    assert_eq!(cache.motion_report.trapq, vec!["toolhead", "extruder"]);
+}
+
+#[test]
+fn parses_gcode_move() {
+    let cache = parse_into_cache(json!({
+        "gcode_move": {
+            "speed_factor": 1.5,
+            "speed": 3000.0,
+            "extruder_factor": 0.95,
+            "absolute_coordinates": true,
+            "absolute_extrude": false,
+            "homing_origin": [0.0, 0.0, -0.05, 0.0],
+            "position": [10.0, 20.0, 0.25, 100.0],
+            "gcode_position": [10.0, 20.0, 0.3, 100.0],
+        }
+    }));
+
+    assert_eq!(cache.gcode_move.speed_factor, 1.5);
+    assert_eq!(cache.gcode_move.speed, 3000.0);
+    assert_eq!(cache.gcode_move.extruder_factor, 0.95);
+    assert!(cache.gcode_move.absolute_coordinates);
+    assert!(!cache.gcode_move.absolute_extrude);
+    assert_eq!(cache.gcode_move.homing_origin[2], -0.05);
+    assert_eq!(cache.gcode_move.position, [10.0, 20.0, 0.25, 100.0]);
+    assert_eq!(cache.gcode_move.gcode_position[2], 0.3);
+}
+
+#[test]
+fn parses_toolhead() {
+    let cache = parse_into_cache(json!({
+        "toolhead": {
+            "homed_axes": "xyz",
+            "axis_minimum": [-5.0, -5.0, -2.0, 0.0],
+            "axis_maximum": [256.0, 256.0, 256.0, 0.0],
+            "cone_start_z": null,
+            "print_time": 120.5,
+            "stalls": 2,
+            "estimated_print_time": 119.0,
+            "extruder": "extruder",
+            "position": [10.0, 20.0, 30.0, 40.0],
+            "max_velocity": 500.0,
+            "max_accel": 20000.0,
+            "minimum_cruise_ratio": 0.5,
+            "square_corner_velocity": 5.0,
+        }
+    }));
+
+    assert_eq!(cache.toolhead.homed_axes, "xyz");
+    assert_eq!(cache.toolhead.axis_minimum[2], -2.0);
+    assert_eq!(cache.toolhead.axis_maximum[0], 256.0);
+    assert_eq!(cache.toolhead.cone_start_z, None);
+    assert_eq!(cache.toolhead.print_time, 120.5);
+    assert_eq!(cache.toolhead.stalls, 2);
+    assert_eq!(cache.toolhead.estimated_print_time, 119.0);
+    assert_eq!(cache.toolhead.extruder, "extruder");
+    assert_eq!(cache.toolhead.position, [10.0, 20.0, 30.0, 40.0]);
+    assert_eq!(cache.toolhead.max_velocity, 500.0);
+    assert_eq!(cache.toolhead.max_accel, 20000.0);
+    assert_eq!(cache.toolhead.minimum_cruise_ratio, 0.5);
+    assert_eq!(cache.toolhead.square_corner_velocity, 5.0);
+}
+
+#[test]
+fn parses_extruders_but_not_extruder_steppers() {
+    let status = json!({
+        "extruder": {
+            "temperature": 210.5,
+            "target": 210.0,
+            "power": 0.45,
+            "can_extrude": true,
+            "pressure_advance": 0.04,
+            "smooth_time": 0.04,
+            "motion_queue": "extruder",
+        },
+        "extruder2": { "temperature": 24.0 },
+        "extruder_stepper belted": { "pressure_advance": 0.1 },
+    });
+
+    assert_eq!(parse(status.clone()).len(), 2);
+
+    let cache = parse_into_cache(status);
+    let extruder = &cache.extruders[0];
+    assert_eq!(extruder.name, "extruder");
+    assert_eq!(extruder.extruder.temperature, 210.5);
+    assert_eq!(extruder.extruder.target, 210.0);
+    assert_eq!(extruder.extruder.power, 0.45);
+    assert!(extruder.extruder.can_extrude);
+    assert_eq!(extruder.extruder.pressure_advance, 0.04);
+    assert_eq!(extruder.extruder.smooth_time, 0.04);
+    assert_eq!(extruder.extruder.motion_queue.as_deref(), Some("extruder"));
+    assert_eq!(cache.extruders[1].name, "extruder2");
+}
+
+#[test]
+fn parses_heaters() {
+    let cache = parse_into_cache(json!({
+        "heater_bed": { "temperature": 59.8, "target": 60.0, "power": 0.3 },
+        "heater_generic chamber_heater": { "temperature": 35.0, "target": 45.0, "power": 1.0 },
+    }));
+
+    assert_eq!(cache.heater_bed.temperature, 59.8);
+    assert_eq!(cache.heater_bed.target, 60.0);
+    assert_eq!(cache.heater_bed.power, 0.3);
+
+    let heater = &cache.heater_generics[0];
+    assert_eq!(heater.name, "chamber_heater");
+    assert_eq!(heater.heater.temperature, 35.0);
+    assert_eq!(heater.heater.target, 45.0);
+    assert_eq!(heater.heater.power, 1.0);
+}
+
+#[test]
+fn parses_fans_and_temperature_sensors() {
+    let cache = parse_into_cache(json!({
+        "fan": { "speed": 0.75, "rpm": 4200 },
+        "temperature_fan exhaust_fan": { "speed": 0.4, "rpm": null, "temperature": 38.0, "target": 40.0 },
+        "temperature_sensor mcu_temp": { "temperature": 42.5, "measured_min_temp": 20.1, "measured_max_temp": 48.3 },
+    }));
+
+    assert_eq!(cache.fan.speed, 0.75);
+    assert_eq!(cache.fan.rpm, Some(4200));
+
+    let fan = &cache.temperature_fans[0];
+    assert_eq!(fan.name, "exhaust_fan");
+    assert_eq!(fan.fan.speed, 0.4);
+    assert_eq!(fan.fan.rpm, None);
+    assert_eq!(fan.fan.temperature, 38.0);
+    assert_eq!(fan.fan.target, 40.0);
+
+    let sensor = &cache.temperature_sensors[0];
+    assert_eq!(sensor.name, "mcu_temp");
+    assert_eq!(sensor.sensor.temperature, 42.5);
+    assert_eq!(sensor.sensor.measured_min_temp, 20.1);
+    assert_eq!(sensor.sensor.measured_max_temp, 48.3);
+}
+
+#[test]
+fn parses_idle_timeout_in_both_spellings() {
+    let cache = parse_into_cache(json!({ "idle_timeout": { "state": "Printing", "printing_time": 42.0 } }));
+    assert_eq!(cache.idle_timeout.state, IdleTimeoutState::Printing);
+    assert_eq!(cache.idle_timeout.printing_time, 42.0);
+
+    let cache = parse_into_cache(json!({ "idle_timeout": { "state": "ready" } }));
+    assert_eq!(cache.idle_timeout.state, IdleTimeoutState::Ready);
+}
+
+#[test]
+fn parses_print_progress() {
+    let cache = parse_into_cache(json!({
+        "virtual_sdcard": {
+            "file_path": "/home/pi/printer_data/gcodes/benchy.gcode",
+            "progress": 0.25,
+            "is_active": true,
+            "file_position": 1024,
+            "file_size": 4096,
+        },
+        "print_stats": {
+            "filename": "benchy.gcode",
+            "total_duration": 600.0,
+            "print_duration": 540.0,
+            "filament_used": 1234.5,
+            "state": "paused",
+            "message": "",
+            "info": { "total_layer": 120, "current_layer": 30 },
+        },
+        "display_status": { "message": "Layer 30", "progress": 0.24 },
+    }));
+
+    assert_eq!(cache.virtual_sdcard.file_path.as_deref(), Some("/home/pi/printer_data/gcodes/benchy.gcode"));
+    assert_eq!(cache.virtual_sdcard.progress, 0.25);
+    assert!(cache.virtual_sdcard.is_active);
+    assert_eq!(cache.virtual_sdcard.file_position, 1024);
+    assert_eq!(cache.virtual_sdcard.file_size, 4096);
+
+    assert_eq!(cache.print_stats.filename, "benchy.gcode");
+    assert_eq!(cache.print_stats.total_duration, 600.0);
+    assert_eq!(cache.print_stats.print_duration, 540.0);
+    assert_eq!(cache.print_stats.filament_used, 1234.5);
+    assert_eq!(cache.print_stats.state, PrintState::Paused);
+    assert_eq!(cache.print_stats.info.current_layer, Some(30));
+
+    assert_eq!(cache.display_status.message, "Layer 30");
+    assert_eq!(cache.display_status.progress, 0.24);
+}
+
+#[test]
+fn display_status_message_can_be_cleared() {
+    let mut cache = parse_into_cache(json!({ "display_status": { "message": "Heating", "progress": 0.0 } }));
+
+    for event in parse(json!({ "display_status": { "message": null } })) {
+        cache.complete_event(event);
+    }
+
+    assert_eq!(cache.display_status.message, "");
+}
+
+#[test]
+fn parses_filament_sensors_and_output_pins() {
+    let cache = parse_into_cache(json!({
+        "filament_switch_sensor runout": { "filament_detected": true, "enabled": false },
+        "output_pin caselight": { "value": 0.5 },
+    }));
+
+    let sensor = &cache.filament_switch_sensors[0];
+    assert_eq!(sensor.name, "runout");
+    assert!(sensor.sensor.filament_detected);
+    assert!(!sensor.sensor.enabled);
+
+    let pin = &cache.output_pins[0];
+    assert_eq!(pin.name, "caselight");
+    assert_eq!(pin.pin.value, 0.5);
+}
+
+#[test]
+fn parses_exclude_object() {
+    let cache = parse_into_cache(json!({
+        "exclude_object": {
+            "objects": [
+                { "name": "PART_1", "polygon": [[10.0, 10.0], [20.0, 10.0], [20.0, 20.0]], "center": [15.0, 15.0] },
+                { "name": "PART_2", "polygon": [], "center": [50.0, 50.0] },
+            ],
+            "excluded_objects": ["PART_2"],
+            "current_object": "PART_1",
+        }
+    }));
+
+    assert_eq!(cache.exclude_object.objects.len(), 2);
+    assert_eq!(
+        cache.exclude_object.objects[0],
+        ExcludeObjectDefinition {
+            name: "PART_1".to_string(),
+            polygon: vec![[10.0, 10.0], [20.0, 10.0], [20.0, 20.0]],
+            center: [15.0, 15.0],
+        }
+    );
+    assert_eq!(cache.exclude_object.excluded_objects, vec!["PART_2"]);
+    assert_eq!(cache.exclude_object.current_object.as_deref(), Some("PART_1"));
+}
+
+#[test]
+fn parses_configfile() {
+    let cache = parse_into_cache(json!({
+        "configfile": {
+            "settings": {
+                "extruder": { "nozzle_diameter": 0.4, "max_temp": 300.0 },
+                "heater_bed": { "max_temp": 120.0 },
+            },
+            "save_config_pending": true,
+        }
+    }));
+
+    assert_eq!(cache.configfile.nozzle_diameter("extruder"), Some(0.4));
+    assert_eq!(cache.configfile.max_temp("heater_bed"), Some(120.0));
+    assert!(cache.configfile.save_config_pending);
+}
+
+#[test]
+fn skips_unknown_objects() {
+    let events = parse(json!({
+        "gcode_macro START_PRINT": { "variable_bed_temp": 60 },
+        "bed_mesh": { "profile_name": "default" },
+        "webhooks": { "state": "ready" },
+    }));
+
+    assert!(matches!(&events[..], [OptionalPrinterEvent::Webhooks(_)]));
+}
+
+#[test]
+fn rejects_malformed_objects() {
+    let result = serde_json::from_value::<MoonrakerEventNotifyStatusUpdate>(json!({
+        "extruder": { "temperature": "hot" }
+    }));
+
+    assert!(result.is_err());
+}
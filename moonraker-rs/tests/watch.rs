@@ -0,0 +1,94 @@
+mod common;
+
+use common::{MockServer, TEST_TIMEOUT, connect};
+use moonraker_rs::{
+    connector::read_deserialize::MoonrakerEventNotifyStatusUpdate, moonraker_connection::DEFAULT_REQUEST_TIMEOUT,
+    printer_objects::NamedExtruder,
+};
+use serde_json::json;
+use tokio::{sync::watch, time::timeout};
+
+fn extruder_temperature(extruders: &[NamedExtruder]) -> Option<f32> {
+    extruders.first().map(|extruder| extruder.extruder.temperature)
+}
+
+async fn wait_for_temperature(receiver: &mut watch::Receiver<Vec<NamedExtruder>>, temperature: f32) {
+    timeout(TEST_TIMEOUT, receiver.wait_for(|extruders| extruder_temperature(extruders) == Some(temperature)))
+        .await
+        .expect("Timed out waiting for the extruder temperature")
+        .expect("Watch channel closed");
+}
+
+#[tokio::test]
+async fn watchers_see_the_latest_value() {
+    let mut server = MockServer::start().await;
+    let (connection, _listener) = connect(&server, DEFAULT_REQUEST_TIMEOUT);
+    let mut extruders = connection.watch_extruders();
+
+    let mut client = server.accept().await;
+    client.handshake(json!({ "extruder": { "temperature": 20.0, "target": 210.0 } })).await;
+    for temperature in [40.0, 60.0, 80.0] {
+        client.notify_status_update(json!({ "extruder": { "temperature": temperature } })).await;
+    }
+
+    // Intermediate values may be skipped, the last one never is
+    wait_for_temperature(&mut extruders, 80.0).await;
+    assert_eq!(extruders.borrow()[0].extruder.target, 210.0);
+
+    let snapshot = connection.cache_snapshot().await;
+    assert_eq!(extruder_temperature(&snapshot.extruders), Some(80.0));
+    assert_eq!(snapshot.extruders[0].extruder.target, 210.0);
+
+    // Subscribing late still starts from the latest value
+    let late = connection.watch_extruders();
+    assert_eq!(extruder_temperature(&late.borrow()), Some(80.0));
+}
+
+#[tokio::test]
+async fn watchers_carry_over_reconnects() {
+    let mut server = MockServer::start().await;
+    let (connection, _listener) = connect(&server, DEFAULT_REQUEST_TIMEOUT);
+    let mut extruders = connection.watch_extruders();
+
+    let mut client = server.accept().await;
+    client
+        .handshake(json!({ "extruder": { "temperature": 20.0 }, "heater_generic chamber": { "temperature": 30.0 } }))
+        .await;
+    wait_for_temperature(&mut extruders, 20.0).await;
+    client.close().await;
+
+    let mut heater_generics = connection.watch_heater_generics();
+    assert_eq!(heater_generics.borrow().len(), 1);
+
+    let mut client = server.accept().await;
+    client.handshake(json!({ "extruder": { "temperature": 25.0 } })).await;
+    wait_for_temperature(&mut extruders, 25.0).await;
+
+    // Objects the new connection doesn't have are gone from the watchers as well
+    timeout(TEST_TIMEOUT, heater_generics.wait_for(|heaters| heaters.is_empty()))
+        .await
+        .expect("Timed out waiting for the chamber heater to be dropped")
+        .expect("Watch channel closed");
+    assert!(connection.cache_snapshot().await.heater_generics.is_empty());
+}
+
+#[tokio::test]
+async fn snapshots_do_not_publish() {
+    let mut server = MockServer::start().await;
+    let (connection, _listener) = connect(&server, DEFAULT_REQUEST_TIMEOUT);
+    let mut extruders = connection.watch_extruders();
+
+    let mut client = server.accept().await;
+    client.handshake(json!({ "extruder": { "temperature": 20.0 } })).await;
+    wait_for_temperature(&mut extruders, 20.0).await;
+
+    let mut snapshot = connection.cache_snapshot().await;
+    let update: MoonrakerEventNotifyStatusUpdate =
+        serde_json::from_value(json!({ "extruder": { "temperature": 99.0 } })).unwrap();
+    for event in update.events {
+        snapshot.complete_event(event);
+    }
+
+    assert_eq!(extruder_temperature(&snapshot.extruders), Some(99.0));
+    assert_eq!(extruder_temperature(&extruders.borrow()), Some(20.0));
+}
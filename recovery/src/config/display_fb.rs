@@ -2,7 +2,7 @@ use crate::{
     config::DisplayInit,
     AppWindow,
 };
-use driver::init_framebuffer;
+use driver::{init_framebuffer, FramebufferOptions};
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize)]
@@ -21,7 +21,10 @@ impl DisplayInit for DisplayFramebufferConfig {
             _ => panic!("Config value {:?} is unsupported for buffering. Supported is \"Single\" or \"Double\"", self.buffering)
         };
 
-        slint::platform::set_platform(init_framebuffer(self.fb_path.clone(), self.event_path.clone(), double_buffering)).expect("Failed to set platform");
+        let mut options = FramebufferOptions::new(self.fb_path.clone(), self.event_path.clone());
+        options.double_buffering = double_buffering;
+
+        slint::platform::set_platform(init_framebuffer(options).expect("Failed to initialise framebuffer")).expect("Failed to set platform");
         let ui = AppWindow::new().expect("Failed to initialize window");
 
         ui
@@ -0,0 +1,134 @@
+use std::sync::{Arc, Mutex};
+
+use fastwebsockets::{FragmentCollectorRead, Frame, OpCode, Payload, WebSocket, WebSocketError};
+use hyper::upgrade::Upgraded;
+use hyper_util::rt::TokioIo;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tokio::sync::mpsc::{self, UnboundedSender};
+
+use crate::{rpc::handle_request, simulator::Simulator, subscription::Subscription};
+
+#[derive(Deserialize)]
+struct JsonRpcRequest {
+    method: String,
+    params: Option<Value>,
+    id: Option<Value>,
+}
+
+/// Serves one websocket client until it disconnects. Requests are answered
+/// concurrently, as a waiting M109 must not hold up everything else.
+pub async fn serve_websocket(simulator: Arc<Simulator>, websocket: WebSocket<TokioIo<Upgraded>>) -> Result<(), WebSocketError> {
+    let (reader, mut writer) = websocket.split(tokio::io::split);
+    let mut reader = FragmentCollectorRead::new(reader);
+    let (outbound_sender, mut outbound_receiver) = mpsc::unbounded_channel::<Frame<'static>>();
+    let subscription = Arc::new(Mutex::new(Subscription::default()));
+
+    let writer_handle = tokio::spawn(async move {
+        while let Some(frame) = outbound_receiver.recv().await {
+            let close = frame.opcode == OpCode::Close;
+            if writer.write_frame(frame).await.is_err() || close {
+                break;
+            }
+        }
+    });
+
+    let updates_handle = {
+        let simulator = simulator.clone();
+        let subscription = subscription.clone();
+        let outbound_sender = outbound_sender.clone();
+        tokio::spawn(async move { send_updates(&simulator, &subscription, &outbound_sender).await })
+    };
+
+    let result = loop {
+        let sender = outbound_sender.clone();
+        let frame = match reader
+            .read_frame(&mut move |frame| {
+                // Pongs and close replies
+                let _ = sender.send(frame);
+                async { Ok::<(), std::io::Error>(()) }
+            })
+            .await
+        {
+            Ok(frame) => frame,
+            Err(e) => break Err(e),
+        };
+
+        match frame.opcode {
+            OpCode::Close => break Ok(()),
+            OpCode::Text => {
+                let request = match serde_json::from_slice::<JsonRpcRequest>(&frame.payload) {
+                    Ok(request) => request,
+                    Err(e) => {
+                        eprintln!("Ignoring invalid request: {}", e);
+                        continue;
+                    }
+                };
+
+                let simulator = simulator.clone();
+                let subscription = subscription.clone();
+                let sender = outbound_sender.clone();
+                tokio::spawn(async move {
+                    let result = handle_request(&simulator, &subscription, &request.method, request.params.unwrap_or(Value::Null)).await;
+
+                    // Notifications from the client don't get a reply
+                    let Some(id) = request.id else {
+                        return;
+                    };
+
+                    let response = match result {
+                        Ok(result) => json!({ "jsonrpc": "2.0", "result": result, "id": id }),
+                        Err(e) => {
+                            println!("{} failed: {}", request.method, e);
+                            json!({ "jsonrpc": "2.0", "error": { "code": e.code, "message": e.message }, "id": id })
+                        }
+                    };
+                    send_json(&sender, &response);
+                });
+            }
+            _ => {}
+        }
+    };
+
+    updates_handle.abort();
+    drop(outbound_sender);
+    let _ = writer_handle.await;
+
+    result
+}
+
+/// Forwards status changes of the subscribed objects and Moonraker's own notifications.
+async fn send_updates(simulator: &Simulator, subscription: &Mutex<Subscription>, sender: &UnboundedSender<Frame<'static>>) {
+    let mut status = simulator.subscribe_status();
+    let mut notifications = simulator.subscribe_notifications();
+
+    loop {
+        tokio::select! {
+            changed = status.changed() => {
+                if changed.is_err() {
+                    break;
+                }
+
+                let snapshot = status.borrow_and_update().clone();
+                let changes = subscription.lock().unwrap().changes(&snapshot.objects);
+
+                if !changes.is_empty() {
+                    send_json(sender, &json!({
+                        "jsonrpc": "2.0",
+                        "method": "notify_status_update",
+                        "params": [changes, snapshot.eventtime],
+                    }));
+                }
+            }
+            notification = notifications.recv() => match notification {
+                Ok(notification) => send_json(sender, &notification),
+                Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+            },
+        }
+    }
+}
+
+fn send_json(sender: &UnboundedSender<Frame<'static>>, value: &Value) {
+    let _ = sender.send(Frame::text(Payload::Owned(value.to_string().into_bytes())));
+}
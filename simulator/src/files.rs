@@ -0,0 +1,201 @@
+use std::io::Cursor;
+
+use image::{ImageFormat, Rgba, RgbaImage};
+use serde_json::{json, Value};
+
+use crate::printer::unix_time;
+
+const THUMBNAIL_SIZES: [u32; 2] = [32, 300];
+
+pub struct Thumbnail {
+    pub width: u32,
+    pub height: u32,
+    /// Relative to the gcodes root.
+    pub path: String,
+    pub png: Vec<u8>,
+}
+
+/// A G-code file that only exists as metadata and thumbnails.
+pub struct SimulatedFile {
+    pub path: String,
+    pub size: i32,
+    pub modified: f64,
+    pub estimated_time: f32,
+    pub layer_height: f32,
+    pub first_layer_height: f32,
+    pub object_height: f32,
+    pub first_layer_extr_temp: f32,
+    pub first_layer_bed_temp: f32,
+    pub filament_type: String,
+    pub filament_name: String,
+    /// Millimeters.
+    pub filament_total: f32,
+    pub thumbnails: Vec<Thumbnail>,
+}
+
+impl SimulatedFile {
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        path: &str,
+        age_in_days: f64,
+        estimated_time: f32,
+        layer_height: f32,
+        object_height: f32,
+        (first_layer_extr_temp, first_layer_bed_temp): (f32, f32),
+        (filament_type, filament_name): (&str, &str),
+        color: Option<[u8; 3]>,
+    ) -> Self {
+        let now = unix_time();
+
+        let thumbnails = match color {
+            Some(color) => THUMBNAIL_SIZES.iter().map(|size| render_thumbnail(path, *size, color)).collect(),
+            None => vec![],
+        };
+
+        Self {
+            path: path.to_string(),
+            // Roughly what a slicer writes per second of printing
+            size: (estimated_time * 420.0) as i32,
+            modified: now - age_in_days * 86400.0,
+            estimated_time,
+            layer_height,
+            first_layer_height: layer_height,
+            object_height,
+            first_layer_extr_temp,
+            first_layer_bed_temp,
+            filament_type: filament_type.to_string(),
+            filament_name: filament_name.to_string(),
+            filament_total: estimated_time * 0.9,
+            thumbnails,
+        }
+    }
+
+    pub fn total_layers(&self) -> i32 {
+        (((self.object_height - self.first_layer_height) / self.layer_height).round() as i32 + 1).max(1)
+    }
+
+    pub fn list_entry(&self) -> Value {
+        json!({
+            "path": self.path,
+            "modified": self.modified,
+            "size": self.size,
+            "permissions": "rw",
+        })
+    }
+
+    pub fn thumbnails(&self) -> Value {
+        Value::Array(
+            self.thumbnails
+                .iter()
+                .map(|thumbnail| {
+                    json!({
+                        "width": thumbnail.width,
+                        "height": thumbnail.height,
+                        "size": thumbnail.png.len(),
+                        "thumbnail_path": thumbnail.path,
+                    })
+                })
+                .collect(),
+        )
+    }
+
+    pub fn metadata(&self) -> Value {
+        // Thumbnail paths are relative to the file in metadata
+        let directory = self.path.rsplit_once('/').map(|(directory, _)| format!("{}/", directory)).unwrap_or_default();
+        let thumbnails: Vec<Value> = self
+            .thumbnails
+            .iter()
+            .map(|thumbnail| {
+                json!({
+                    "width": thumbnail.width,
+                    "height": thumbnail.height,
+                    "size": thumbnail.png.len(),
+                    "relative_path": thumbnail.path.strip_prefix(&directory).unwrap_or(&thumbnail.path),
+                })
+            })
+            .collect();
+
+        json!({
+            "size": self.size,
+            "modified": self.modified,
+            "uuid": format!("00000000-0000-4000-8000-{:012x}", self.size),
+            "file_processors": [],
+            "slicer": "PrusaSlicer",
+            "slicer_version": "2.8.1",
+            "gcode_start_byte": 8192,
+            "gcode_end_byte": self.size - 4096,
+            "object_height": self.object_height,
+            "estimated_time": self.estimated_time,
+            "nozzle_diameter": 0.4,
+            "layer_height": self.layer_height,
+            "first_layer_height": self.first_layer_height,
+            "first_layer_extr_temp": self.first_layer_extr_temp,
+            "first_layer_bed_temp": self.first_layer_bed_temp,
+            "chamber_temp": 0.0,
+            "filament_name": self.filament_name,
+            "filament_colors": [],
+            "extruder_colors": [],
+            "filament_temps": [self.first_layer_extr_temp as i32],
+            "filament_type": self.filament_type,
+            "filament_total": self.filament_total,
+            "filament_change_count": 0,
+            "filament_weight_total": self.filament_total * 0.003,
+            "filament_weights": [self.filament_total * 0.003],
+            "mmu_print": 0,
+            "referenced_tools": [],
+            "thumbnails": thumbnails,
+            "job_id": null,
+            "print_start_time": null,
+            "filename": self.path,
+        })
+    }
+}
+
+pub fn simulated_files() -> Vec<SimulatedFile> {
+    vec![
+        SimulatedFile::new("benchy.gcode", 0.2, 6120.0, 0.2, 48.0, (210.0, 60.0), ("PLA", "Generic PLA"), Some([230, 120, 30])),
+        SimulatedFile::new("vase_spiral.gcode", 3.0, 9300.0, 0.3, 150.0, (240.0, 80.0), ("PETG", "Generic PETG"), Some([40, 120, 220])),
+        SimulatedFile::new("enclosure_clip.gcode", 12.0, 780.0, 0.2, 8.0, (240.0, 80.0), ("PETG", "Generic PETG"), Some([90, 90, 90])),
+        SimulatedFile::new("calibration/cube_20mm.gcode", 30.0, 1140.0, 0.2, 20.0, (205.0, 60.0), ("PLA", "Generic PLA"), Some([200, 200, 200])),
+        SimulatedFile::new("calibration/first_layer.gcode", 31.0, 300.0, 0.2, 0.2, (205.0, 60.0), ("PLA", "Generic PLA"), Some([60, 200, 90])),
+        SimulatedFile::new("no_thumbnail.gcode", 60.0, 2400.0, 0.25, 30.0, (220.0, 60.0), ("PLA", "Generic PLA"), None),
+    ]
+}
+
+/// A shaded disc with layer lines, enough to tell files apart.
+fn render_thumbnail(path: &str, size: u32, [red, green, blue]: [u8; 3]) -> Thumbnail {
+    let image = RgbaImage::from_fn(size, size, |x, y| {
+        let dx = (x as f32 + 0.5) / size as f32 - 0.5;
+        let dy = (y as f32 + 0.5) / size as f32 - 0.5;
+        let distance = (dx * dx + dy * dy).sqrt();
+
+        if distance > 0.4 {
+            return Rgba([0, 0, 0, 0]);
+        }
+
+        let layer_line = match (y * 24 / size) % 2 {
+            0 => 1.0,
+            _ => 0.9,
+        };
+        let shade = (1.0 - distance - dx * 0.5) * layer_line;
+        let scale = |channel: u8| (channel as f32 * shade).clamp(0.0, 255.0) as u8;
+
+        Rgba([scale(red), scale(green), scale(blue), 255])
+    });
+
+    let mut png = Cursor::new(vec![]);
+    image.write_to(&mut png, ImageFormat::Png).expect("Failed to encode a thumbnail");
+
+    let (directory, file_name) = match path.rsplit_once('/') {
+        Some((directory, file_name)) => (format!("{}/", directory), file_name),
+        None => (String::new(), path),
+    };
+    let stem = file_name.strip_suffix(".gcode").unwrap_or(file_name);
+
+    Thumbnail {
+        width: size,
+        height: size,
+        path: format!("{}.thumbs/{}-{}x{}.png", directory, stem, size, size),
+        png: png.into_inner(),
+    }
+}
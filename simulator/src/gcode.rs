@@ -0,0 +1,183 @@
+use crate::printer::{Printer, Wait, MIN_EXTRUDE_TEMP};
+
+/// Seconds the filament macros take.
+const FILAMENT_MACRO_TIME: f64 = 8.0;
+
+/// One line of G-code, either classic (`G1 X10 F3000`) or extended
+/// (`SET_HEATER_TEMPERATURE HEATER=extruder TARGET=200`).
+struct GcodeCommand<'a> {
+    name: String,
+    arguments: &'a str,
+}
+
+impl<'a> GcodeCommand<'a> {
+    fn parse(line: &'a str) -> Self {
+        let (name, arguments) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+
+        Self {
+            name: name.to_uppercase(),
+            arguments: arguments.trim(),
+        }
+    }
+
+    /// `key` is a single letter for classic commands, a `KEY=` name otherwise.
+    fn get(&self, key: &str) -> Option<&'a str> {
+        self.arguments.split_whitespace().find_map(|word| match key.len() {
+            1 => word.get(..1).filter(|letter| letter.eq_ignore_ascii_case(key)).map(|_| &word[1..]),
+            _ => word.split_once('=').filter(|(name, _)| name.eq_ignore_ascii_case(key)).map(|(_, value)| value),
+        })
+    }
+
+    fn float(&self, key: &str) -> Result<Option<f32>, String> {
+        match self.get(key) {
+            Some(value) => value.parse().map(Some).map_err(|_| format!("Unable to parse '{}' as a float", value)),
+            None => Ok(None),
+        }
+    }
+
+    fn required(&self, key: &str) -> Result<&'a str, String> {
+        self.get(key).ok_or_else(|| format!("Error on '{} {}': missing {}", self.name, self.arguments, key))
+    }
+}
+
+impl Printer {
+    /// Runs a single line, returning what has to happen before the next one.
+    pub fn run_gcode_line(&mut self, line: &str) -> Result<Option<Wait>, String> {
+        let line = line.split(';').next().unwrap_or_default().trim();
+        if line.is_empty() {
+            return Ok(None);
+        }
+
+        let command = GcodeCommand::parse(line);
+
+        if !matches!(command.name.as_str(), "RESTART" | "FIRMWARE_RESTART" | "STATUS") {
+            self.require_ready()?;
+        }
+        self.last_activity = self.eventtime;
+
+        match command.name.as_str() {
+            "G0" | "G1" => self.linear_move(&command)?,
+            "G4" => return Ok(Some(Wait::Until(self.eventtime + command.float("P")?.unwrap_or_default() as f64 / 1000.0))),
+            "G28" => {
+                let axes: String = ["X", "Y", "Z"].into_iter().filter(|axis| command.get(axis).is_some()).collect();
+                self.toolhead.home(&match axes.is_empty() {
+                    true => String::from("xyz"),
+                    false => axes.to_lowercase(),
+                });
+            }
+            "G90" => self.absolute_coordinates = true,
+            "G91" => self.absolute_coordinates = false,
+            "M82" => self.absolute_extrude = true,
+            "M83" => self.absolute_extrude = false,
+            "G92" => {
+                let mut position = self.toolhead.commanded_position();
+                for (index, axis) in ["X", "Y", "Z", "E"].into_iter().enumerate() {
+                    if let Some(value) = command.float(axis)? {
+                        position[index] = value;
+                    }
+                }
+                self.toolhead.set_position(position);
+            }
+            "M18" | "M84" => self.toolhead.motors_off(),
+            "M104" | "M109" => return self.set_temperature(&command, "extruder", command.name == "M109"),
+            "M140" | "M190" => return self.set_temperature(&command, "heater_bed", command.name == "M190"),
+            "M141" | "M191" => return self.set_temperature(&command, "chamber", command.name == "M191"),
+            "SET_HEATER_TEMPERATURE" => {
+                let heater = command.required("HEATER")?;
+                let target = command.float("TARGET")?.unwrap_or_default();
+                let (_, heater) = self.heater_mut(heater).ok_or_else(|| format!("Unknown heater {}", heater))?;
+                heater.set_target(target)?;
+            }
+            "TURN_OFF_HEATERS" => self.turn_off_heaters(),
+            "M106" => self.fan_speed = (command.float("S")?.unwrap_or(255.0) / 255.0).clamp(0.0, 1.0),
+            "M107" => self.fan_speed = 0.0,
+            "M220" => self.speed_factor = command.float("S")?.unwrap_or(100.0).max(1.0) / 100.0,
+            "M221" => self.extrude_factor = command.float("S")?.unwrap_or(100.0).max(1.0) / 100.0,
+            "M117" => self.display_message = Some(command.arguments.to_string()).filter(|message| !message.is_empty()),
+            "M73" | "M114" | "M400" | "STATUS" => {}
+            "M112" => self.emergency_stop(),
+            "SET_GCODE_OFFSET" => {
+                if let Some(z) = command.float("Z")? {
+                    self.z_offset = z;
+                }
+                if let Some(z_adjust) = command.float("Z_ADJUST")? {
+                    self.z_offset += z_adjust;
+                }
+            }
+            "SDCARD_PRINT_FILE" => self.start_print(command.required("FILENAME")?)?,
+            "SDCARD_RESET_FILE" if !self.is_printing() => self.print = None,
+            "SDCARD_RESET_FILE" => return Err(String::from("SD busy")),
+            "PAUSE" => self.pause_print()?,
+            "RESUME" => self.resume_print()?,
+            "CANCEL_PRINT" => self.cancel_print()?,
+            "EXCLUDE_OBJECT" => {
+                let name = command.required("NAME")?.to_uppercase();
+                let print = self.print.as_mut().filter(|print| print.is_active() && print.object_name() == name);
+                let print = print.ok_or_else(|| format!("Unknown object {}", name))?;
+                if !print.excluded_objects.contains(&name) {
+                    print.excluded_objects.push(name);
+                }
+            }
+            "SET_FILAMENT_SENSOR" => {
+                if let Some(enable) = command.float("ENABLE")? {
+                    self.filament_sensor_enabled = enable != 0.0;
+                }
+            }
+            "LOAD_FILAMENT" | "UNLOAD_FILAMENT" => return self.filament_macro(command.name == "LOAD_FILAMENT").map(Some),
+            "CLEAN_NOZZLE" => return Ok(Some(Wait::Until(self.eventtime + 3.0))),
+            "RESTART" | "FIRMWARE_RESTART" => self.restart()?,
+            name => return Err(format!("Unknown command:\"{}\"", name)),
+        }
+
+        Ok(None)
+    }
+
+    fn linear_move(&mut self, command: &GcodeCommand) -> Result<(), String> {
+        let current = self.toolhead.commanded_position();
+        let mut target = current;
+
+        for (index, axis) in ["X", "Y", "Z", "E"].into_iter().enumerate() {
+            if let Some(value) = command.float(axis)? {
+                let absolute = if index == 3 { self.absolute_extrude } else { self.absolute_coordinates };
+                // The z offset is applied through the homing origin
+                let origin = if index == 2 { self.z_offset } else { 0.0 };
+
+                target[index] = match absolute {
+                    true => value + origin,
+                    false => target[index] + value,
+                };
+            }
+        }
+
+        if target[3] != current[3] && self.extruder.temperature < MIN_EXTRUDE_TEMP {
+            return Err(String::from("Extrude below minimum temp\nSee the 'min_extrude_temp' config option for details"));
+        }
+
+        if let Some(feedrate) = command.float("F")? {
+            self.speed = feedrate / 60.0;
+        }
+
+        self.toolhead.move_to(target, self.speed * self.speed_factor)
+    }
+
+    fn set_temperature(&mut self, command: &GcodeCommand, heater: &str, wait: bool) -> Result<Option<Wait>, String> {
+        let target = command.float("S")?.unwrap_or_default();
+        let (name, heater) = self.heater_mut(heater).ok_or_else(|| format!("Unknown heater {}", heater))?;
+        heater.set_target(target)?;
+
+        Ok(wait.then_some(Wait::Heater(name)))
+    }
+
+    /// Pushes or pulls 100mm of filament through a hot nozzle.
+    fn filament_macro(&mut self, load: bool) -> Result<Wait, String> {
+        if self.extruder.temperature < MIN_EXTRUDE_TEMP {
+            return Err(String::from("Extrude below minimum temp\nSee the 'min_extrude_temp' config option for details"));
+        }
+
+        let mut target = self.toolhead.commanded_position();
+        target[3] += if load { 100.0 } else { -100.0 };
+        self.toolhead.move_to(target, 100.0 / FILAMENT_MACRO_TIME as f32)?;
+
+        Ok(Wait::Until(self.eventtime + FILAMENT_MACRO_TIME))
+    }
+}
@@ -0,0 +1,134 @@
+use serde_json::{json, Value};
+
+pub const AMBIENT_TEMPERATURE: f32 = 22.0;
+
+/// A first order thermal model: at a given power the temperature settles
+/// at ambient plus `power * max_rise`, approaching it exponentially.
+pub struct Heater {
+    pub temperature: f32,
+    pub target: f32,
+    pub power: f32,
+    pub max_temp: f32,
+    /// Degrees above ambient reached at full power.
+    max_rise: f32,
+    /// Seconds to get about two thirds of the way to the settling temperature.
+    time_constant: f32,
+}
+
+impl Heater {
+    pub fn new(max_temp: f32, max_rise: f32, time_constant: f32) -> Self {
+        Self {
+            temperature: AMBIENT_TEMPERATURE,
+            target: 0.0,
+            power: 0.0,
+            max_temp,
+            max_rise,
+            time_constant,
+        }
+    }
+
+    pub fn set_target(&mut self, target: f32) -> Result<(), String> {
+        if target != 0.0 && !(0.0..=self.max_temp).contains(&target) {
+            return Err(format!("Requested temperature ({:.1}) out of range (0.0:{:.1})", target, self.max_temp));
+        }
+
+        self.target = target;
+        Ok(())
+    }
+
+    pub fn tick(&mut self, dt: f32) {
+        // Feed forward for the holding power plus a proportional term, like a tuned PID would settle
+        self.power = match self.target > 0.0 {
+            true => ((self.target - AMBIENT_TEMPERATURE) / self.max_rise + (self.target - self.temperature) * 0.1).clamp(0.0, 1.0),
+            false => 0.0,
+        };
+
+        let settling_temperature = AMBIENT_TEMPERATURE + self.power * self.max_rise;
+        self.temperature += (settling_temperature - self.temperature) * (1.0 - (-dt / self.time_constant).exp());
+    }
+
+    /// What M109 and M190 wait for.
+    pub fn reached_target(&self) -> bool {
+        self.target <= 0.0 || (self.temperature - self.target).abs() < 1.0
+    }
+
+    pub fn status(&self) -> Value {
+        json!({
+            "temperature": round(self.temperature),
+            "target": self.target,
+            "power": round(self.power),
+        })
+    }
+}
+
+/// Like Klipper, report two decimals so idle heaters don't send updates every tick.
+pub fn round(value: f32) -> f32 {
+    (value * 100.0).round() / 100.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Steps the model like the simulator does at its default speed.
+    fn run(heater: &mut Heater, seconds: f32) {
+        for _ in 0..(seconds / 0.25) as usize {
+            heater.tick(0.25);
+        }
+    }
+
+    #[test]
+    fn heats_up_gradually() {
+        let mut heater = Heater::new(300.0, 330.0, 40.0);
+        heater.set_target(210.0).unwrap();
+
+        run(&mut heater, 10.0);
+
+        assert!(heater.temperature > AMBIENT_TEMPERATURE + 10.0 && heater.temperature < 200.0, "{}", heater.temperature);
+        assert_eq!(heater.power, 1.0);
+        assert!(!heater.reached_target());
+    }
+
+    #[test]
+    fn settles_at_the_target() {
+        // Extruder, bed and chamber as the printer sets them up
+        for (max_temp, max_rise, time_constant, target) in [(300.0, 330.0, 40.0, 210.0), (120.0, 110.0, 90.0, 60.0), (70.0, 50.0, 300.0, 45.0)] {
+            let mut heater = Heater::new(max_temp, max_rise, time_constant);
+            heater.set_target(target).unwrap();
+
+            run(&mut heater, 3600.0);
+
+            assert!((heater.temperature - target).abs() < 0.1, "{} instead of {}", heater.temperature, target);
+            assert!(heater.reached_target());
+            // Holding a temperature takes less than full power
+            assert!(heater.power > 0.0 && heater.power < 1.0, "{}", heater.power);
+        }
+    }
+
+    #[test]
+    fn cools_down_to_ambient_when_turned_off() {
+        let mut heater = Heater::new(300.0, 330.0, 40.0);
+        heater.set_target(210.0).unwrap();
+        run(&mut heater, 600.0);
+
+        heater.set_target(0.0).unwrap();
+        run(&mut heater, 1200.0);
+
+        assert!((heater.temperature - AMBIENT_TEMPERATURE).abs() < 0.5, "{}", heater.temperature);
+        assert_eq!(heater.power, 0.0);
+        assert!(heater.reached_target());
+    }
+
+    #[test]
+    fn rejects_targets_out_of_range() {
+        let mut heater = Heater::new(120.0, 110.0, 90.0);
+        heater.set_target(60.0).unwrap();
+
+        assert!(heater.set_target(121.0).is_err());
+        assert!(heater.set_target(-5.0).is_err());
+        assert_eq!(heater.target, 60.0);
+
+        assert!(heater.set_target(0.0).is_ok());
+        assert_eq!(heater.target, 0.0);
+    }
+}
@@ -0,0 +1,122 @@
+use serde_json::{json, Value};
+
+pub struct HistoryJob {
+    pub job_id: String,
+    pub filename: String,
+    /// Moonraker's job status, e.g. "in_progress" or "completed".
+    pub status: &'static str,
+    pub start_time: f64,
+    pub end_time: Option<f64>,
+    pub print_duration: f32,
+    pub total_duration: f32,
+    pub filament_used: f32,
+    pub metadata: Value,
+}
+
+impl HistoryJob {
+    pub fn to_json(&self) -> Value {
+        json!({
+            "job_id": self.job_id,
+            "exists": true,
+            "end_time": self.end_time,
+            "filament_used": self.filament_used,
+            "filename": self.filename,
+            "metadata": self.metadata,
+            "print_duration": self.print_duration,
+            "status": self.status,
+            "start_time": self.start_time,
+            "total_duration": self.total_duration,
+        })
+    }
+}
+
+#[derive(Default)]
+struct Totals {
+    total_jobs: u32,
+    total_time: f32,
+    total_print_time: f32,
+    total_filament_used: f32,
+    longest_job: f32,
+    longest_print: f32,
+}
+
+impl Totals {
+    fn to_json(&self) -> Value {
+        json!({
+            "total_jobs": self.total_jobs,
+            "total_time": self.total_time,
+            "total_print_time": self.total_print_time,
+            "total_filament_used": self.total_filament_used,
+            "longest_job": self.longest_job,
+            "longest_print": self.longest_print,
+        })
+    }
+}
+
+#[derive(Default)]
+pub struct History {
+    /// Oldest first.
+    jobs: Vec<HistoryJob>,
+    totals: Totals,
+    next_id: u32,
+}
+
+impl History {
+    pub fn next_job_id(&mut self) -> String {
+        self.next_id += 1;
+        format!("{:06X}", self.next_id)
+    }
+
+    pub fn add(&mut self, job: HistoryJob) -> Value {
+        let job_json = job.to_json();
+        self.jobs.push(job);
+        job_json
+    }
+
+    /// Records the end of a job, returning it as sent with `notify_history_changed`.
+    pub fn finish(&mut self, job_id: &str, status: &'static str, end_time: f64, print_duration: f32, total_duration: f32, filament_used: f32) -> Option<Value> {
+        let job = self.jobs.iter_mut().find(|job| job.job_id == job_id)?;
+        job.status = status;
+        job.end_time = Some(end_time);
+        job.print_duration = print_duration;
+        job.total_duration = total_duration;
+        job.filament_used = filament_used;
+
+        self.totals.total_jobs += 1;
+        self.totals.total_time += total_duration;
+        self.totals.total_print_time += print_duration;
+        self.totals.total_filament_used += filament_used;
+        self.totals.longest_job = self.totals.longest_job.max(total_duration);
+        self.totals.longest_print = self.totals.longest_print.max(print_duration);
+
+        Some(job.to_json())
+    }
+
+    pub fn list(&self, start: usize, limit: usize) -> Value {
+        let jobs: Vec<Value> = self.jobs.iter().rev().skip(start).take(limit).map(HistoryJob::to_json).collect();
+
+        json!({
+            "count": self.jobs.len(),
+            "jobs": jobs,
+        })
+    }
+
+    pub fn get(&self, job_id: &str) -> Option<Value> {
+        self.jobs.iter().find(|job| job.job_id == job_id).map(HistoryJob::to_json)
+    }
+
+    pub fn delete(&mut self, job_id: &str) -> Vec<String> {
+        let deleted: Vec<String> = self.jobs.iter().filter(|job| job.job_id == job_id).map(|job| job.job_id.clone()).collect();
+        self.jobs.retain(|job| job.job_id != job_id);
+        deleted
+    }
+
+    pub fn totals(&self) -> Value {
+        self.totals.to_json()
+    }
+
+    /// Returns the totals from before the reset.
+    pub fn reset_totals(&mut self) -> Value {
+        std::mem::take(&mut self.totals).to_json()
+    }
+}
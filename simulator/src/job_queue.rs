@@ -0,0 +1,93 @@
+use serde_json::{json, Value};
+
+struct QueuedJob {
+    job_id: String,
+    filename: String,
+    time_added: f64,
+}
+
+/// Moonraker's job queue. Jobs start when the printer is idle and the queue
+/// is ready, a failed or cancelled print pauses it.
+pub struct JobQueue {
+    jobs: Vec<QueuedJob>,
+    pub paused: bool,
+    next_id: u32,
+}
+
+impl JobQueue {
+    pub fn new() -> Self {
+        Self { jobs: vec![], paused: false, next_id: 0 }
+    }
+
+    pub fn add(&mut self, filenames: &[String], reset: bool, now: f64) {
+        if reset {
+            self.jobs.clear();
+        }
+
+        for filename in filenames {
+            self.next_id += 1;
+            self.jobs.push(QueuedJob {
+                job_id: format!("{:016X}", self.next_id),
+                filename: filename.clone(),
+                time_added: now,
+            });
+        }
+    }
+
+    pub fn remove(&mut self, job_ids: &[String]) {
+        self.jobs.retain(|job| !job_ids.contains(&job.job_id));
+    }
+
+    pub fn jump(&mut self, job_id: &str) -> bool {
+        match self.jobs.iter().position(|job| job.job_id == job_id) {
+            Some(index) => {
+                let job = self.jobs.remove(index);
+                self.jobs.insert(0, job);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.jobs.is_empty()
+    }
+
+    /// The next file to print, if the queue isn't paused.
+    pub fn pop_next(&mut self) -> Option<String> {
+        match self.paused || self.jobs.is_empty() {
+            true => None,
+            false => Some(self.jobs.remove(0).filename),
+        }
+    }
+
+    pub fn queued_jobs(&self, now: f64) -> Value {
+        Value::Array(
+            self.jobs
+                .iter()
+                .map(|job| {
+                    json!({
+                        "filename": job.filename,
+                        "job_id": job.job_id,
+                        "time_added": job.time_added,
+                        "time_in_queue": now - job.time_added,
+                    })
+                })
+                .collect(),
+        )
+    }
+
+    pub fn state(&self) -> &'static str {
+        match self.paused {
+            true => "paused",
+            false => "ready",
+        }
+    }
+
+    pub fn status(&self, now: f64) -> Value {
+        json!({
+            "queued_jobs": self.queued_jobs(now),
+            "queue_state": self.state(),
+        })
+    }
+}
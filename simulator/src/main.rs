@@ -0,0 +1,63 @@
+use std::process::exit;
+
+use clap::Parser;
+
+use crate::simulator::Simulator;
+
+mod connection;
+mod files;
+mod gcode;
+mod heater;
+mod history;
+mod job_queue;
+mod power;
+mod print_job;
+mod printer;
+mod rpc;
+mod server;
+mod simulator;
+mod subscription;
+mod toolhead;
+
+/// A fake Moonraker with a simulated Klipper printer behind it, for working
+/// on the UI without a printer.
+#[derive(Parser)]
+#[command(version, about)]
+struct Args {
+    /// Address to listen on, point [moonraker] in the atomscreen config here
+    #[arg(short, long, default_value = "127.0.0.1:7125")]
+    listen: String,
+
+    /// How much faster than real time heaters and prints run, 0 holds the
+    /// printer still
+    #[arg(short, long, default_value_t = 1.0)]
+    speed: f32,
+
+    /// Report this unix time as the current time, for file dates, history
+    /// and the job queue, so screenshots are the same on every run
+    #[arg(long, value_name = "UNIX_TIME")]
+    frozen_clock: Option<f64>,
+}
+
+#[tokio::main]
+async fn main() {
+    let args = Args::parse();
+
+    if let Some(time) = args.frozen_clock {
+        printer::freeze_clock(time);
+    }
+
+    let simulator = Simulator::new(args.speed);
+
+    {
+        let simulator = simulator.clone();
+        tokio::spawn(async move {
+            simulator.run().await;
+        });
+    }
+
+    if let Err(e) = server::serve(simulator, &args.listen).await {
+        eprintln!("Failed to listen on {}: {}", args.listen, e);
+        exit(1);
+    }
+}
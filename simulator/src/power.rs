@@ -0,0 +1,37 @@
+use serde_json::{json, Value};
+
+pub struct PowerDevice {
+    pub name: &'static str,
+    device_type: &'static str,
+    pub on: bool,
+    pub locked_while_printing: bool,
+}
+
+impl PowerDevice {
+    pub fn status(&self) -> &'static str {
+        match self.on {
+            true => "on",
+            false => "off",
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        json!({
+            "device": self.name,
+            "status": self.status(),
+            "locked_while_printing": self.locked_while_printing,
+            "type": self.device_type,
+        })
+    }
+}
+
+/// Switching off "printer" takes Klipper down with it.
+pub const PRINTER_POWER_DEVICE: &str = "printer";
+
+pub fn simulated_power_devices() -> Vec<PowerDevice> {
+    vec![
+        PowerDevice { name: PRINTER_POWER_DEVICE, device_type: "gpio", on: true, locked_while_printing: true },
+        PowerDevice { name: "lights", device_type: "tplink_smartplug", on: true, locked_while_printing: false },
+        PowerDevice { name: "enclosure_fan", device_type: "klipper_device", on: false, locked_while_printing: false },
+    ]
+}
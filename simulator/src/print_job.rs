@@ -0,0 +1,276 @@
+use serde_json::{json, Value};
+
+use crate::{files::SimulatedFile, heater::round};
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum PrintState {
+    Printing,
+    Paused,
+    Complete,
+    Cancelled,
+    Error,
+}
+
+impl PrintState {
+    pub fn name(&self) -> &'static str {
+        match self {
+            PrintState::Printing => "printing",
+            PrintState::Paused => "paused",
+            PrintState::Complete => "complete",
+            PrintState::Cancelled => "cancelled",
+            PrintState::Error => "error",
+        }
+    }
+}
+
+const PRINT_CENTER: [f32; 2] = [117.5, 117.5];
+/// Radians per second the nozzle circles the part with.
+const PATH_SPEED: f32 = 1.5;
+
+/// A print running through a [SimulatedFile] at the slicer's estimated pace.
+pub struct PrintJob {
+    pub filename: String,
+    pub job_id: String,
+    pub state: PrintState,
+    pub message: String,
+    /// Of the estimated time.
+    pub progress: f32,
+    pub print_duration: f32,
+    pub total_duration: f32,
+    pub filament_used: f32,
+    /// Still waiting for the first layer temperatures.
+    pub heating: bool,
+    /// The part cooling fan comes on from the second layer, like slicers do.
+    pub fan_started: bool,
+    pub start_time: f64,
+    pub excluded_objects: Vec<String>,
+    file_size: i32,
+    estimated_time: f32,
+    total_layers: i32,
+    layer_height: f32,
+    first_layer_height: f32,
+    filament_total: f32,
+    angle: f32,
+}
+
+impl PrintJob {
+    pub fn new(file: &SimulatedFile, job_id: String, start_time: f64) -> Self {
+        Self {
+            filename: file.path.clone(),
+            job_id,
+            state: PrintState::Printing,
+            message: String::new(),
+            progress: 0.0,
+            print_duration: 0.0,
+            total_duration: 0.0,
+            filament_used: 0.0,
+            heating: true,
+            fan_started: false,
+            start_time,
+            excluded_objects: vec![],
+            file_size: file.size,
+            estimated_time: file.estimated_time,
+            total_layers: file.total_layers(),
+            layer_height: file.layer_height,
+            first_layer_height: file.first_layer_height,
+            filament_total: file.filament_total,
+            angle: 0.0,
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        matches!(self.state, PrintState::Printing | PrintState::Paused)
+    }
+
+    pub fn current_layer(&self) -> i32 {
+        match self.heating {
+            true => 0,
+            false => ((self.progress * self.total_layers as f32) as i32 + 1).min(self.total_layers),
+        }
+    }
+
+    pub fn layer_z(&self) -> f32 {
+        self.first_layer_height + (self.current_layer() - 1).max(0) as f32 * self.layer_height
+    }
+
+    /// Advances the print by `dt` seconds of printing at `speed_factor`.
+    /// Returns the next nozzle position and whether the print has finished.
+    pub fn advance(&mut self, dt: f32, speed_factor: f32, extrude_factor: f32, z_offset: f32) -> ([f32; 4], bool) {
+        self.print_duration += dt;
+        self.progress = (self.progress + dt * speed_factor / self.estimated_time).min(1.0);
+        self.filament_used = self.progress * self.filament_total * extrude_factor;
+        self.angle = (self.angle + dt * speed_factor * PATH_SPEED) % std::f32::consts::TAU;
+
+        // Wobble the radius per layer so the path doesn't look static
+        let radius = 30.0 + 5.0 * (self.current_layer() as f32 * 0.7).sin();
+        let position = [
+            PRINT_CENTER[0] + radius * self.angle.cos(),
+            PRINT_CENTER[1] + radius * self.angle.sin(),
+            self.layer_z() + z_offset,
+            self.filament_used,
+        ];
+
+        (position, self.progress >= 1.0)
+    }
+
+    pub fn file_position(&self) -> i32 {
+        (self.progress * self.file_size as f32) as i32
+    }
+
+    pub fn object_name(&self) -> String {
+        let file_name = self.filename.rsplit('/').next().unwrap_or(&self.filename);
+        file_name.strip_suffix(".gcode").unwrap_or(file_name).to_uppercase()
+    }
+
+    pub fn print_stats_status(&self) -> Value {
+        json!({
+            "filename": self.filename,
+            "total_duration": round(self.total_duration),
+            "print_duration": round(self.print_duration),
+            "filament_used": round(self.filament_used),
+            "state": self.state.name(),
+            "message": self.message,
+            "info": {
+                "total_layer": self.total_layers,
+                "current_layer": self.current_layer(),
+            },
+        })
+    }
+
+    pub fn virtual_sdcard_status(&self, gcodes_root: &str) -> Value {
+        json!({
+            "file_path": format!("{}/{}", gcodes_root, self.filename),
+            "progress": round(self.file_position() as f32 / self.file_size as f32),
+            "is_active": self.state == PrintState::Printing,
+            "file_position": self.file_position(),
+            "file_size": self.file_size,
+        })
+    }
+
+    pub fn exclude_object_status(&self) -> Value {
+        let name = self.object_name();
+        let [x, y] = PRINT_CENTER;
+
+        json!({
+            "objects": [{
+                "name": name,
+                "polygon": [[x - 35.0, y - 35.0], [x + 35.0, y - 35.0], [x + 35.0, y + 35.0], [x - 35.0, y + 35.0]],
+                "center": PRINT_CENTER,
+            }],
+            "excluded_objects": self.excluded_objects,
+            "current_object": match self.is_active() && !self.excluded_objects.contains(&name) {
+                true => Value::String(name),
+                false => Value::Null,
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{files::simulated_files, printer::Printer};
+
+    fn print_job(path: &str) -> PrintJob {
+        let file = simulated_files().into_iter().find(|file| file.path == path).unwrap();
+        let mut job = PrintJob::new(&file, String::from("000001"), 0.0);
+        job.heating = false;
+        job
+    }
+
+    #[test]
+    fn advances_at_the_estimated_pace() {
+        // Estimated at 1140 seconds
+        let mut job = print_job("calibration/cube_20mm.gcode");
+
+        let (_, finished) = job.advance(285.0, 1.0, 1.0, 0.0);
+
+        assert!(!finished);
+        assert_eq!(job.progress, 0.25);
+        assert_eq!(job.print_duration, 285.0);
+        assert_eq!(job.filament_used, 0.25 * 1140.0 * 0.9);
+        assert_eq!(job.file_position(), (0.25 * job.file_size as f32) as i32);
+    }
+
+    #[test]
+    fn speed_factor_speeds_up_progress_not_print_duration() {
+        let mut job = print_job("calibration/cube_20mm.gcode");
+
+        job.advance(285.0, 2.0, 1.0, 0.0);
+
+        assert_eq!(job.progress, 0.5);
+        assert_eq!(job.print_duration, 285.0);
+    }
+
+    #[test]
+    fn layers_follow_progress() {
+        let mut job = print_job("calibration/cube_20mm.gcode");
+        assert_eq!(job.current_layer(), 1);
+
+        job.heating = true;
+        assert_eq!(job.current_layer(), 0);
+        job.heating = false;
+
+        job.advance(570.0, 1.0, 1.0, 0.0);
+        assert_eq!(job.current_layer(), 51);
+
+        let (position, finished) = job.advance(10000.0, 1.0, 1.0, 0.0);
+        assert!(finished);
+        assert_eq!(job.progress, 1.0);
+        assert_eq!(job.current_layer(), 100);
+        assert!((position[2] - 20.0).abs() < 0.001, "{}", position[2]);
+    }
+
+    #[test]
+    fn status_objects_report_progress() {
+        let mut job = print_job("calibration/cube_20mm.gcode");
+        job.advance(570.0, 1.0, 1.0, 0.0);
+
+        let print_stats = job.print_stats_status();
+        assert_eq!(print_stats["state"], "printing");
+        assert_eq!(print_stats["print_duration"], 570.0);
+        assert_eq!(print_stats["info"]["current_layer"], 51);
+        assert_eq!(print_stats["info"]["total_layer"], 100);
+
+        let virtual_sdcard = job.virtual_sdcard_status("/gcodes");
+        assert_eq!(virtual_sdcard["file_path"], "/gcodes/calibration/cube_20mm.gcode");
+        assert_eq!(virtual_sdcard["progress"], 0.5);
+        assert_eq!(virtual_sdcard["is_active"], true);
+        assert_eq!(virtual_sdcard["file_position"], job.file_position());
+    }
+
+    #[test]
+    fn printer_heats_up_then_prints_to_completion() {
+        let mut printer = Printer::new();
+        printer.start_print("calibration/first_layer.gcode").unwrap();
+
+        let mut last_file_position = 0;
+        let mut heated_after = None;
+
+        for tick in 0..10000 {
+            printer.tick(0.25);
+            let status = printer.status();
+            let file_position = status["virtual_sdcard"]["file_position"].as_i64().unwrap();
+
+            // Nothing moves until the first layer temperatures are reached
+            if heated_after.is_none() && file_position > 0 {
+                assert!(printer.extruder.temperature > 204.0 && printer.heater_bed.temperature > 59.0);
+                heated_after = Some(tick);
+            }
+
+            assert!(file_position >= last_file_position);
+            last_file_position = file_position;
+
+            if status["print_stats"]["state"] == "complete" {
+                assert_eq!(status["virtual_sdcard"]["progress"], 1.0);
+                assert!(status["print_stats"]["print_duration"].as_f64().unwrap() >= 300.0);
+                assert_eq!(printer.extruder.target, 0.0);
+                return;
+            }
+
+            assert_eq!(status["print_stats"]["state"], "printing");
+        }
+
+        panic!("print never completed, heated after {:?} ticks", heated_after);
+    }
+}
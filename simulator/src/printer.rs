@@ -0,0 +1,592 @@
+use std::{
+    sync::OnceLock,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde_json::{json, Map, Value};
+
+use crate::{
+    files::{simulated_files, SimulatedFile},
+    heater::{round, Heater},
+    history::{History, HistoryJob},
+    job_queue::JobQueue,
+    power::{simulated_power_devices, PowerDevice, PRINTER_POWER_DEVICE},
+    print_job::{PrintJob, PrintState},
+    toolhead::{Toolhead, AXIS_MAXIMUM},
+};
+
+const GCODES_ROOT: &str = "/home/pi/printer_data/gcodes";
+const READY_MESSAGE: &str = "Printer is ready";
+const RESTART_HINT: &str = "Once the underlying issue is corrected, use the\n\"FIRMWARE_RESTART\" command to reset the firmware, reload the\nconfig, and restart the host software.\nPrinter is shutdown";
+/// Seconds Klipper takes to come back after a restart.
+const STARTUP_TIME: f64 = 2.0;
+/// Seconds between a queued job finishing and the next one starting.
+const JOB_TRANSITION_DELAY: f64 = 5.0;
+/// Seconds without activity before idle_timeout turns everything off.
+const IDLE_TIMEOUT: f64 = 600.0;
+pub const MIN_EXTRUDE_TEMP: f32 = 170.0;
+pub const MACROS: [&str; 3] = ["LOAD_FILAMENT", "UNLOAD_FILAMENT", "CLEAN_NOZZLE"];
+
+/// Something a G-code command has to wait for before the next one runs.
+pub enum Wait {
+    Heater(&'static str),
+    /// An eventtime.
+    Until(f64),
+}
+
+/// Simulated Klipper state plus the parts of Moonraker that keep state of their own.
+pub struct Printer {
+    /// Seconds since start, scaled by the simulation speed.
+    pub eventtime: f64,
+    klippy_state: &'static str,
+    state_message: String,
+    ready_at: Option<f64>,
+    pub extruder: Heater,
+    pub heater_bed: Heater,
+    pub chamber: Heater,
+    host_temperature: (f32, f32, f32),
+    pub toolhead: Toolhead,
+    pub fan_speed: f32,
+    /// Of G0/G1 moves, in mm/s.
+    pub speed: f32,
+    pub speed_factor: f32,
+    pub extrude_factor: f32,
+    pub z_offset: f32,
+    pub absolute_coordinates: bool,
+    pub absolute_extrude: bool,
+    pub display_message: Option<String>,
+    pub filament_detected: bool,
+    pub filament_sensor_enabled: bool,
+    pub last_activity: f64,
+    idle: bool,
+    pub print: Option<PrintJob>,
+    next_queued_job_at: Option<f64>,
+    pub files: Vec<SimulatedFile>,
+    pub history: History,
+    pub job_queue: JobQueue,
+    pub power_devices: Vec<PowerDevice>,
+    notifications: Vec<Value>,
+}
+
+impl Printer {
+    pub fn new() -> Self {
+        Self {
+            eventtime: 0.0,
+            klippy_state: "ready",
+            state_message: READY_MESSAGE.to_string(),
+            ready_at: None,
+            extruder: Heater::new(300.0, 330.0, 40.0),
+            heater_bed: Heater::new(120.0, 110.0, 90.0),
+            chamber: Heater::new(70.0, 50.0, 300.0),
+            host_temperature: (45.0, 45.0, 45.0),
+            toolhead: Toolhead::new(),
+            fan_speed: 0.0,
+            speed: 25.0,
+            speed_factor: 1.0,
+            extrude_factor: 1.0,
+            z_offset: 0.0,
+            absolute_coordinates: true,
+            absolute_extrude: true,
+            display_message: None,
+            filament_detected: true,
+            filament_sensor_enabled: true,
+            last_activity: 0.0,
+            idle: false,
+            print: None,
+            next_queued_job_at: None,
+            files: simulated_files(),
+            history: History::default(),
+            job_queue: JobQueue::new(),
+            power_devices: simulated_power_devices(),
+            notifications: vec![],
+        }
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.klippy_state == "ready"
+    }
+
+    pub fn require_ready(&self) -> Result<(), String> {
+        match self.is_ready() {
+            true => Ok(()),
+            false => Err(self.state_message.lines().next().unwrap_or_default().to_string()),
+        }
+    }
+
+    pub fn is_printing(&self) -> bool {
+        self.print.as_ref().is_some_and(PrintJob::is_active)
+    }
+
+    pub fn heater_mut(&mut self, name: &str) -> Option<(&'static str, &mut Heater)> {
+        match name {
+            "extruder" => Some(("extruder", &mut self.extruder)),
+            "heater_bed" => Some(("heater_bed", &mut self.heater_bed)),
+            "chamber" | "heater_generic chamber" => Some(("chamber", &mut self.chamber)),
+            _ => None,
+        }
+    }
+
+    pub fn turn_off_heaters(&mut self) {
+        self.extruder.target = 0.0;
+        self.heater_bed.target = 0.0;
+        self.chamber.target = 0.0;
+    }
+
+    pub fn wait_finished(&self, wait: &Wait) -> Result<bool, String> {
+        self.require_ready()?;
+
+        Ok(match wait {
+            Wait::Heater("extruder") => self.extruder.reached_target(),
+            Wait::Heater("heater_bed") => self.heater_bed.reached_target(),
+            Wait::Heater(_) => self.chamber.reached_target(),
+            Wait::Until(eventtime) => self.eventtime >= *eventtime,
+        })
+    }
+
+    pub fn tick(&mut self, dt: f32) {
+        self.eventtime += dt as f64;
+
+        if let Some(ready_at) = self.ready_at
+            && self.eventtime >= ready_at
+        {
+            self.ready_at = None;
+            self.klippy_state = "ready";
+            self.state_message = READY_MESSAGE.to_string();
+            self.last_activity = self.eventtime;
+        }
+
+        self.extruder.tick(dt);
+        self.heater_bed.tick(dt);
+        self.chamber.tick(dt);
+
+        let load = if self.is_printing() { 6.0 } else { 0.0 };
+        let (temperature, min, max) = &mut self.host_temperature;
+        *temperature = 45.0 + load + 2.0 * (self.eventtime as f32 / 90.0).sin();
+        *min = min.min(*temperature);
+        *max = max.max(*temperature);
+
+        self.tick_print(dt);
+        self.toolhead.tick(dt);
+        self.tick_idle_timeout();
+
+        if let Some(next_queued_job_at) = self.next_queued_job_at
+            && self.eventtime >= next_queued_job_at
+        {
+            self.next_queued_job_at = None;
+            self.start_next_queued_job();
+        }
+    }
+
+    fn tick_print(&mut self, dt: f32) {
+        let Some(print) = &mut self.print else {
+            return;
+        };
+
+        match print.state {
+            PrintState::Printing => print.total_duration += dt,
+            PrintState::Paused => {
+                print.total_duration += dt;
+                return;
+            }
+            _ => return,
+        }
+
+        if print.heating {
+            print.heating = !(self.extruder.reached_target() && self.heater_bed.reached_target());
+            return;
+        }
+
+        let (position, finished) = print.advance(dt, self.speed_factor, self.extrude_factor, self.z_offset);
+        self.toolhead.follow(position, dt);
+
+        if print.current_layer() >= 2 && !print.fan_started {
+            print.fan_started = true;
+            self.fan_speed = 1.0;
+        }
+
+        if finished {
+            self.finish_print(PrintState::Complete, "completed");
+        }
+    }
+
+    fn tick_idle_timeout(&mut self) {
+        let idle = !self.is_printing() && self.eventtime - self.last_activity >= IDLE_TIMEOUT;
+
+        if idle && !self.idle {
+            self.turn_off_heaters();
+            self.toolhead.motors_off();
+        }
+        self.idle = idle;
+    }
+
+    pub fn start_print(&mut self, filename: &str) -> Result<(), String> {
+        self.require_ready()?;
+
+        if self.is_printing() {
+            return Err(String::from("SD busy"));
+        }
+
+        let file = self
+            .files
+            .iter()
+            .find(|file| file.path == filename)
+            .ok_or_else(|| format!("Unable to open file {}", filename))?;
+
+        let start_time = unix_time();
+        let job_id = self.history.next_job_id();
+        let print = PrintJob::new(file, job_id.clone(), start_time);
+        let (extruder_temperature, bed_temperature) = (file.first_layer_extr_temp, file.first_layer_bed_temp);
+
+        let job = self.history.add(HistoryJob {
+            job_id,
+            filename: file.path.clone(),
+            status: "in_progress",
+            start_time,
+            end_time: None,
+            print_duration: 0.0,
+            total_duration: 0.0,
+            filament_used: 0.0,
+            metadata: file.metadata(),
+        });
+        self.notify("notify_history_changed", json!({ "action": "added", "job": job }));
+
+        self.extruder.set_target(extruder_temperature)?;
+        self.heater_bed.set_target(bed_temperature)?;
+        self.toolhead.home("xyz");
+        self.fan_speed = 0.0;
+        self.display_message = None;
+        self.print = Some(print);
+        self.last_activity = self.eventtime;
+
+        println!("Started printing {}", filename);
+        Ok(())
+    }
+
+    pub fn pause_print(&mut self) -> Result<(), String> {
+        let print = self.print.as_mut().filter(|print| print.is_active()).ok_or_else(|| String::from("No print in progress"))?;
+
+        if print.state == PrintState::Paused {
+            return Err(String::from("Print already paused"));
+        }
+
+        print.state = PrintState::Paused;
+        self.park();
+        Ok(())
+    }
+
+    pub fn resume_print(&mut self) -> Result<(), String> {
+        let print = self.print.as_mut().filter(|print| print.is_active()).ok_or_else(|| String::from("No print in progress"))?;
+
+        if print.state != PrintState::Paused {
+            return Err(String::from("Print is not paused, resume aborted"));
+        }
+
+        print.state = PrintState::Printing;
+        Ok(())
+    }
+
+    pub fn cancel_print(&mut self) -> Result<(), String> {
+        if !self.is_printing() {
+            return Err(String::from("No print in progress"));
+        }
+
+        self.finish_print(PrintState::Cancelled, "cancelled");
+        Ok(())
+    }
+
+    /// Ends the active print, `status` is what history records.
+    fn finish_print(&mut self, state: PrintState, status: &'static str) {
+        let Some(print) = self.print.as_mut().filter(|print| print.is_active()) else {
+            return;
+        };
+
+        print.state = state;
+        let job = self.history.finish(&print.job_id, status, unix_time(), print.print_duration, print.total_duration, print.filament_used);
+        println!("Print of {} {}", print.filename, status);
+
+        if let Some(job) = job {
+            self.notify("notify_history_changed", json!({ "action": "finished", "job": job }));
+        }
+
+        self.turn_off_heaters();
+        self.fan_speed = 0.0;
+
+        if self.is_ready() {
+            self.park();
+        }
+
+        match state {
+            PrintState::Complete => self.next_queued_job_at = Some(self.eventtime + JOB_TRANSITION_DELAY),
+            _ => {
+                if !self.job_queue.paused && !self.job_queue.is_empty() {
+                    self.job_queue.paused = true;
+                    self.notify_job_queue_changed("state_changed");
+                }
+            }
+        }
+    }
+
+    fn park(&mut self) {
+        let [x, _, z, e] = self.toolhead.commanded_position();
+        let _ = self.toolhead.move_to([x, AXIS_MAXIMUM[1], (z + 10.0).min(AXIS_MAXIMUM[2]), e], 100.0);
+    }
+
+    pub fn start_next_queued_job(&mut self) {
+        if self.is_printing() || !self.is_ready() {
+            return;
+        }
+
+        let Some(filename) = self.job_queue.pop_next() else {
+            return;
+        };
+
+        self.notify_job_queue_changed("job_loaded");
+
+        if let Err(e) = self.start_print(&filename) {
+            println!("Failed to start queued job {}: {}", filename, e);
+        }
+    }
+
+    pub fn emergency_stop(&mut self) {
+        self.shutdown("Shutdown due to M112 command");
+    }
+
+    /// Klipper stops everything and waits for a FIRMWARE_RESTART.
+    fn shutdown(&mut self, reason: &str) {
+        if let Some(print) = self.print.as_mut().filter(|print| print.is_active()) {
+            print.message = reason.to_string();
+        }
+        self.finish_print(PrintState::Error, "klippy_shutdown");
+
+        self.klippy_state = "shutdown";
+        self.state_message = format!("{}\n{}", reason, RESTART_HINT);
+        self.ready_at = None;
+        self.turn_off_heaters();
+        self.fan_speed = 0.0;
+        self.toolhead.motors_off();
+
+        println!("Klipper shut down: {}", reason);
+    }
+
+    pub fn restart(&mut self) -> Result<(), String> {
+        if !self.power_devices.iter().any(|device| device.name == PRINTER_POWER_DEVICE && device.on) {
+            return Err(String::from("Printer power is off"));
+        }
+
+        self.finish_print(PrintState::Error, "klippy_disconnect");
+
+        self.klippy_state = "startup";
+        self.state_message = String::from("Printer is restarting");
+        self.ready_at = Some(self.eventtime + STARTUP_TIME);
+        self.turn_off_heaters();
+        self.fan_speed = 0.0;
+        self.toolhead.motors_off();
+        self.speed_factor = 1.0;
+        self.extrude_factor = 1.0;
+        self.z_offset = 0.0;
+        self.display_message = None;
+        self.print = None;
+
+        println!("Klipper restarting");
+        Ok(())
+    }
+
+    pub fn set_power_device(&mut self, name: &str, action: &str) -> Result<Value, String> {
+        let printing = self.is_printing();
+        let device = self
+            .power_devices
+            .iter_mut()
+            .find(|device| device.name == name)
+            .ok_or_else(|| format!("No valid device named {}", name))?;
+
+        let on = match action {
+            "on" => true,
+            "off" => false,
+            "toggle" => !device.on,
+            _ => return Err(format!("Invalid power action {}", action)),
+        };
+
+        if device.locked_while_printing && printing && on != device.on {
+            return Err(format!("Unable to change power for {} while printing", name));
+        }
+
+        let changed = device.on != on;
+        device.on = on;
+        let status = device.status();
+
+        if changed && name == PRINTER_POWER_DEVICE {
+            match on {
+                true => self.restart()?,
+                false => {
+                    self.shutdown("Lost communication with MCU 'mcu'");
+                    self.klippy_state = "error";
+                }
+            }
+        }
+
+        Ok(json!({ name: status }))
+    }
+
+    pub fn thumbnail(&self, path: &str) -> Option<Vec<u8>> {
+        self.files
+            .iter()
+            .flat_map(|file| &file.thumbnails)
+            .find(|thumbnail| thumbnail.path == path)
+            .map(|thumbnail| thumbnail.png.clone())
+    }
+
+    fn notify(&mut self, method: &str, params: Value) {
+        self.notifications.push(json!({
+            "jsonrpc": "2.0",
+            "method": method,
+            "params": [params],
+        }));
+    }
+
+    pub fn notify_job_queue_changed(&mut self, action: &str) {
+        let updated_queue = match action {
+            "state_changed" => Value::Null,
+            _ => self.job_queue.queued_jobs(unix_time()),
+        };
+        let queue_state = self.job_queue.state();
+
+        self.notify(
+            "notify_job_queue_changed",
+            json!({
+                "action": action,
+                "updated_queue": updated_queue,
+                "queue_state": queue_state,
+            }),
+        );
+    }
+
+    /// JSON-RPC notifications queued since the last call.
+    pub fn take_notifications(&mut self) -> Vec<Value> {
+        std::mem::take(&mut self.notifications)
+    }
+
+    pub fn object_names(&self) -> Vec<String> {
+        self.status().keys().cloned().collect()
+    }
+
+    /// Every printer object as `printer.objects.query` would return it.
+    pub fn status(&self) -> Map<String, Value> {
+        let mut status = Map::new();
+        let mut insert = |name: &str, value: Value| {
+            status.insert(name.to_string(), value);
+        };
+
+        let position = self.toolhead.commanded_position();
+        let homing_origin = [0.0, 0.0, self.z_offset, 0.0];
+        let gcode_position = [position[0], position[1], position[2] - self.z_offset, position[3]];
+
+        insert("webhooks", json!({ "state": self.klippy_state, "state_message": self.state_message }));
+        insert(
+            "configfile",
+            json!({
+                "settings": {
+                    "printer": { "kinematics": "cartesian", "max_velocity": 300.0, "max_accel": 3000.0 },
+                    "extruder": { "nozzle_diameter": 0.4, "min_temp": 0.0, "max_temp": self.extruder.max_temp, "min_extrude_temp": MIN_EXTRUDE_TEMP },
+                    "heater_bed": { "min_temp": 0.0, "max_temp": self.heater_bed.max_temp },
+                    "heater_generic chamber": { "min_temp": 0.0, "max_temp": self.chamber.max_temp },
+                    "stepper_x": { "position_min": 0.0, "position_max": AXIS_MAXIMUM[0] },
+                    "stepper_y": { "position_min": 0.0, "position_max": AXIS_MAXIMUM[1] },
+                    "stepper_z": { "position_min": 0.0, "position_max": AXIS_MAXIMUM[2] },
+                },
+                "save_config_pending": false,
+            }),
+        );
+        insert("toolhead", self.toolhead.status("extruder"));
+        insert(
+            "gcode_move",
+            json!({
+                "speed_factor": self.speed_factor,
+                "speed": self.speed,
+                "extruder_factor": self.extrude_factor,
+                "absolute_coordinates": self.absolute_coordinates,
+                "absolute_extrude": self.absolute_extrude,
+                "homing_origin": homing_origin,
+                "position": position.map(round),
+                "gcode_position": gcode_position.map(round),
+            }),
+        );
+        insert("motion_report", self.toolhead.motion_report_status());
+
+        let mut extruder = self.extruder.status();
+        extruder["can_extrude"] = json!(self.extruder.temperature >= MIN_EXTRUDE_TEMP);
+        extruder["pressure_advance"] = json!(0.04);
+        extruder["smooth_time"] = json!(0.04);
+        extruder["motion_queue"] = Value::Null;
+        insert("extruder", extruder);
+        insert("heater_bed", self.heater_bed.status());
+        insert("heater_generic chamber", self.chamber.status());
+
+        let (temperature, min, max) = self.host_temperature;
+        insert(
+            "temperature_sensor raspberry_pi",
+            json!({ "temperature": round(temperature), "measured_min_temp": round(min), "measured_max_temp": round(max) }),
+        );
+        insert("fan", json!({ "speed": self.fan_speed, "rpm": (self.fan_speed * 7000.0) as i32 }));
+        insert(
+            "filament_switch_sensor runout",
+            json!({ "filament_detected": self.filament_detected, "enabled": self.filament_sensor_enabled }),
+        );
+
+        let (idle_state, printing_time) = match &self.print {
+            Some(print) if print.state == PrintState::Printing => ("Printing", print.print_duration),
+            _ if self.idle => ("Idle", 0.0),
+            _ => ("Ready", 0.0),
+        };
+        insert("idle_timeout", json!({ "state": idle_state, "printing_time": round(printing_time) }));
+
+        match &self.print {
+            Some(print) => {
+                insert("virtual_sdcard", print.virtual_sdcard_status(GCODES_ROOT));
+                insert("print_stats", print.print_stats_status());
+                insert("exclude_object", print.exclude_object_status());
+            }
+            None => {
+                insert("virtual_sdcard", json!({ "file_path": null, "progress": 0.0, "is_active": false, "file_position": 0, "file_size": 0 }));
+                insert(
+                    "print_stats",
+                    json!({
+                        "filename": "",
+                        "total_duration": 0.0,
+                        "print_duration": 0.0,
+                        "filament_used": 0.0,
+                        "state": "standby",
+                        "message": "",
+                        "info": { "total_layer": null, "current_layer": null },
+                    }),
+                );
+                insert("exclude_object", json!({ "objects": [], "excluded_objects": [], "current_object": null }));
+            }
+        }
+
+        let progress = self.print.as_ref().map(|print| round(print.progress)).unwrap_or_default();
+        insert("display_status", json!({ "message": self.display_message, "progress": progress }));
+
+        for name in MACROS {
+            insert(&format!("gcode_macro {}", name), json!({}));
+        }
+
+        status
+    }
+}
+
+/// Set by `--frozen-clock`, so dates on screen are the same on every run.
+static FROZEN_TIME: OnceLock<f64> = OnceLock::new();
+
+/// Makes [unix_time] return `time` from now on. Call it before creating the
+/// printer, file modification times are taken when it starts.
+pub fn freeze_clock(time: f64) {
+    let _ = FROZEN_TIME.set(time);
+}
+
+/// For history, job queue and file timestamps, which are wall clock times.
+pub fn unix_time() -> f64 {
+    match FROZEN_TIME.get() {
+        Some(time) => *time,
+        None => SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs_f64(),
+    }
+}
@@ -0,0 +1,331 @@
+use std::sync::Mutex;
+
+use serde_json::{json, Map, Value};
+use thiserror::Error;
+
+use crate::{printer::unix_time, simulator::Simulator, subscription::{filter, Subscription}};
+
+#[derive(Error, Debug)]
+#[error("{message}")]
+pub struct RpcError {
+    pub code: i32,
+    pub message: String,
+}
+
+impl RpcError {
+    fn new(code: i32, message: impl Into<String>) -> Self {
+        Self { code, message: message.into() }
+    }
+}
+
+/// Klipper and Moonraker errors, which Moonraker returns as code 400.
+impl From<String> for RpcError {
+    fn from(message: String) -> Self {
+        Self::new(400, message)
+    }
+}
+
+fn string_param<'a>(params: &'a Value, name: &str) -> Result<&'a str, RpcError> {
+    params.get(name).and_then(Value::as_str).ok_or_else(|| RpcError::new(400, format!("No data for argument: {}", name)))
+}
+
+fn objects_param(params: &Value) -> Result<Map<String, Value>, RpcError> {
+    params.get("objects").and_then(Value::as_object).cloned().ok_or_else(|| RpcError::new(400, "No data for argument: objects"))
+}
+
+/// Answers a JSON-RPC request the way Moonraker would.
+pub async fn handle_request(simulator: &Simulator, subscription: &Mutex<Subscription>, method: &str, params: Value) -> Result<Value, RpcError> {
+    match method {
+        "server.info" => Ok(simulator.read(|printer| {
+            let klippy_state = printer.status()["webhooks"]["state"].clone();
+            json!({
+                "klippy_connected": true,
+                "klippy_state": klippy_state,
+                "components": ["file_manager", "history", "job_queue", "power"],
+                "failed_components": [],
+                "warnings": [],
+                "websocket_count": 1,
+                "moonraker_version": "simulator",
+                "api_version": [1, 5, 0],
+                "api_version_string": "1.5.0",
+            })
+        })),
+        "printer.info" => Ok(simulator.read(|printer| {
+            let status = printer.status();
+            let webhooks = &status["webhooks"];
+            json!({
+                "state": webhooks["state"],
+                "state_message": webhooks["state_message"],
+                "hostname": "simulator",
+                "software_version": "simulator",
+                "cpu_info": "Simulated",
+            })
+        })),
+        "printer.objects.list" => Ok(json!({ "objects": simulator.read(|printer| printer.object_names()) })),
+        "printer.objects.query" => {
+            let objects = objects_param(&params)?;
+            Ok(simulator.read(|printer| json!({ "eventtime": printer.eventtime, "status": filter(&objects, &printer.status()) })))
+        }
+        "printer.objects.subscribe" => {
+            let objects = objects_param(&params)?;
+            let (eventtime, status) = simulator.read(|printer| (printer.eventtime, printer.status()));
+            let status = subscription.lock().unwrap().subscribe(objects, &status);
+
+            Ok(json!({ "eventtime": eventtime, "status": status }))
+        }
+        "printer.gcode.script" => run_gcode_script(simulator, string_param(&params, "script")?).await,
+        "printer.emergency_stop" => {
+            simulator.update(|printer| printer.emergency_stop());
+            Ok(json!("ok"))
+        }
+        "printer.restart" | "printer.firmware_restart" => {
+            simulator.update(|printer| printer.restart())?;
+            Ok(json!("ok"))
+        }
+        "printer.print.start" => {
+            let filename = string_param(&params, "filename")?;
+            simulator.update(|printer| printer.start_print(filename))?;
+            Ok(json!("ok"))
+        }
+        "printer.print.pause" => {
+            simulator.update(|printer| printer.pause_print())?;
+            Ok(json!("ok"))
+        }
+        "printer.print.resume" => {
+            simulator.update(|printer| printer.resume_print())?;
+            Ok(json!("ok"))
+        }
+        "printer.print.cancel" => {
+            simulator.update(|printer| printer.cancel_print())?;
+            Ok(json!("ok"))
+        }
+        "server.files.list" => match params.get("root").and_then(Value::as_str).unwrap_or("gcodes") {
+            "gcodes" => Ok(simulator.read(|printer| printer.files.iter().map(|file| file.list_entry()).collect())),
+            "config" | "config_examples" | "docs" | "logs" => Ok(json!([])),
+            root => Err(RpcError::new(400, format!("Invalid root path ({})", root))),
+        },
+        "server.files.metadata" | "server.files.thumbnails" => {
+            let filename = string_param(&params, "filename")?;
+
+            simulator
+                .read(|printer| {
+                    let file = printer.files.iter().find(|file| file.path == filename)?;
+                    Some(match method {
+                        "server.files.metadata" => file.metadata(),
+                        _ => file.thumbnails(),
+                    })
+                })
+                .ok_or_else(|| RpcError::new(404, format!("Metadata not available for <{}>", filename)))
+        }
+        "server.history.list" => {
+            let start = params.get("start").and_then(Value::as_u64).unwrap_or(0) as usize;
+            let limit = params.get("limit").and_then(Value::as_u64).unwrap_or(50) as usize;
+            Ok(simulator.read(|printer| printer.history.list(start, limit)))
+        }
+        "server.history.get_job" => {
+            let uid = string_param(&params, "uid")?;
+            let job = simulator.read(|printer| printer.history.get(uid));
+            let job = job.ok_or_else(|| RpcError::new(404, format!("Invalid job uid: {}", uid)))?;
+
+            Ok(json!({ "job": job }))
+        }
+        "server.history.delete_job" => {
+            let uid = string_param(&params, "uid")?;
+            let deleted_jobs = simulator.update(|printer| printer.history.delete(uid));
+
+            match deleted_jobs.is_empty() {
+                true => Err(RpcError::new(404, format!("Invalid job uid: {}", uid))),
+                false => Ok(json!({ "deleted_jobs": deleted_jobs })),
+            }
+        }
+        "server.history.totals" => Ok(json!({ "job_totals": simulator.read(|printer| printer.history.totals()) })),
+        "server.history.reset_totals" => Ok(json!({ "last_totals": simulator.update(|printer| printer.history.reset_totals()) })),
+        "server.job_queue.status" => Ok(simulator.read(|printer| printer.job_queue.status(unix_time()))),
+        "server.job_queue.post_job" => {
+            let filenames: Vec<String> = params
+                .get("filenames")
+                .and_then(Value::as_array)
+                .map(|filenames| filenames.iter().filter_map(Value::as_str).map(String::from).collect())
+                .ok_or_else(|| RpcError::new(400, "No data for argument: filenames"))?;
+            let reset = params.get("reset").and_then(Value::as_bool).unwrap_or(false);
+
+            simulator.update(|printer| {
+                if let Some(missing) = filenames.iter().find(|filename| !printer.files.iter().any(|file| &file.path == *filename)) {
+                    return Err(RpcError::new(400, format!("Invalid filename: {}", missing)));
+                }
+
+                printer.job_queue.add(&filenames, reset, unix_time());
+                printer.notify_job_queue_changed("jobs_added");
+                printer.start_next_queued_job();
+                Ok(printer.job_queue.status(unix_time()))
+            })
+        }
+        "server.job_queue.delete_job" => {
+            let job_ids: Vec<String> = params
+                .get("job_ids")
+                .and_then(Value::as_array)
+                .map(|job_ids| job_ids.iter().filter_map(Value::as_str).map(String::from).collect())
+                .ok_or_else(|| RpcError::new(400, "No data for argument: job_ids"))?;
+
+            Ok(simulator.update(|printer| {
+                printer.job_queue.remove(&job_ids);
+                printer.notify_job_queue_changed("jobs_removed");
+                printer.job_queue.status(unix_time())
+            }))
+        }
+        "server.job_queue.pause" | "server.job_queue.start" => Ok(simulator.update(|printer| {
+            printer.job_queue.paused = method == "server.job_queue.pause";
+            printer.notify_job_queue_changed("state_changed");
+            printer.start_next_queued_job();
+            printer.job_queue.status(unix_time())
+        })),
+        "server.job_queue.jump" => {
+            let job_id = string_param(&params, "job_id")?;
+
+            simulator.update(|printer| match printer.job_queue.jump(job_id) {
+                true => {
+                    printer.notify_job_queue_changed("jobs_added");
+                    Ok(printer.job_queue.status(unix_time()))
+                }
+                false => Err(RpcError::new(404, format!("Invalid job id: {}", job_id))),
+            })
+        }
+        "machine.device_power.devices" => {
+            let devices: Vec<Value> = simulator.read(|printer| printer.power_devices.iter().map(|device| device.to_json()).collect());
+            Ok(json!({ "devices": devices }))
+        }
+        "machine.device_power.post_device" => {
+            let device = string_param(&params, "device")?;
+            let action = string_param(&params, "action")?;
+            simulator.update(|printer| printer.set_power_device(device, action)).map_err(RpcError::from)
+        }
+        _ => Err(RpcError::new(-32601, "Method not found")),
+    }
+}
+
+/// Runs each line in turn, waiting for heaters and dwells like Klipper does
+/// before answering.
+async fn run_gcode_script(simulator: &Simulator, script: &str) -> Result<Value, RpcError> {
+    let mut status = simulator.subscribe_status();
+
+    for line in script.lines() {
+        let Some(wait) = simulator.update(|printer| printer.run_gcode_line(line))? else {
+            continue;
+        };
+
+        // Every tick publishes a new status
+        while !simulator.read(|printer| printer.wait_finished(&wait))? {
+            if status.changed().await.is_err() {
+                return Err(RpcError::new(503, "Simulator stopped"));
+            }
+        }
+    }
+
+    Ok(json!("ok"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn request(simulator: &Simulator, method: &str, params: Value) -> Result<Value, RpcError> {
+        handle_request(simulator, &Mutex::new(Subscription::default()), method, params).await
+    }
+
+    #[tokio::test]
+    async fn unknown_methods_are_not_found() {
+        let simulator = Simulator::new(1.0);
+
+        let error = request(&simulator, "printer.nonexistent", json!({})).await.unwrap_err();
+        assert_eq!(error.code, -32601);
+    }
+
+    #[tokio::test]
+    async fn server_info_reports_klippy_ready() {
+        let simulator = Simulator::new(1.0);
+
+        let info = request(&simulator, "server.info", json!({})).await.unwrap();
+        assert_eq!(info["klippy_connected"], true);
+        assert_eq!(info["klippy_state"], "ready");
+    }
+
+    #[tokio::test]
+    async fn query_returns_only_the_requested_fields() {
+        let simulator = Simulator::new(1.0);
+
+        let result = request(&simulator, "printer.objects.query", json!({ "objects": { "extruder": ["target"], "heater_bed": null, "unknown": null } }))
+            .await
+            .unwrap();
+        let status = result["status"].as_object().unwrap();
+
+        assert_eq!(status.keys().collect::<Vec<_>>(), vec!["extruder", "heater_bed"]);
+        assert_eq!(status["extruder"], json!({ "target": 0.0 }));
+        assert!(status["heater_bed"]["temperature"].is_number());
+    }
+
+    #[tokio::test]
+    async fn subscribe_returns_the_initial_status() {
+        let simulator = Simulator::new(1.0);
+        let subscription = Mutex::new(Subscription::default());
+
+        let result = handle_request(&simulator, &subscription, "printer.objects.subscribe", json!({ "objects": { "print_stats": ["state"] } }))
+            .await
+            .unwrap();
+        assert_eq!(result["status"], json!({ "print_stats": { "state": "standby" } }));
+
+        simulator.update(|printer| printer.start_print("benchy.gcode")).unwrap();
+        let changes = subscription.lock().unwrap().changes(&simulator.read(|printer| printer.status()));
+        assert_eq!(Value::Object(changes), json!({ "print_stats": { "state": "printing" } }));
+    }
+
+    #[tokio::test]
+    async fn gcode_scripts_change_the_printer() {
+        let simulator = Simulator::new(1.0);
+
+        let result = request(&simulator, "printer.gcode.script", json!({ "script": "SET_HEATER_TEMPERATURE HEATER=extruder TARGET=200\nM106 S255" })).await;
+        assert_eq!(result.unwrap(), "ok");
+        assert_eq!(simulator.read(|printer| (printer.extruder.target, printer.fan_speed)), (200.0, 1.0));
+
+        let error = request(&simulator, "printer.gcode.script", json!({ "script": "SET_HEATER_TEMPERATURE HEATER=extruder TARGET=900" })).await.unwrap_err();
+        assert_eq!(error.code, 400);
+    }
+
+    #[tokio::test]
+    async fn missing_arguments_are_rejected() {
+        let simulator = Simulator::new(1.0);
+
+        let error = request(&simulator, "printer.gcode.script", json!({})).await.unwrap_err();
+        assert_eq!(error.code, 400);
+        assert_eq!(error.message, "No data for argument: script");
+
+        let error = request(&simulator, "printer.objects.query", json!({ "objects": ["extruder"] })).await.unwrap_err();
+        assert_eq!(error.code, 400);
+    }
+
+    #[tokio::test]
+    async fn print_start_needs_a_known_file() {
+        let simulator = Simulator::new(1.0);
+
+        let error = request(&simulator, "printer.print.start", json!({ "filename": "missing.gcode" })).await.unwrap_err();
+        assert_eq!(error.code, 400);
+        assert!(!simulator.read(|printer| printer.is_printing()));
+
+        let result = request(&simulator, "printer.print.start", json!({ "filename": "benchy.gcode" })).await;
+        assert_eq!(result.unwrap(), "ok");
+        assert!(simulator.read(|printer| printer.is_printing()));
+
+        let error = request(&simulator, "printer.print.start", json!({ "filename": "benchy.gcode" })).await.unwrap_err();
+        assert_eq!(error.message, "SD busy");
+    }
+
+    #[tokio::test]
+    async fn metadata_for_unknown_files_is_not_found() {
+        let simulator = Simulator::new(1.0);
+
+        let metadata = request(&simulator, "server.files.metadata", json!({ "filename": "benchy.gcode" })).await.unwrap();
+        assert_eq!(metadata["filename"], "benchy.gcode");
+
+        let error = request(&simulator, "server.files.metadata", json!({ "filename": "missing.gcode" })).await.unwrap_err();
+        assert_eq!(error.code, 404);
+    }
+}
@@ -0,0 +1,70 @@
+use std::sync::Arc;
+
+use fastwebsockets::{upgrade, WebSocketError};
+use http_body_util::Full;
+use hyper::{
+    body::{Bytes, Incoming},
+    header::CONTENT_TYPE,
+    server::conn::http1,
+    service::service_fn,
+    Request, Response, StatusCode,
+};
+use hyper_util::rt::TokioIo;
+use tokio::net::TcpListener;
+
+use crate::{connection::serve_websocket, simulator::Simulator};
+
+/// Accepts websocket clients on /websocket and serves thumbnails like
+/// Moonraker's file endpoint.
+pub async fn serve(simulator: Arc<Simulator>, listen: &str) -> std::io::Result<()> {
+    let listener = TcpListener::bind(listen).await?;
+    println!("Simulated Moonraker listening on {}", listen);
+
+    loop {
+        let (stream, peer) = listener.accept().await?;
+        let simulator = simulator.clone();
+
+        tokio::spawn(async move {
+            let service = service_fn(move |request| handle_http_request(simulator.clone(), request));
+            let connection = http1::Builder::new().serve_connection(TokioIo::new(stream), service).with_upgrades();
+
+            if let Err(e) = connection.await {
+                eprintln!("Connection from {} failed: {}", peer, e);
+            }
+        });
+    }
+}
+
+async fn handle_http_request(simulator: Arc<Simulator>, mut request: Request<Incoming>) -> Result<Response<Full<Bytes>>, WebSocketError> {
+    let path = request.uri().path().to_string();
+
+    if path == "/websocket" {
+        let (response, websocket) = upgrade::upgrade(&mut request)?;
+
+        tokio::spawn(async move {
+            println!("Websocket client connected");
+
+            let result = match websocket.await {
+                Ok(websocket) => serve_websocket(simulator, websocket).await,
+                Err(e) => Err(e.into()),
+            };
+
+            match result {
+                Ok(()) => println!("Websocket client disconnected"),
+                Err(e) => println!("Websocket client disconnected: {}", e),
+            }
+        });
+
+        let (parts, _) = response.into_parts();
+        return Ok(Response::from_parts(parts, Full::default()));
+    }
+
+    let thumbnail = path
+        .strip_prefix("/server/files/gcodes/")
+        .and_then(|path| simulator.read(|printer| printer.thumbnail(path)));
+
+    Ok(match thumbnail {
+        Some(png) => Response::builder().header(CONTENT_TYPE, "image/png").body(Full::new(Bytes::from(png))).unwrap(),
+        None => Response::builder().status(StatusCode::NOT_FOUND).body(Full::new(Bytes::from_static(b"Not Found"))).unwrap(),
+    })
+}
@@ -0,0 +1,83 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use serde_json::{Map, Value};
+use tokio::sync::{broadcast, watch};
+
+use crate::printer::Printer;
+
+/// How often Klipper sends status updates, and so how often the simulation steps.
+const TICK_INTERVAL: Duration = Duration::from_millis(250);
+
+pub struct StatusSnapshot {
+    pub eventtime: f64,
+    pub objects: Map<String, Value>,
+}
+
+/// The printer shared between websocket connections. Every change publishes
+/// a new status snapshot that connections diff against what they last sent.
+pub struct Simulator {
+    printer: Mutex<Printer>,
+    status: watch::Sender<Arc<StatusSnapshot>>,
+    notifications: broadcast::Sender<Arc<Value>>,
+    /// How much faster than real time the simulation runs.
+    speed: f32,
+}
+
+impl Simulator {
+    pub fn new(speed: f32) -> Arc<Self> {
+        let printer = Printer::new();
+        let (status, _) = watch::channel(Arc::new(StatusSnapshot { eventtime: printer.eventtime, objects: printer.status() }));
+        let (notifications, _) = broadcast::channel(64);
+
+        Arc::new(Self {
+            printer: Mutex::new(printer),
+            status,
+            notifications,
+            speed,
+        })
+    }
+
+    pub async fn run(&self) {
+        let mut interval = tokio::time::interval(TICK_INTERVAL);
+        let mut last_tick = Instant::now();
+
+        loop {
+            interval.tick().await;
+
+            let now = Instant::now();
+            let dt = now.duration_since(last_tick).as_secs_f32() * self.speed;
+            last_tick = now;
+
+            self.update(|printer| printer.tick(dt));
+        }
+    }
+
+    /// Changes the printer and publishes the result.
+    pub fn update<T>(&self, f: impl FnOnce(&mut Printer) -> T) -> T {
+        let mut printer = self.printer.lock().unwrap();
+        let result = f(&mut printer);
+
+        self.status.send_replace(Arc::new(StatusSnapshot { eventtime: printer.eventtime, objects: printer.status() }));
+        for notification in printer.take_notifications() {
+            // Nobody listening is fine
+            let _ = self.notifications.send(Arc::new(notification));
+        }
+
+        result
+    }
+
+    pub fn read<T>(&self, f: impl FnOnce(&Printer) -> T) -> T {
+        f(&self.printer.lock().unwrap())
+    }
+
+    pub fn subscribe_status(&self) -> watch::Receiver<Arc<StatusSnapshot>> {
+        self.status.subscribe()
+    }
+
+    pub fn subscribe_notifications(&self) -> broadcast::Receiver<Arc<Value>> {
+        self.notifications.subscribe()
+    }
+}
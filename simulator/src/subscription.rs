@@ -0,0 +1,68 @@
+use serde_json::{Map, Value};
+
+/// The objects a connection subscribed to and what it was last sent, so
+/// `notify_status_update` only carries the fields that changed.
+#[derive(Default)]
+pub struct Subscription {
+    /// Object name to a list of fields, or null for all of them.
+    objects: Map<String, Value>,
+    sent: Map<String, Value>,
+}
+
+impl Subscription {
+    /// Replaces the subscription, returning the full status of the new objects.
+    pub fn subscribe(&mut self, objects: Map<String, Value>, status: &Map<String, Value>) -> Map<String, Value> {
+        self.objects = objects;
+        self.sent = filter(&self.objects, status);
+        self.sent.clone()
+    }
+
+    pub fn changes(&mut self, status: &Map<String, Value>) -> Map<String, Value> {
+        let current = filter(&self.objects, status);
+        let mut changes = Map::new();
+
+        for (name, object) in &current {
+            let Value::Object(object) = object else {
+                continue;
+            };
+            let previous = self.sent.get(name);
+
+            let changed: Map<String, Value> = object
+                .iter()
+                .filter(|(field, value)| previous.and_then(|previous| previous.get(field.as_str())) != Some(*value))
+                .map(|(field, value)| (field.clone(), value.clone()))
+                .collect();
+
+            if !changed.is_empty() {
+                changes.insert(name.clone(), Value::Object(changed));
+            }
+        }
+
+        self.sent = current;
+        changes
+    }
+}
+
+/// The requested objects and fields of `status`, unknown objects are left out.
+pub fn filter(objects: &Map<String, Value>, status: &Map<String, Value>) -> Map<String, Value> {
+    let mut result = Map::new();
+
+    for (name, fields) in objects {
+        let Some(Value::Object(object)) = status.get(name) else {
+            continue;
+        };
+
+        let object = match fields {
+            Value::Array(fields) => object
+                .iter()
+                .filter(|(field, _)| fields.iter().any(|requested| requested.as_str() == Some(field.as_str())))
+                .map(|(field, value)| (field.clone(), value.clone()))
+                .collect(),
+            _ => object.clone(),
+        };
+
+        result.insert(name.clone(), Value::Object(object));
+    }
+
+    result
+}
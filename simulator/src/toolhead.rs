@@ -0,0 +1,158 @@
+use serde_json::{json, Value};
+
+use crate::heater::round;
+
+pub const AXIS_MAXIMUM: [f32; 3] = [235.0, 235.0, 250.0];
+const HOMING_SPEED: f32 = 50.0;
+
+pub struct Toolhead {
+    pub position: [f32; 4],
+    target: [f32; 4],
+    /// Of the current move, in mm/s.
+    speed: f32,
+    pub homed_axes: String,
+    pub live_velocity: f32,
+    pub live_extruder_velocity: f32,
+}
+
+impl Toolhead {
+    pub fn new() -> Self {
+        Self {
+            position: [0.0; 4],
+            target: [0.0; 4],
+            speed: 0.0,
+            homed_axes: String::new(),
+            live_velocity: 0.0,
+            live_extruder_velocity: 0.0,
+        }
+    }
+
+    pub fn home(&mut self, axes: &str) {
+        for (index, axis) in ['x', 'y', 'z'].into_iter().enumerate() {
+            if axes.contains(axis) {
+                self.target[index] = 0.0;
+                if !self.homed_axes.contains(axis) {
+                    self.homed_axes.push(axis);
+                }
+            }
+        }
+
+        // Keep Klipper's order, "xyz"
+        let mut homed: Vec<char> = self.homed_axes.chars().collect();
+        homed.sort();
+        self.homed_axes = homed.into_iter().collect();
+        self.speed = HOMING_SPEED;
+    }
+
+    pub fn motors_off(&mut self) {
+        self.homed_axes.clear();
+        self.target = self.position;
+    }
+
+    /// Queues a move, checked like Klipper does.
+    pub fn move_to(&mut self, target: [f32; 4], speed: f32) -> Result<(), String> {
+        for (index, axis) in ['x', 'y', 'z'].into_iter().enumerate() {
+            if target[index] == self.target[index] {
+                continue;
+            }
+
+            if !self.homed_axes.contains(axis) {
+                return Err(format!("Must home axis first: {}", format_position(&target)));
+            }
+
+            if !(0.0..=AXIS_MAXIMUM[index]).contains(&target[index]) {
+                return Err(format!("Move out of range: {}", format_position(&target)));
+            }
+        }
+
+        self.target = target;
+        self.speed = speed;
+        Ok(())
+    }
+
+    /// Where the last queued move ends, the base for relative moves.
+    pub fn commanded_position(&self) -> [f32; 4] {
+        self.target
+    }
+
+    pub fn set_position(&mut self, position: [f32; 4]) {
+        self.position = position;
+        self.target = position;
+    }
+
+    /// Jumps to `position`, reporting the speed it took to get there.
+    pub fn follow(&mut self, position: [f32; 4], dt: f32) {
+        let distance = distance(&self.position, &position);
+        self.live_velocity = if dt > 0.0 { distance / dt } else { 0.0 };
+        self.live_extruder_velocity = if dt > 0.0 { (position[3] - self.position[3]) / dt } else { 0.0 };
+        self.set_position(position);
+    }
+
+    pub fn tick(&mut self, dt: f32) {
+        let remaining = distance(&self.position, &self.target);
+        let extrude = self.target[3] - self.position[3];
+
+        if remaining == 0.0 && extrude == 0.0 {
+            self.live_velocity = 0.0;
+            self.live_extruder_velocity = 0.0;
+            return;
+        }
+
+        // Extrude only moves take as long as the extruder needs
+        let duration = match remaining > 0.0 {
+            true => remaining / self.speed.max(1.0),
+            false => extrude.abs() / self.speed.max(1.0),
+        };
+        let fraction = (dt / duration).min(1.0);
+
+        for index in 0..4 {
+            self.position[index] += (self.target[index] - self.position[index]) * fraction;
+        }
+
+        self.live_velocity = match remaining > 0.0 {
+            true => self.speed,
+            false => 0.0,
+        };
+        self.live_extruder_velocity = extrude / duration;
+    }
+
+    pub fn status(&self, extruder: &str) -> Value {
+        json!({
+            "homed_axes": self.homed_axes,
+            "axis_minimum": [0.0, 0.0, 0.0, 0.0],
+            "axis_maximum": [AXIS_MAXIMUM[0], AXIS_MAXIMUM[1], AXIS_MAXIMUM[2], 0.0],
+            "cone_start_z": null,
+            "print_time": 0.0,
+            "stalls": 0,
+            "estimated_print_time": 0.0,
+            "extruder": extruder,
+            "position": round_position(&self.target),
+            "max_velocity": 300.0,
+            "max_accel": 3000.0,
+            "minimum_cruise_ratio": 0.5,
+            "square_corner_velocity": 5.0,
+        })
+    }
+
+    pub fn motion_report_status(&self) -> Value {
+        json!({
+            "live_position": round_position(&self.position),
+            "live_velocity": round(self.live_velocity),
+            "live_extruder_velocity": round(self.live_extruder_velocity),
+            "steppers": ["extruder", "stepper_x", "stepper_y", "stepper_z"],
+            "trapq": ["extruder", "toolhead"],
+        })
+    }
+}
+
+pub fn round_position(position: &[f32; 4]) -> [f32; 4] {
+    position.map(round)
+}
+
+fn distance(from: &[f32; 4], to: &[f32; 4]) -> f32 {
+    (0..3).map(|index| (to[index] - from[index]).powi(2)).sum::<f32>().sqrt()
+}
+
+fn format_position(position: &[f32; 4]) -> String {
+    format!("{:.3} {:.3} {:.3} [{:.3}]", position[0], position[1], position[2], position[3])
+}
@@ -8,6 +8,9 @@ pub enum ApplicationError {
     SlintFailure(#[from] slint::PlatformError),
     #[error("Event loop error")]
     EventLoopError(#[from] slint::EventLoopError),
+    #[cfg(unix)]
+    #[error("Display driver error: {0}")]
+    DriverError(#[from] driver::DriverError),
     #[error("Unknown application error")]
     Unknown(String),
 }
@@ -5,4 +5,16 @@ use clap::Parser;
 pub struct Args {
     #[arg(required = true)]
     pub config: String,
+
+    /// Write every Moonraker websocket message to this file, for debugging
+    #[arg(long, value_name = "FILE")]
+    pub record: Option<String>,
+
+    /// Play back a file written with --record instead of connecting to Moonraker
+    #[arg(long, value_name = "FILE", conflicts_with = "record")]
+    pub replay: Option<String>,
+
+    /// How much faster than recorded to play back
+    #[arg(long, default_value_t = 1.0)]
+    pub replay_speed: f32,
 }
@@ -1,6 +1,6 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
-use crate::config::{MoonrakerConfig, OptionalGcodeCommands, OptionalUiConfig};
+use crate::config::{ControlConfig, MaterialPreset, MoonrakerConfig, OptionalGcodeCommands, OptionalUiConfig};
 use serde::Deserialize;
 
 use super::DisplayConfig;
@@ -10,7 +10,9 @@ pub struct Config {
     pub display: DisplayConfig,
     pub moonraker: Option<MoonrakerConfig>,
     pub heater_presets: Option<HashMap<String, Vec<u32>>>,
+    pub material_presets: Option<BTreeMap<String, MaterialPreset>>,
     pub gcode_commands: Option<OptionalGcodeCommands>,
     pub ui: Option<OptionalUiConfig>,
     pub quick_actions: Option<HashMap<String, Vec<String>>>,
+    pub control: Option<ControlConfig>,
 }
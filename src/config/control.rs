@@ -0,0 +1,7 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ControlConfig {
+    pub socket_path: String, // Unix socket accepting one JSON request per line
+    pub screenshot_dir: Option<String>, // screenshots are only written in here, defaults to "screenshots"
+}
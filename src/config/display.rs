@@ -1,7 +1,7 @@
 use super::DisplayDefaultConfig;
 #[cfg(unix)]
-use super::DisplayFramebufferConfig;
-use crate::{application_error, AppWindow};
+use super::{DisplayFramebufferConfig, DisplayHeadlessConfig};
+use crate::{application_error, hardware::{ScreensaverControl, ScreenshotControl, TouchCalibrationControl}, AppWindow};
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize)]
@@ -9,8 +9,11 @@ pub struct DisplayConfig {
     pub default: Option<DisplayDefaultConfig>,
     #[cfg(unix)]
     pub framebuffer: Option<DisplayFramebufferConfig>,
+    /// Renders into memory, for screenshots and golden-image tests.
+    #[cfg(unix)]
+    pub headless: Option<DisplayHeadlessConfig>,
 }
 
 pub trait DisplayInit {
-    fn init(&self) -> Result<AppWindow, application_error::ApplicationError>;
+    fn init(&self, screensaver: &ScreensaverControl, touch_calibration: &TouchCalibrationControl, screenshots: &ScreenshotControl) -> Result<AppWindow, application_error::ApplicationError>;
 }
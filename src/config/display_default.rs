@@ -1,5 +1,5 @@
 use super::DisplayInit;
-use crate::{application_error, AppWindow};
+use crate::{application_error, hardware::{ScreensaverControl, ScreenshotControl, TouchCalibrationControl}, AppWindow};
 use serde::{Deserialize, Serialize};
 use slint::ComponentHandle;
 
@@ -10,7 +10,7 @@ pub struct DisplayDefaultConfig {
 }
 
 impl DisplayInit for DisplayDefaultConfig {
-    fn init(&self) -> Result<AppWindow, application_error::ApplicationError> {
+    fn init(&self, _screensaver: &ScreensaverControl, _touch_calibration: &TouchCalibrationControl, _screenshots: &ScreenshotControl) -> Result<AppWindow, application_error::ApplicationError> {
         let app = AppWindow::new()?;
 
         app.window()
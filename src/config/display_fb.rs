@@ -1,19 +1,29 @@
 use crate::{
-    application_error::ApplicationError, config::DisplayInit,
-    AppWindow,
+    application_error::ApplicationError, config::{DisplayInit, GestureConfig, InputConfig, ScreensaverConfig, TouchConfig, VncConfig},
+    hardware::{ScreensaverControl, ScreenshotControl, TouchCalibrationControl},
+    AppWindow, Navigation,
 };
-use driver::init_framebuffer;
+use std::{cell::OnceCell, rc::Rc, time::Duration};
+use slint::{ComponentHandle, SharedString, Weak};
+use driver::{init_framebuffer, FramebufferOptions, GestureOptions, InputKind, InputOptions, PixelFormat, Rotation, SwipeEdge, TouchKind, TouchMappingOptions, TouchOptions};
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize)]
 pub struct DisplayFramebufferConfig {
     pub fb_path: String,
-    pub event_path: Option<String>,
+    pub event_path: Option<String>, // touchscreen, same as an inputs entry with device_type "touch"
+    pub inputs: Option<Vec<InputConfig>>,
     pub buffering: Option<String>,
+    pub screensaver: Option<ScreensaverConfig>,
+    pub rotation: Option<u32>, // degrees clockwise
+    pub pixel_format: Option<String>, // e.g. "rgb565" or "argb8888", detected if unset
+    pub touch: Option<TouchConfig>,
+    pub gestures: Option<GestureConfig>,
+    pub vnc: Option<VncConfig>, // mirrors the screen to VNC viewers
 }
 
 impl DisplayInit for DisplayFramebufferConfig {
-    fn init(&self) -> Result<crate::AppWindow, crate::application_error::ApplicationError> {
+    fn init(&self, screensaver: &ScreensaverControl, touch_calibration: &TouchCalibrationControl, _screenshots: &ScreenshotControl) -> Result<crate::AppWindow, crate::application_error::ApplicationError> {
         let double_buffering = match self.buffering.clone().unwrap_or(String::from("double")).to_lowercase().as_str()
         {
             "double" => true,
@@ -21,8 +31,94 @@ impl DisplayInit for DisplayFramebufferConfig {
             _ => panic!("Config value {:?} is unsupported for buffering. Supported is \"Single\" or \"Double\"", self.buffering)
         };
 
-        slint::platform::set_platform(init_framebuffer(self.fb_path.clone(), self.event_path.clone(), double_buffering));
+        let rotation = match Rotation::from_degrees(self.rotation.unwrap_or(0))
+        {
+            Some(rotation) => rotation,
+            None => return Err(ApplicationError::Unknown(format!("Config value {:?} is unsupported for rotation. Supported is 0, 90, 180 or 270", self.rotation)))
+        };
+
+        let pixel_format = match &self.pixel_format
+        {
+            Some(pixel_format) => Some(pixel_format.parse::<PixelFormat>()?),
+            None => None,
+        };
+
+        let mut inputs: Vec<InputOptions> = self.event_path.iter().map(|path| InputOptions::from_path(path.clone(), InputKind::Touch)).collect();
+        for input in self.inputs.iter().flatten() {
+            inputs.push(InputOptions {
+                path: input.path.clone(),
+                name: input.name.clone(),
+                vendor: input.vendor,
+                kind: match &input.device_type {
+                    Some(device_type) => device_type.parse::<InputKind>()?,
+                    None => InputKind::Auto,
+                },
+            });
+        }
+
+        // Without any input configured, use the first touchscreen that shows up
+        if self.event_path.is_none() && self.inputs.is_none() {
+            inputs.push(InputOptions::default());
+        }
+
+        let touch_config = self.touch.clone().unwrap_or_default();
+        let touch = TouchOptions {
+            kind: match &touch_config.device_type {
+                Some(device_type) => device_type.parse::<TouchKind>()?,
+                None => TouchKind::Auto,
+            },
+            pressure_threshold: touch_config.pressure_threshold,
+            jitter_distance: touch_config.jitter_distance.unwrap_or(3.0),
+            mapping: TouchMappingOptions {
+                swap_xy: touch_config.swap_xy,
+                invert_x: touch_config.invert_x,
+                invert_y: touch_config.invert_y,
+                calibration: touch_calibration.handle().unwrap_or_default(),
+            },
+        };
+
+        let gesture_config = self.gestures.clone().unwrap_or_default();
+        let default_gestures = GestureOptions::default();
+        let gestures = GestureOptions {
+            long_press: gesture_config.long_press.map(Duration::from_millis),
+            drag_threshold: gesture_config.drag_threshold.unwrap_or(default_gestures.drag_threshold),
+            drag_scrolling: gesture_config.drag_scrolling,
+            kinetic_time_constant: gesture_config.kinetic_time_constant.map(Duration::from_millis).unwrap_or(default_gestures.kinetic_time_constant),
+            edge_width: gesture_config.edge_width.unwrap_or(0.0),
+            swipe_distance: gesture_config.swipe_distance.unwrap_or(default_gestures.swipe_distance),
+        };
+
+        // The window only exists once the platform is set
+        let swipe_target: Rc<OnceCell<Weak<AppWindow>>> = Rc::new(OnceCell::new());
+        let on_swipe = {
+            let swipe_target = swipe_target.clone();
+            move |edge: SwipeEdge| {
+                if let Some(ui) = swipe_target.get().and_then(|ui_weak| ui_weak.upgrade()) {
+                    let edge = match edge {
+                        SwipeEdge::Left => "left",
+                        SwipeEdge::Right => "right",
+                        SwipeEdge::Top => "top",
+                        SwipeEdge::Bottom => "bottom",
+                    };
+                    ui.global::<Navigation>().invoke_swipe(SharedString::from(edge));
+                }
+            }
+        };
+
+        slint::platform::set_platform(init_framebuffer(FramebufferOptions {
+            fb_path: self.fb_path.clone(),
+            inputs,
+            double_buffering,
+            pixel_format,
+            rotation,
+            touch,
+            gestures,
+            on_swipe: Some(Box::new(on_swipe)),
+            screensaver: screensaver.handle(),
+            vnc: self.vnc.as_ref().map(VncConfig::to_options),
+        })?);
         let ui = AppWindow::new()?;
+        let _ = swipe_target.set(ui.as_weak());
 
         Ok(ui)
     }
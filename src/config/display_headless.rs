@@ -0,0 +1,58 @@
+use crate::{
+    application_error::ApplicationError, config::{DisplayInit, VncConfig},
+    hardware::{ScreensaverControl, ScreenshotControl, TouchCalibrationControl},
+    AppWindow,
+};
+use std::{fs, path::PathBuf, time::Duration};
+use driver::{parse_input_script, HeadlessOptions, HeadlessPlatform};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize)]
+pub struct DisplayHeadlessConfig {
+    pub width: u32,
+    pub height: u32,
+    pub screenshot_dir: Option<String>, // defaults to "screenshots"
+    pub screenshot_interval: Option<u64>, // milliseconds
+    pub script: Option<String>, // input script, see driver/src/headless/input_script.rs
+    pub golden_dir: Option<String>, // script screenshots are compared against the images in here
+    pub vnc: Option<VncConfig>, // serves the screen to VNC viewers, a display without hardware
+}
+
+impl DisplayInit for DisplayHeadlessConfig {
+    fn init(&self, _screensaver: &ScreensaverControl, _touch_calibration: &TouchCalibrationControl, screenshots: &ScreenshotControl) -> Result<AppWindow, ApplicationError> {
+        let script = match &self.script {
+            Some(path) => parse_input_script(&fs::read_to_string(path)?)?,
+            None => vec![],
+        };
+
+        let screenshot_dir = PathBuf::from(self.screenshot_dir.as_deref().unwrap_or("screenshots"));
+        let screenshots = screenshots.requests().unwrap_or_default();
+
+        // kill -USR1 takes a screenshot on demand
+        {
+            let screenshots = screenshots.clone();
+            let screenshot_dir = screenshot_dir.clone();
+            let mut signal = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::user_defined1())?;
+            tokio::spawn(async move {
+                while signal.recv().await.is_some() {
+                    let timestamp = chrono::Local::now().format("%Y%m%d-%H%M%S%.3f");
+                    screenshots.request(screenshot_dir.join(format!("{}.png", timestamp)));
+                }
+            });
+        }
+
+        slint::platform::set_platform(Box::new(HeadlessPlatform::new(HeadlessOptions {
+            width: self.width,
+            height: self.height,
+            screenshot_dir,
+            screenshot_interval: self.screenshot_interval.map(Duration::from_millis),
+            script,
+            golden_dir: self.golden_dir.as_ref().map(PathBuf::from),
+            screenshots: Some(screenshots),
+            vnc: self.vnc.as_ref().map(VncConfig::to_options),
+        })?))
+        .map_err(|e| ApplicationError::Unknown(format!("Failed to set platform: {:?}", e)))?;
+
+        Ok(AppWindow::new()?)
+    }
+}
@@ -0,0 +1,12 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct GestureConfig {
+    pub long_press: Option<u64>, // milliseconds, sends a right click
+    pub drag_threshold: Option<f32>, // pixels
+    #[serde(default)]
+    pub drag_scrolling: bool,
+    pub kinetic_time_constant: Option<u64>, // milliseconds
+    pub edge_width: Option<f32>, // pixels, swipes in from the edges switch pages
+    pub swipe_distance: Option<f32>, // pixels
+}
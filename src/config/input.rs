@@ -0,0 +1,16 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct InputConfig {
+    pub path: Option<String>, // discovered in /dev/input if unset
+    pub name: Option<String>, // part of the device name to discover
+    pub vendor: Option<u16>, // USB vendor id to discover
+    pub device_type: Option<String>, // "auto", "touch", "mouse" or "keys", detected if unset
+}
+
+impl InputConfig {
+    /// Whether the device is or could be detected as a touchscreen.
+    pub fn may_be_touch(&self) -> bool {
+        self.device_type.as_deref().is_none_or(|device_type| matches!(device_type.to_lowercase().as_str(), "auto" | "touch"))
+    }
+}
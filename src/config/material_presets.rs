@@ -0,0 +1,12 @@
+use std::collections::BTreeMap;
+
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct MaterialPreset {
+    /// Extra gcode to run after the heaters were set, e.g. to switch the part cooling fan off.
+    pub gcode: Option<String>,
+    /// Target temperature in °C per heater name, e.g. `extruder = 210`.
+    #[serde(flatten)]
+    pub temperatures: BTreeMap<String, u32>,
+}
@@ -4,9 +4,18 @@ pub mod display;
 pub mod display_default;
 #[cfg(unix)]
 pub mod display_fb;
+#[cfg(unix)]
+pub mod display_headless;
 pub mod moonraker;
 pub mod gcode_commands;
 pub mod ui;
+pub mod material_presets;
+pub mod screensaver;
+pub mod gestures;
+pub mod touch;
+pub mod input;
+pub mod vnc;
+pub mod control;
 
 pub use cli::*;
 pub use config::*;
@@ -14,6 +23,15 @@ pub use display::*;
 pub use display_default::*;
 #[cfg(unix)]
 pub use display_fb::*;
+#[cfg(unix)]
+pub use display_headless::*;
 pub use moonraker::*;
 pub use gcode_commands::*;
-pub use ui::*;
\ No newline at end of file
+pub use ui::*;
+pub use material_presets::*;
+pub use screensaver::*;
+pub use gestures::*;
+pub use touch::*;
+pub use input::*;
+pub use vnc::*;
+pub use control::*;
\ No newline at end of file
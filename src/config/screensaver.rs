@@ -0,0 +1,13 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct ScreensaverConfig {
+    pub dim_timeout: Option<u64>, // seconds
+    pub off_timeout: Option<u64>, // seconds
+    pub dim_brightness: Option<u32>, // percent
+    pub backlight_path: Option<String>,
+    #[serde(default)]
+    pub never_sleep_while_printing: bool,
+    #[serde(default)]
+    pub wake_on_print_state_change: bool,
+}
@@ -0,0 +1,22 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct TouchConfig {
+    pub device_type: Option<String>, // "auto", "multitouch" or "singletouch"
+    pub pressure_threshold: Option<i32>, // single-touch, raw ABS_PRESSURE value
+    pub jitter_distance: Option<f32>, // single-touch, pixels
+    #[serde(default)]
+    pub swap_xy: bool,
+    #[serde(default)]
+    pub invert_x: bool,
+    #[serde(default)]
+    pub invert_y: bool,
+    pub calibration_matrix: Option<[f32; 6]>, // a, b, c, d, e, f in window coordinates
+    pub calibration_path: Option<String>, // written by the calibration screen, relative to the config file
+}
+
+/// Contents of the file the touch calibration screen saves.
+#[derive(Serialize, Deserialize)]
+pub struct TouchCalibrationFile {
+    pub matrix: [f32; 6],
+}
@@ -1,12 +1,15 @@
 use optional_struct::optional_struct;
 use serde::Deserialize;
 
+use crate::estimator::EstimatorKind;
+
 #[optional_struct]
 #[derive(Deserialize, Debug)]
 pub struct UiConfig {
     pub dark_mode: bool,
     pub left_sidebar: Vec<String>,
     pub right_sidebar: Vec<String>,
+    pub eta_estimator: EstimatorKind,
 }
 
 impl UiConfig {
@@ -15,6 +18,7 @@ impl UiConfig {
             dark_mode: ui_config.dark_mode.unwrap_or(UiConfig::default().dark_mode),
             left_sidebar: ui_config.left_sidebar.clone().unwrap_or(UiConfig::default().left_sidebar),
             right_sidebar: ui_config.right_sidebar.clone().unwrap_or(UiConfig::default().right_sidebar),
+            eta_estimator: ui_config.eta_estimator.unwrap_or(UiConfig::default().eta_estimator),
         }
     }
 }
@@ -35,6 +39,7 @@ impl Default for UiConfig {
                 "console".into(),
                 "settings".into(),
             ],
+            eta_estimator: EstimatorKind::default(),
         }
     }
 }
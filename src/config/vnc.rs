@@ -0,0 +1,23 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct VncConfig {
+    pub listen: Option<String>, // defaults to "127.0.0.1:5900", other addresses need a password or view_only
+    pub name: Option<String>, // shown by the viewer
+    pub password: Option<String>, // VNC authentication, only the first 8 characters count
+    pub view_only: Option<bool>, // ignore the viewers' input, defaults to false
+}
+
+#[cfg(unix)]
+impl VncConfig {
+    pub fn to_options(&self) -> driver::VncOptions {
+        let default = driver::VncOptions::default();
+
+        driver::VncOptions {
+            listen: self.listen.clone().unwrap_or(default.listen),
+            name: self.name.clone().unwrap_or(default.name),
+            password: self.password.clone(),
+            view_only: self.view_only.unwrap_or(default.view_only),
+        }
+    }
+}
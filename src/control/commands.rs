@@ -0,0 +1,325 @@
+use std::path::{Component, Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use slint::{
+    platform::{PointerEventButton, WindowEvent},
+    ComponentHandle, LogicalPosition, Model, Rgba8Pixel, SharedPixelBuffer, SharedString,
+};
+
+use crate::{hardware::ScreenshotControl, ui_functions::try_name_to_id, AppWindow, DisplayStatus, Navigation, PrintStatus, QuickActions, Webhooks};
+
+/// One line of the control socket, e.g. `{"id": 1, "command": "navigate", "page": "files"}`.
+#[derive(Deserialize)]
+pub struct ControlRequest {
+    /// Echoed back so clients can match responses.
+    pub id: Option<Value>,
+    #[serde(flatten)]
+    pub command: ControlCommand,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub enum ControlCommand {
+    /// Page names as in the sidebar config, e.g. "files" or "temperature".
+    Navigate { page: String },
+    /// Logical window pixels.
+    Tap { x: f32, y: f32 },
+    /// Typed one character at a time.
+    Text { text: String },
+    GetPrintStatus,
+    GetWebhooks,
+    ListQuickActions,
+    QuickAction { name: String },
+    /// Shown like an M117 message.
+    ShowMessage { message: String },
+    /// PNG of the current window contents, saved as `<name>.png` in the
+    /// screenshot directory. Names can't contain directories.
+    Screenshot { name: String },
+}
+
+#[derive(Serialize)]
+pub struct ControlResponse {
+    pub id: Option<Value>,
+    pub ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl ControlResponse {
+    pub fn new(id: Option<Value>, result: Result<Value, String>) -> Self {
+        match result {
+            Ok(result) => Self { id, ok: true, result: Some(result), error: None },
+            Err(error) => Self { id, ok: false, result: None, error: Some(error) },
+        }
+    }
+}
+
+/// What is left of a command once it ran on the event loop.
+pub enum ControlOutput {
+    Done(Value),
+    /// Encoding and writing the PNG is too slow for the event loop, see `finish_control_command`.
+    Snapshot { snapshot: SharedPixelBuffer<Rgba8Pixel>, path: PathBuf },
+}
+
+/// Runs on the Slint event loop.
+pub fn execute_control_command(ui: &AppWindow, command: ControlCommand, screenshot_dir: &Path, screenshots: &ScreenshotControl) -> Result<ControlOutput, String> {
+    let result = match command {
+        ControlCommand::Navigate { page } => {
+            // The emergency stop opens a prompt rather than a page
+            let id = try_name_to_id(&page).filter(|id| *id != 3).ok_or_else(|| format!("Unknown page {}", page))?;
+            ui.global::<Navigation>().set_current_page(id);
+            Ok(Value::Null)
+        }
+        ControlCommand::Tap { x, y } => {
+            let position = LogicalPosition::new(x, y);
+            let button = PointerEventButton::Left;
+
+            for event in [
+                WindowEvent::PointerMoved { position },
+                WindowEvent::PointerPressed { position, button },
+                WindowEvent::PointerReleased { position, button },
+                WindowEvent::PointerExited,
+            ] {
+                ui.window().dispatch_event(event);
+            }
+            Ok(Value::Null)
+        }
+        ControlCommand::Text { text } => {
+            for c in text.chars() {
+                let text = SharedString::from(c.to_string());
+                ui.window().dispatch_event(WindowEvent::KeyPressed { text: text.clone() });
+                ui.window().dispatch_event(WindowEvent::KeyReleased { text });
+            }
+            Ok(Value::Null)
+        }
+        ControlCommand::GetPrintStatus => {
+            let print_status = ui.global::<PrintStatus>();
+            let state = print_status.get_state();
+            let state = if state.is_printing {
+                "printing"
+            } else if state.is_paused {
+                "paused"
+            } else if state.is_complete {
+                "complete"
+            } else if state.is_error {
+                "error"
+            } else if state.is_cancelled {
+                "cancelled"
+            } else {
+                "standby"
+            };
+
+            Ok(json!({
+                "state": state,
+                "filename": print_status.get_filename().as_str(),
+                "progress": print_status.get_progress(),
+                "current_layer": print_status.get_current_layer(),
+                "total_layers": print_status.get_total_layers(),
+                "elapsed_time": print_status.get_elapsed_time(),
+                "estimated_time": print_status.get_estimated_time(),
+                "finish_time": print_status.get_finish_time().as_str(),
+                "speed_factor": print_status.get_speed_factor(),
+                "extruder_factor": print_status.get_extruder_factor(),
+                "z_offset": print_status.get_z_offset(),
+            }))
+        }
+        ControlCommand::GetWebhooks => {
+            let webhooks = ui.global::<Webhooks>();
+
+            Ok(json!({
+                "moonraker_connected": webhooks.get_moonraker_connected(),
+                "klipper_state": webhooks.get_klipper_state().as_str(),
+                "klipper_state_message": webhooks.get_klipper_state_message().as_str(),
+                "connection_state": webhooks.get_connection_state().as_str(),
+                "connection_message": webhooks.get_connection_message().as_str(),
+                "connection_retry_in": webhooks.get_connection_retry_in(),
+            }))
+        }
+        ControlCommand::ListQuickActions => {
+            let quick_actions: Vec<String> = ui.global::<QuickActions>().get_quick_actions().iter().map(|name| name.to_string()).collect();
+            Ok(json!(quick_actions))
+        }
+        ControlCommand::QuickAction { name } => {
+            let quick_actions = ui.global::<QuickActions>();
+
+            if !quick_actions.get_quick_actions().iter().any(|quick_action| quick_action.as_str() == name) {
+                return Err(format!("Unknown quick action {}", name));
+            }
+
+            quick_actions.invoke_execute_quick_action(SharedString::from(name));
+            Ok(Value::Null)
+        }
+        ControlCommand::ShowMessage { message } => {
+            ui.global::<DisplayStatus>().set_message(SharedString::from(message));
+            Ok(Value::Null)
+        }
+        ControlCommand::Screenshot { name } => {
+            let path = screenshot_path(screenshot_dir, &name)?;
+
+            // The headless backend has the frame at hand and writes it after the next one
+            if let Some(requests) = screenshots.requests() {
+                requests.request(path.clone());
+                return Ok(ControlOutput::Done(json!({ "path": path.to_string_lossy() })));
+            }
+
+            let snapshot = ui.window().take_snapshot().map_err(|e| format!("Failed to take screenshot: {}", e))?;
+            return Ok(ControlOutput::Snapshot { snapshot, path });
+        }
+    };
+
+    result.map(ControlOutput::Done)
+}
+
+/// Runs the rest of a command off the event loop.
+pub async fn finish_control_command(output: ControlOutput) -> Result<Value, String> {
+    match output {
+        ControlOutput::Done(value) => Ok(value),
+        ControlOutput::Snapshot { snapshot, path } => tokio::task::spawn_blocking(move || save_snapshot(&snapshot, &path))
+            .await
+            .map_err(|e| format!("Failed to save screenshot: {}", e))?,
+    }
+}
+
+/// Where the screenshot `name` goes. Anything but a plain file name is
+/// rejected, so the socket can't be used to write files elsewhere.
+pub fn screenshot_path(screenshot_dir: &Path, name: &str) -> Result<PathBuf, String> {
+    let name = name.strip_suffix(".png").unwrap_or(name);
+    let mut components = Path::new(name).components();
+
+    match (components.next(), components.next()) {
+        (Some(Component::Normal(_)), None) if !name.contains(['/', '\\']) => Ok(screenshot_dir.join(format!("{}.png", name))),
+        _ => Err(format!("Invalid screenshot name {:?}, expected a file name without directories", name)),
+    }
+}
+
+fn save_snapshot(snapshot: &SharedPixelBuffer<Rgba8Pixel>, path: &Path) -> Result<Value, String> {
+    let image = image::RgbaImage::from_raw(snapshot.width(), snapshot.height(), snapshot.as_bytes().to_vec())
+        .ok_or_else(|| String::from("Unexpected screenshot size"))?;
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+
+    image.save(path).map_err(|e| format!("Failed to save {}: {}", path.display(), e))?;
+    Ok(json!({ "path": path.to_string_lossy() }))
+}
+
+#[cfg(test)]
+mod tests {
+    use driver::{HeadlessOptions, HeadlessPlatform};
+
+    use super::*;
+    use crate::config::DisplayConfig;
+
+    fn parse(line: &str) -> Result<ControlRequest, serde_json::Error> {
+        serde_json::from_str(line)
+    }
+
+    /// A window on the headless backend, each test runs on a thread of its own.
+    fn window() -> AppWindow {
+        let platform = HeadlessPlatform::new(HeadlessOptions::new(480, 272)).unwrap();
+        slint::platform::set_platform(Box::new(platform)).unwrap();
+        AppWindow::new().unwrap()
+    }
+
+    fn execute(ui: &AppWindow, command: ControlCommand) -> Result<Value, String> {
+        match execute_control_command(ui, command, Path::new("screenshots"), &ScreenshotControl::default())? {
+            ControlOutput::Done(value) => Ok(value),
+            ControlOutput::Snapshot { .. } => panic!("unexpected snapshot"),
+        }
+    }
+
+    #[test]
+    fn parses_commands_with_their_arguments() {
+        let request = parse(r#"{"id": 7, "command": "navigate", "page": "files"}"#).unwrap();
+        assert_eq!(request.id, Some(json!(7)));
+        assert!(matches!(request.command, ControlCommand::Navigate { page } if page == "files"));
+
+        let request = parse(r#"{"command": "tap", "x": 10, "y": 20.5}"#).unwrap();
+        assert_eq!(request.id, None);
+        assert!(matches!(request.command, ControlCommand::Tap { x, y } if x == 10.0 && y == 20.5));
+
+        let request = parse(r#"{"id": "a", "command": "get_print_status"}"#).unwrap();
+        assert!(matches!(request.command, ControlCommand::GetPrintStatus));
+
+        let request = parse(r#"{"command": "screenshot", "name": "home"}"#).unwrap();
+        assert!(matches!(request.command, ControlCommand::Screenshot { name } if name == "home"));
+    }
+
+    #[test]
+    fn rejects_unknown_commands_and_missing_arguments() {
+        assert!(parse(r#"{"id": 1, "command": "reboot"}"#).is_err());
+        assert!(parse(r#"{"id": 1, "command": "tap", "x": 10}"#).is_err());
+        assert!(parse(r#"{"id": 1, "page": "files"}"#).is_err());
+        assert!(parse("navigate files").is_err());
+    }
+
+    #[test]
+    fn responses_carry_either_a_result_or_an_error() {
+        let ok = serde_json::to_value(ControlResponse::new(Some(json!(1)), Ok(json!({ "a": 1 })))).unwrap();
+        assert_eq!(ok, json!({ "id": 1, "ok": true, "result": { "a": 1 } }));
+
+        let error = serde_json::to_value(ControlResponse::new(None, Err(String::from("nope")))).unwrap();
+        assert_eq!(error, json!({ "id": null, "ok": false, "error": "nope" }));
+    }
+
+    #[test]
+    fn screenshots_stay_in_the_screenshot_directory() {
+        let dir = Path::new("/tmp/shots");
+
+        assert_eq!(screenshot_path(dir, "home"), Ok(PathBuf::from("/tmp/shots/home.png")));
+        assert_eq!(screenshot_path(dir, "home.png"), Ok(PathBuf::from("/tmp/shots/home.png")));
+
+        for name in ["", ".", "..", "../home", "pages/home", "/etc/passwd", "..\\home"] {
+            assert!(screenshot_path(dir, name).is_err(), "{:?} was accepted", name);
+        }
+    }
+
+    #[test]
+    fn navigates_to_sidebar_pages_only() {
+        let ui = window();
+
+        assert_eq!(execute(&ui, ControlCommand::Navigate { page: String::from("history") }), Ok(Value::Null));
+        assert_eq!(ui.global::<Navigation>().get_current_page(), 8);
+
+        assert!(execute(&ui, ControlCommand::Navigate { page: String::from("emergency_stop") }).is_err());
+        assert!(execute(&ui, ControlCommand::Navigate { page: String::from("nowhere") }).is_err());
+        assert_eq!(ui.global::<Navigation>().get_current_page(), 8);
+    }
+
+    #[test]
+    fn shows_messages_and_reports_state() {
+        let ui = window();
+
+        execute(&ui, ControlCommand::ShowMessage { message: String::from("Hello") }).unwrap();
+        assert_eq!(ui.global::<DisplayStatus>().get_message(), "Hello");
+
+        let status = execute(&ui, ControlCommand::GetPrintStatus).unwrap();
+        assert_eq!(status["state"], "standby");
+
+        let webhooks = execute(&ui, ControlCommand::GetWebhooks).unwrap();
+        assert!(webhooks.get("klipper_state").is_some());
+    }
+
+    #[test]
+    fn unknown_quick_actions_are_errors() {
+        let ui = window();
+        assert!(execute(&ui, ControlCommand::QuickAction { name: String::from("nothing") }).is_err());
+    }
+
+    #[test]
+    fn headless_screenshots_go_through_the_backend() {
+        let ui = window();
+        let config: DisplayConfig = toml::from_str("[headless]\nwidth = 480\nheight = 272").unwrap();
+        let screenshots = ScreenshotControl::from_config(&config);
+
+        let output = execute_control_command(&ui, ControlCommand::Screenshot { name: String::from("home") }, Path::new("shots"), &screenshots).unwrap();
+        assert!(matches!(output, ControlOutput::Done(value) if value == json!({ "path": "shots/home.png" })));
+
+        let output = execute_control_command(&ui, ControlCommand::Screenshot { name: String::from("../home") }, Path::new("shots"), &screenshots);
+        assert!(output.is_err());
+    }
+}
@@ -0,0 +1,96 @@
+use std::{fs, os::unix::fs::FileTypeExt, path::{Path, PathBuf}};
+
+use slint::Weak;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::{UnixListener, UnixStream},
+    sync::oneshot,
+};
+
+use crate::{
+    application_error::ApplicationError,
+    config::ControlConfig,
+    control::{execute_control_command, finish_control_command, ControlCommand, ControlRequest, ControlResponse},
+    hardware::ScreenshotControl,
+    AppWindow,
+};
+
+/// Shared by all connections.
+#[derive(Clone)]
+struct ControlContext {
+    ui_weak: Weak<AppWindow>,
+    screenshot_dir: PathBuf,
+    screenshots: ScreenshotControl,
+}
+
+/// Listens for JSON requests, one per line, and answers each with one line.
+/// Commands run on the Slint event loop.
+pub fn start_control_socket(config: &ControlConfig, ui_weak: Weak<AppWindow>, screenshots: ScreenshotControl) -> Result<(), ApplicationError> {
+    let path = Path::new(&config.socket_path);
+
+    // Left behind by a previous run, anything else is not ours to remove
+    if fs::symlink_metadata(path).is_ok_and(|metadata| metadata.file_type().is_socket()) {
+        fs::remove_file(path)?;
+    }
+
+    let listener = UnixListener::bind(path)?;
+    println!("Control socket listening on {}", path.display());
+
+    let context = ControlContext {
+        ui_weak,
+        screenshot_dir: PathBuf::from(config.screenshot_dir.as_deref().unwrap_or("screenshots")),
+        screenshots,
+    };
+
+    tokio::spawn(async move {
+        loop {
+            match listener.accept().await {
+                Ok((stream, _)) => {
+                    tokio::spawn(handle_connection(stream, context.clone()));
+                }
+                Err(e) => println!("Control socket failed to accept: {}", e),
+            }
+        }
+    });
+
+    Ok(())
+}
+
+async fn handle_connection(stream: UnixStream, context: ControlContext) {
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+
+    while let Ok(Some(line)) = lines.next_line().await {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let response = match serde_json::from_str::<ControlRequest>(&line) {
+            Ok(request) => ControlResponse::new(request.id, execute(request.command, &context).await),
+            Err(e) => ControlResponse::new(None, Err(format!("Invalid request: {}", e))),
+        };
+
+        let mut response = serde_json::to_string(&response).unwrap();
+        response.push('\n');
+
+        if writer.write_all(response.as_bytes()).await.is_err() {
+            return;
+        }
+    }
+}
+
+async fn execute(command: ControlCommand, context: &ControlContext) -> Result<serde_json::Value, String> {
+    let (sender, receiver) = oneshot::channel();
+    let screenshot_dir = context.screenshot_dir.clone();
+    let screenshots = context.screenshots.clone();
+
+    context
+        .ui_weak
+        .upgrade_in_event_loop(move |ui| {
+            let _ = sender.send(execute_control_command(&ui, command, &screenshot_dir, &screenshots));
+        })
+        .map_err(|e| format!("Event loop is not running: {}", e))?;
+
+    let output = receiver.await.map_err(|_| String::from("The window is gone"))??;
+    finish_control_command(output).await
+}
@@ -0,0 +1,5 @@
+pub mod commands;
+pub mod control_socket;
+
+pub use commands::*;
+pub use control_socket::*;
\ No newline at end of file
@@ -0,0 +1,69 @@
+use crate::estimator::{FilePositionEstimator, PrintProgress, PrintTimeEstimator, SlicerEstimator};
+
+/// Starts out with the slicer estimate and shifts towards the file position estimate as the print progresses,
+/// as the latter is unreliable early on. Falls back to whichever of the two is available.
+pub struct BlendedEstimator;
+
+impl PrintTimeEstimator for BlendedEstimator {
+    fn estimate_remaining(&self, progress: &PrintProgress) -> Option<f32> {
+        let file_estimate = FilePositionEstimator.estimate_remaining(progress);
+        let slicer_estimate = SlicerEstimator.estimate_remaining(progress);
+
+        match (file_estimate, slicer_estimate, progress.file_progress()) {
+            (Some(file_estimate), Some(slicer_estimate), Some(weight)) => {
+                Some(file_estimate * weight + slicer_estimate * (1.0 - weight))
+            }
+            (Some(file_estimate), None, _) => Some(file_estimate),
+            (None, Some(slicer_estimate), _) => Some(slicer_estimate),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 10000 bytes of gcode and a slicer estimate of an hour
+    fn progress(file_position: i32, print_duration: f32) -> PrintProgress {
+        PrintProgress {
+            file_position,
+            print_duration,
+            gcode_start_byte: 0,
+            gcode_end_byte: 10000,
+            slicer_estimate: 3600.0,
+            ..PrintProgress::default()
+        }
+    }
+
+    #[test]
+    fn starts_with_the_slicer_estimate() {
+        assert_eq!(BlendedEstimator.estimate_remaining(&progress(0, 0.0)), Some(3600.0));
+    }
+
+    #[test]
+    fn weights_by_file_progress() {
+        // A quarter through after 20 minutes: the file position says 60 minutes
+        // are left, the slicer 40, weighted 1:3
+        let remaining = BlendedEstimator.estimate_remaining(&progress(2500, 1200.0)).unwrap();
+
+        assert!((remaining - (3600.0 * 0.25 + 2400.0 * 0.75)).abs() < 0.01, "{}", remaining);
+    }
+
+    #[test]
+    fn ends_with_the_file_position_estimate() {
+        // The slicer still expects 10 minutes, but the whole file was read
+        assert_eq!(BlendedEstimator.estimate_remaining(&progress(10000, 3000.0)), Some(0.0));
+    }
+
+    #[test]
+    fn falls_back_to_whichever_estimate_exists() {
+        let without_slicer = PrintProgress { slicer_estimate: 0.0, ..progress(5000, 1800.0) };
+        let without_gcode_range = PrintProgress { gcode_end_byte: 0, ..progress(5000, 1800.0) };
+        let without_metadata = PrintProgress { file_position: 5000, print_duration: 1800.0, ..PrintProgress::default() };
+
+        assert_eq!(BlendedEstimator.estimate_remaining(&without_slicer), Some(1800.0));
+        assert_eq!(BlendedEstimator.estimate_remaining(&without_gcode_range), Some(1800.0));
+        assert_eq!(BlendedEstimator.estimate_remaining(&without_metadata), None);
+    }
+}
@@ -0,0 +1,84 @@
+use moonraker_rs::requests::GcodeMetadata;
+use serde::Deserialize;
+
+use crate::estimator::{BlendedEstimator, FilamentEstimator, FilePositionEstimator, SlicerEstimator};
+
+/// Everything the estimators know about the running print job.
+#[derive(Debug, Default, Clone)]
+pub struct PrintProgress {
+    /// Time spent actually printing, in seconds. Klipper does not count paused time here.
+    pub print_duration: f32,
+    pub file_position: i32,
+    pub gcode_start_byte: i32,
+    pub gcode_end_byte: i32,
+    /// Filament extruded so far, in mm.
+    pub filament_used: f32,
+    /// Filament needed for the whole print according to the slicer, in mm.
+    pub filament_total: f32,
+    /// Total print time according to the slicer, in seconds.
+    pub slicer_estimate: f32,
+}
+
+impl PrintProgress {
+    pub fn apply_metadata(&mut self, metadata: &GcodeMetadata) {
+        self.gcode_start_byte = metadata.gcode_start_byte;
+        self.gcode_end_byte = metadata.gcode_end_byte;
+        self.filament_total = metadata.filament_total;
+        self.slicer_estimate = metadata.estimated_time;
+    }
+
+    /// Progress through the gcode part of the file, ignoring the header and the trailing slicer config.
+    pub fn file_progress(&self) -> Option<f32> {
+        if self.gcode_end_byte <= self.gcode_start_byte || self.file_position <= self.gcode_start_byte {
+            return None;
+        }
+
+        let progress = (self.file_position - self.gcode_start_byte) as f32
+            / (self.gcode_end_byte - self.gcode_start_byte) as f32;
+
+        Some(progress.min(1.0))
+    }
+
+    pub fn filament_progress(&self) -> Option<f32> {
+        if self.filament_total <= 0.0 || self.filament_used <= 0.0 {
+            return None;
+        }
+
+        Some((self.filament_used / self.filament_total).min(1.0))
+    }
+
+    /// Extrapolates the remaining time from the time it took to reach `progress`.
+    pub fn extrapolate_remaining(&self, progress: f32) -> Option<f32> {
+        if progress <= 0.0 || self.print_duration <= 0.0 {
+            return None;
+        }
+
+        Some((self.print_duration / progress - self.print_duration).max(0.0))
+    }
+}
+
+pub trait PrintTimeEstimator: Send {
+    /// Remaining print time in seconds, or `None` if there is not enough data yet.
+    fn estimate_remaining(&self, progress: &PrintProgress) -> Option<f32>;
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum EstimatorKind {
+    FilePosition,
+    Filament,
+    Slicer,
+    #[default]
+    Blended,
+}
+
+impl EstimatorKind {
+    pub fn create(self) -> Box<dyn PrintTimeEstimator> {
+        match self {
+            EstimatorKind::FilePosition => Box::new(FilePositionEstimator),
+            EstimatorKind::Filament => Box::new(FilamentEstimator),
+            EstimatorKind::Slicer => Box::new(SlicerEstimator),
+            EstimatorKind::Blended => Box::new(BlendedEstimator),
+        }
+    }
+}
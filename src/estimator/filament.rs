@@ -0,0 +1,41 @@
+use crate::estimator::{PrintProgress, PrintTimeEstimator};
+
+/// Extrapolates from the filament used so far compared to the slicer's `filament_total`.
+pub struct FilamentEstimator;
+
+impl PrintTimeEstimator for FilamentEstimator {
+    fn estimate_remaining(&self, progress: &PrintProgress) -> Option<f32> {
+        progress.extrapolate_remaining(progress.filament_progress()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn progress(filament_used: f32, filament_total: f32, print_duration: f32) -> PrintProgress {
+        PrintProgress { filament_used, filament_total, print_duration, ..PrintProgress::default() }
+    }
+
+    #[test]
+    fn extrapolates_from_the_filament_used() {
+        assert_eq!(FilamentEstimator.estimate_remaining(&progress(250.0, 1000.0, 600.0)), Some(1800.0));
+    }
+
+    #[test]
+    fn nothing_extruded_yet_has_no_estimate() {
+        assert_eq!(FilamentEstimator.estimate_remaining(&progress(0.0, 1000.0, 0.0)), None);
+        assert_eq!(FilamentEstimator.estimate_remaining(&progress(0.0, 1000.0, 60.0)), None);
+    }
+
+    #[test]
+    fn all_filament_used_leaves_nothing() {
+        assert_eq!(FilamentEstimator.estimate_remaining(&progress(1000.0, 1000.0, 3600.0)), Some(0.0));
+        assert_eq!(FilamentEstimator.estimate_remaining(&progress(1100.0, 1000.0, 3600.0)), Some(0.0));
+    }
+
+    #[test]
+    fn needs_the_filament_total_from_the_metadata() {
+        assert_eq!(FilamentEstimator.estimate_remaining(&progress(250.0, 0.0, 600.0)), None);
+    }
+}
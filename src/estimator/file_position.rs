@@ -0,0 +1,44 @@
+use crate::estimator::{PrintProgress, PrintTimeEstimator};
+
+/// Extrapolates from how far the virtual sdcard has read into the gcode.
+pub struct FilePositionEstimator;
+
+impl PrintTimeEstimator for FilePositionEstimator {
+    fn estimate_remaining(&self, progress: &PrintProgress) -> Option<f32> {
+        progress.extrapolate_remaining(progress.file_progress()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The gcode sits between byte 1000 and 11000 of the file
+    fn progress(file_position: i32, print_duration: f32) -> PrintProgress {
+        PrintProgress { file_position, print_duration, gcode_start_byte: 1000, gcode_end_byte: 11000, ..PrintProgress::default() }
+    }
+
+    #[test]
+    fn extrapolates_from_the_file_position() {
+        assert_eq!(FilePositionEstimator.estimate_remaining(&progress(3500, 600.0)), Some(1800.0));
+    }
+
+    #[test]
+    fn the_header_does_not_count_as_progress() {
+        assert_eq!(FilePositionEstimator.estimate_remaining(&progress(0, 0.0)), None);
+        assert_eq!(FilePositionEstimator.estimate_remaining(&progress(1000, 30.0)), None);
+    }
+
+    #[test]
+    fn the_trailing_slicer_config_counts_as_done() {
+        assert_eq!(FilePositionEstimator.estimate_remaining(&progress(11000, 3600.0)), Some(0.0));
+        assert_eq!(FilePositionEstimator.estimate_remaining(&progress(12000, 3600.0)), Some(0.0));
+    }
+
+    #[test]
+    fn needs_the_gcode_range_from_the_metadata() {
+        let progress = PrintProgress { file_position: 3500, print_duration: 600.0, ..PrintProgress::default() };
+
+        assert_eq!(FilePositionEstimator.estimate_remaining(&progress), None);
+    }
+}
@@ -0,0 +1,11 @@
+pub mod estimator;
+pub mod file_position;
+pub mod filament;
+pub mod slicer;
+pub mod blended;
+
+pub use estimator::*;
+pub use file_position::*;
+pub use filament::*;
+pub use slicer::*;
+pub use blended::*;
@@ -0,0 +1,39 @@
+use crate::estimator::{PrintProgress, PrintTimeEstimator};
+
+/// Trusts the slicer's estimate and subtracts the time already printed.
+pub struct SlicerEstimator;
+
+impl PrintTimeEstimator for SlicerEstimator {
+    fn estimate_remaining(&self, progress: &PrintProgress) -> Option<f32> {
+        if progress.slicer_estimate <= 0.0 {
+            return None;
+        }
+
+        Some((progress.slicer_estimate - progress.print_duration).max(0.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn progress(slicer_estimate: f32, print_duration: f32) -> PrintProgress {
+        PrintProgress { slicer_estimate, print_duration, ..PrintProgress::default() }
+    }
+
+    #[test]
+    fn counts_down_from_the_slicer_estimate() {
+        assert_eq!(SlicerEstimator.estimate_remaining(&progress(3600.0, 0.0)), Some(3600.0));
+        assert_eq!(SlicerEstimator.estimate_remaining(&progress(3600.0, 600.0)), Some(3000.0));
+    }
+
+    #[test]
+    fn stops_at_zero_when_running_over() {
+        assert_eq!(SlicerEstimator.estimate_remaining(&progress(3600.0, 4000.0)), Some(0.0));
+    }
+
+    #[test]
+    fn needs_a_slicer_estimate() {
+        assert_eq!(SlicerEstimator.estimate_remaining(&progress(0.0, 600.0)), None);
+    }
+}
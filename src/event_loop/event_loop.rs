@@ -1,16 +1,20 @@
-use std::sync::Arc;
+use std::{collections::BTreeMap, sync::Arc};
 
 use moonraker_rs::{connector::websocket_read::{MoonrakerEvent, PrinterEvent}, moonraker_connection::{MoonrakerConnection, WebsocketEvent}, printer_objects::PrintState};
 use slint::{ComponentHandle, Weak};
 
-use crate::{application_error::ApplicationError, Webhooks, AppWindow};
+use crate::{application_error::ApplicationError, config::MaterialPreset, estimator::{PrintProgress, PrintTimeEstimator}, hardware::ScreensaverControl, Webhooks, AppWindow};
 
 pub struct EventLoop
 {
     pub ui_weak : Weak<AppWindow>,
     pub moonraker_connection : Arc<MoonrakerConnection>,
     pub last_state: PrintState,
-    pub slicer_time_estimate : Option<u64>,
+    pub print_progress: PrintProgress,
+    pub print_progress_filename: Option<String>, // File the metadata in print_progress belongs to
+    pub estimator: Box<dyn PrintTimeEstimator>,
+    pub material_presets: BTreeMap<String, MaterialPreset>,
+    pub screensaver: ScreensaverControl,
     pub progress: f32, // TODO: Figure out a better way to track this, probably directly from the moonraker connection.
 }
 
@@ -21,9 +25,19 @@ pub struct EventLoop
 
 impl EventLoop
 {
-    pub fn new(ui_weak : Weak<AppWindow>, moonraker_connection : Arc<MoonrakerConnection>) -> EventLoop
+    pub fn new(ui_weak : Weak<AppWindow>, moonraker_connection : Arc<MoonrakerConnection>, estimator : Box<dyn PrintTimeEstimator>, material_presets : BTreeMap<String, MaterialPreset>, screensaver : ScreensaverControl) -> EventLoop
     {
-        EventLoop { ui_weak: ui_weak, moonraker_connection: moonraker_connection, last_state: PrintState::Standby, slicer_time_estimate: None, progress: 0.0 }
+        EventLoop {
+            ui_weak: ui_weak,
+            moonraker_connection: moonraker_connection,
+            last_state: PrintState::Standby,
+            print_progress: PrintProgress::default(),
+            print_progress_filename: None,
+            estimator: estimator,
+            material_presets: material_presets,
+            screensaver: screensaver,
+            progress: 0.0,
+        }
     }
 
     pub async fn event_loop(&mut self)
@@ -39,6 +53,7 @@ impl EventLoop
                     {
                         WebsocketEvent::Connected => self.on_connected().await,
                         WebsocketEvent::Disconnected => self.on_disconnected().await,
+                        WebsocketEvent::ConnectionStateChanged(state) => self.handle_connection_state_changed(state),
                         WebsocketEvent::MoonrakerEvent(event) => self.on_event(event).await,
                         WebsocketEvent::ApplicationError(err_msg) => {
                             // TODO: Do something with this
@@ -85,6 +100,8 @@ impl EventLoop
         match moonraker_event
         {
             MoonrakerEvent::NotifyStatusUpdate(printer_event) => self.on_status_update(printer_event).await,
+            MoonrakerEvent::NotifyHistoryChanged(history_changed) => self.handle_history_changed(history_changed),
+            MoonrakerEvent::NotifyJobQueueChanged(job_queue_changed) => self.handle_job_queue_changed(job_queue_changed),
             _ => Ok(()),
         }
     }
@@ -95,6 +112,7 @@ impl EventLoop
         self.handle_klipper_state_updates(printer_event)?;
         self.handle_display_status_updates(printer_event)?;
         self.handle_print_stats_updates(printer_event).await?;
+        self.handle_printer_info_updates(printer_event)?;
 
         Ok(())
     }
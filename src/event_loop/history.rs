@@ -0,0 +1,17 @@
+use moonraker_rs::requests::HistoryChangedNotification;
+use slint::ComponentHandle;
+
+use crate::{application_error::ApplicationError, event_loop::EventLoop, History};
+
+impl EventLoop {
+    pub fn handle_history_changed(
+        &self,
+        _history_changed: &HistoryChangedNotification,
+    ) -> Result<(), ApplicationError> {
+        self.ui_weak.upgrade_in_event_loop(move |ui| {
+            ui.global::<History>().invoke_fetch_history();
+        })?;
+
+        Ok(())
+    }
+}
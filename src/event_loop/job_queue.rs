@@ -0,0 +1,28 @@
+use moonraker_rs::requests::JobQueueChangedNotification;
+use slint::{ComponentHandle, ModelRc, SharedString, VecModel};
+
+use crate::{application_error::ApplicationError, event_loop::EventLoop, ui_functions::convert_queued_jobs, JobQueue};
+
+impl EventLoop {
+    pub fn handle_job_queue_changed(
+        &self,
+        job_queue_changed: &JobQueueChangedNotification,
+    ) -> Result<(), ApplicationError> {
+        let queue_state = SharedString::from(job_queue_changed.queue_state.to_string());
+        // A plain state change comes without the queue contents
+        let updated_queue = job_queue_changed
+            .updated_queue
+            .as_ref()
+            .map(|queue| convert_queued_jobs(queue));
+
+        self.ui_weak.upgrade_in_event_loop(move |ui| {
+            ui.global::<JobQueue>().set_queue_state(queue_state);
+
+            if let Some(updated_queue) = updated_queue {
+                ui.global::<JobQueue>().set_jobs(ModelRc::new(VecModel::from(updated_queue)));
+            }
+        })?;
+
+        Ok(())
+    }
+}
@@ -1,4 +1,6 @@
-use moonraker_rs::{connector::websocket_read::PrinterEvent};
+use std::time::Instant;
+
+use moonraker_rs::{connector::websocket_read::PrinterEvent, moonraker_connection::ConnectionState};
 use slint::{ComponentHandle, SharedString};
 
 use crate::{application_error::ApplicationError, event_loop::EventLoop, Webhooks};
@@ -20,4 +22,29 @@ impl EventLoop {
 
         Ok(())
     }
+
+    pub fn handle_connection_state_changed(
+        &self,
+        connection_state: &ConnectionState,
+    ) -> Result<(), ApplicationError> {
+        let (state, message, retry_in) = match connection_state {
+            ConnectionState::Connecting { attempt: 1 } => ("connecting", String::new(), 0),
+            ConnectionState::Connecting { attempt } => ("connecting", format!("Attempt {}", attempt), 0),
+            ConnectionState::Connected => ("connected", String::new(), 0),
+            ConnectionState::KlippyNotReady { message } => ("klippy_not_ready", message.clone(), 0),
+            ConnectionState::BackingOff { next_attempt, last_error, .. } => (
+                "backing_off",
+                last_error.clone(),
+                next_attempt.saturating_duration_since(Instant::now()).as_secs_f32().ceil() as i32,
+            ),
+        };
+
+        self.ui_weak.upgrade_in_event_loop(move |ui| {
+            ui.global::<Webhooks>().set_connection_state(state.into());
+            ui.global::<Webhooks>().set_connection_message(message.into());
+            ui.global::<Webhooks>().set_connection_retry_in(retry_in);
+        })?;
+
+        Ok(())
+    }
 }
@@ -3,9 +3,15 @@ pub mod temperature_devices;
 pub mod klipper_state;
 pub mod display_status;
 pub mod print_stats;
+pub mod history;
+pub mod job_queue;
+pub mod printer_info;
 
 pub use event_loop::*;
 pub use temperature_devices::*;
 pub use klipper_state::*;
 pub use display_status::*;
-pub use print_stats::*;
\ No newline at end of file
+pub use print_stats::*;
+pub use history::*;
+pub use job_queue::*;
+pub use printer_info::*;
\ No newline at end of file
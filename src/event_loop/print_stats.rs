@@ -1,33 +1,44 @@
 use moonraker_rs::{connector::websocket_read::PrinterEvent, printer_objects::PrintState, requests::FileManagementRequestHandler};
 use slint::{ComponentHandle, SharedString};
 
-use crate::{PrintStatus, PrintStatusState, application_error::ApplicationError, event_loop::EventLoop};
+use crate::{PrintStatus, PrintStatusState, application_error::ApplicationError, estimator::PrintProgress, event_loop::EventLoop, ui_functions::format_unix_timestamp};
 
 impl EventLoop {
     pub async fn handle_print_stats_updates(
         &mut self,
         printer_event: &PrinterEvent,
     ) -> Result<(), ApplicationError> {
+        if let PrinterEvent::VirtualSdcard(virtual_sdcard) = printer_event {
+            self.print_progress.file_position = virtual_sdcard.file_position;
+            // File position based estimators move on with every sdcard update, not only with print_stats
+            self.update_remaining_time()?;
+        }
+
         if let PrinterEvent::PrintStats(print_stats) = printer_event {
             let total_layers = print_stats.info.total_layer.unwrap_or(0);
             let current_layer = print_stats.info.current_layer.unwrap_or(0);
             let elapsed_time = print_stats.print_duration;
             let filename = SharedString::from(&print_stats.filename);
 
+            // Reprinting the same file still needs fresh metadata, as the file might have been replaced
             if self.last_state == PrintState::Standby && print_stats.state == PrintState::Printing {
-                self.slicer_time_estimate = None;
+                self.print_progress_filename = None;
             }
 
-            if self.slicer_time_estimate.is_none() {
-                let metadata = self.moonraker_connection.get_gcode_metadata_for_file(&print_stats.filename).await;
-                if let Ok(metadata) = metadata {
-                    self.slicer_time_estimate = Some(metadata.estimated_time as u64);
-                }
-                else {
-                    self.slicer_time_estimate = Some(0);
+            if !print_stats.filename.is_empty() && self.print_progress_filename.as_ref() != Some(&print_stats.filename) {
+                self.print_progress = PrintProgress::default();
+
+                match self.moonraker_connection.get_gcode_metadata_for_file(&print_stats.filename).await {
+                    Ok(metadata) => self.print_progress.apply_metadata(&metadata),
+                    Err(e) => eprintln!("Failed to fetch metadata for {}: {}", print_stats.filename, e),
                 }
+
+                self.print_progress_filename = Some(print_stats.filename.clone());
             }
 
+            self.print_progress.print_duration = print_stats.print_duration;
+            self.print_progress.filament_used = print_stats.filament_used;
+            self.screensaver.on_print_state(&self.last_state, &print_stats.state);
             self.last_state = print_stats.state.clone();
 
             let state = PrintStatusState {
@@ -38,26 +49,40 @@ impl EventLoop {
                 is_error: print_stats.state == PrintState::Error,
                 is_cancelled: print_stats.state == PrintState::Cancelled,
             };
-            
-            let remaining_time_s_percentage = (elapsed_time / self.progress) - elapsed_time;
-
-            let remaining_time = if let Some(estimate) = self.slicer_time_estimate && estimate > 0 {
-                remaining_time_s_percentage * self.progress + (estimate as f32) * (1.0 - self.progress)
-            }
-            else {
-                remaining_time_s_percentage
-            };
 
             self.ui_weak.upgrade_in_event_loop(move |ui| {
                 ui.global::<PrintStatus>().set_total_layers(total_layers);
                 ui.global::<PrintStatus>().set_current_layer(current_layer);
                 ui.global::<PrintStatus>().set_elapsed_time(elapsed_time);
-                ui.global::<PrintStatus>().set_estimated_time(remaining_time);
                 ui.global::<PrintStatus>().set_filename(filename);
                 ui.global::<PrintStatus>().set_state(state);
             })?;
+
+            self.update_remaining_time()?;
         }
 
         Ok(())
     }
+
+    fn update_remaining_time(&self) -> Result<(), ApplicationError> {
+        let remaining_time = self.estimator.estimate_remaining(&self.print_progress);
+
+        // While paused the remaining time stands still, so the finish time keeps moving back
+        let finish_time = match remaining_time {
+            Some(remaining_time) if self.last_state == PrintState::Printing || self.last_state == PrintState::Paused => {
+                let now = chrono::Local::now().timestamp() as f64;
+                SharedString::from(format_unix_timestamp(now + remaining_time as f64, "%H:%M"))
+            }
+            _ => SharedString::new(),
+        };
+
+        let remaining_time = remaining_time.unwrap_or(0.0);
+
+        self.ui_weak.upgrade_in_event_loop(move |ui| {
+            ui.global::<PrintStatus>().set_estimated_time(remaining_time);
+            ui.global::<PrintStatus>().set_finish_time(finish_time);
+        })?;
+
+        Ok(())
+    }
 }
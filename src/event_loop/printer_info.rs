@@ -0,0 +1,38 @@
+use moonraker_rs::connector::websocket_read::PrinterEvent;
+use slint::{ComponentHandle, ModelRc, VecModel};
+
+use crate::{application_error::ApplicationError, event_loop::EventLoop, ui_functions::create_material_preset_entries, MaterialPresets, PrinterInfo};
+
+impl EventLoop {
+    pub fn handle_printer_info_updates(
+        &self,
+        printer_event: &PrinterEvent,
+    ) -> Result<(), ApplicationError> {
+        match printer_event {
+            PrinterEvent::Toolhead(toolhead) => {
+                let max_z = toolhead.axis_maximum[2];
+
+                self.ui_weak.upgrade_in_event_loop(move |ui| {
+                    ui.global::<PrinterInfo>().set_max_z(max_z);
+                })?;
+            }
+            PrinterEvent::Configfile(configfile) => {
+                let nozzle_diameter = configfile.nozzle_diameter("extruder").unwrap_or_default();
+                let extruder_max_temp = configfile.max_temp("extruder").unwrap_or_default();
+                let heater_bed_max_temp = configfile.max_temp("heater_bed").unwrap_or_default();
+                let material_presets = create_material_preset_entries(&self.material_presets, Some(configfile));
+
+                self.ui_weak.upgrade_in_event_loop(move |ui| {
+                    let printer_info = ui.global::<PrinterInfo>();
+                    printer_info.set_nozzle_diameter(nozzle_diameter);
+                    printer_info.set_extruder_max_temp(extruder_max_temp);
+                    printer_info.set_heater_bed_max_temp(heater_bed_max_temp);
+                    ui.global::<MaterialPresets>().set_presets(ModelRc::new(VecModel::from(material_presets)));
+                })?;
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+}
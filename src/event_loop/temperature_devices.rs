@@ -7,6 +7,18 @@ use crate::{
     application_error::ApplicationError, event_loop::EventLoop, AppWindow, Heater, HeaterFan, TemperatureSensor, TemperatureSensors
 };
 
+/// Replaces the heater with the same name, or appends it if it is new.
+fn upsert_heater(heaters: ModelRc<Heater>, heater: Heater) -> ModelRc<Heater> {
+    let mut entries = heaters.iter().collect::<Vec<Heater>>();
+
+    match entries.iter().position(|entry| entry.name == heater.name) {
+        Some(index) => entries[index] = heater,
+        None => entries.push(heater),
+    }
+
+    ModelRc::new(Rc::new(VecModel::from(entries)))
+}
+
 impl EventLoop {
     pub fn handle_temperature_devices_update(
         &self,
@@ -17,15 +29,55 @@ impl EventLoop {
             
             self.ui_weak
                 .upgrade_in_event_loop(Box::new(move |ui: AppWindow| {
-                    ui.global::<TemperatureSensors>().set_extruder(Heater {
-                        name: SharedString::from("extruder"),
-                        target: extruder_event.target as i32,
-                        temperature: extruder_event.temperature as i32,
-                        presets: ModelRc::new(Rc::new(VecModel::from(extruder_event.configuration.presets.iter().map(|f| *f as i32).collect::<Vec<i32>>()))),
-                    })
+                    let heater = Heater {
+                        name: SharedString::from(&extruder_event.name),
+                        target: extruder_event.extruder.target as i32,
+                        temperature: extruder_event.extruder.temperature as i32,
+                        presets: ModelRc::new(Rc::new(VecModel::from(extruder_event.extruder.configuration.presets.iter().map(|f| *f as i32).collect::<Vec<i32>>()))),
+                    };
+
+                    if heater.name == ui.global::<TemperatureSensors>().get_active_extruder() {
+                        ui.global::<TemperatureSensors>().set_extruder(heater.clone());
+                    }
+
+                    let extruders = upsert_heater(ui.global::<TemperatureSensors>().get_extruders(), heater);
+                    ui.global::<TemperatureSensors>().set_extruders(extruders);
                 }))?;
         }
 
+        if let PrinterEvent::Toolhead(toolhead_event) = printer_event && !toolhead_event.extruder.is_empty() {
+            let active_extruder = SharedString::from(&toolhead_event.extruder);
+
+            self.ui_weak.upgrade_in_event_loop(move |ui: AppWindow| {
+                if ui.global::<TemperatureSensors>().get_active_extruder() == active_extruder {
+                    return;
+                }
+
+                let extruders = ui.global::<TemperatureSensors>().get_extruders();
+                if let Some(heater) = extruders.iter().find(|heater| heater.name == active_extruder) {
+                    ui.global::<TemperatureSensors>().set_extruder(heater);
+                }
+
+                ui.global::<TemperatureSensors>().set_active_extruder(active_extruder);
+            })?;
+        }
+
+        if let PrinterEvent::HeaterGeneric(heater_event) = printer_event {
+            let heater_event = heater_event.clone();
+
+            self.ui_weak.upgrade_in_event_loop(move |ui: AppWindow| {
+                let heater = Heater {
+                    name: SharedString::from(&heater_event.name),
+                    target: heater_event.heater.target as i32,
+                    temperature: heater_event.heater.temperature as i32,
+                    presets: ModelRc::new(Rc::new(VecModel::from(heater_event.heater.configuration.presets.iter().map(|f| *f as i32).collect::<Vec<i32>>()))),
+                };
+
+                let heater_generics = upsert_heater(ui.global::<TemperatureSensors>().get_heater_generics(), heater);
+                ui.global::<TemperatureSensors>().set_heater_generics(heater_generics);
+            })?;
+        }
+
         if let PrinterEvent::HeaterBed(heater_bed_event) = printer_event {
             let heater_bed_event = heater_bed_event.clone();
 
@@ -1,17 +1,23 @@
 use crate::{
     application_error::ApplicationError,
     config::{DisplayConfig, DisplayInit},
+    hardware::{ScreensaverControl, ScreenshotControl, TouchCalibrationControl},
     AppWindow,
 };
 
-pub fn init_display(config: &DisplayConfig) -> Result<AppWindow, ApplicationError> {
+pub fn init_display(config: &DisplayConfig, screensaver: &ScreensaverControl, touch_calibration: &TouchCalibrationControl, screenshots: &ScreenshotControl) -> Result<AppWindow, ApplicationError> {
     if let Some(default_config) = &config.default {
-        return default_config.init();
+        return default_config.init(screensaver, touch_calibration, screenshots);
     }
 
     #[cfg(unix)]
     if let Some(fb_config) = &config.framebuffer {
-        return fb_config.init();
+        return fb_config.init(screensaver, touch_calibration, screenshots);
+    }
+
+    #[cfg(unix)]
+    if let Some(headless_config) = &config.headless {
+        return headless_config.init(screensaver, touch_calibration, screenshots);
     }
 
     Err(ApplicationError::Unknown(String::from(
@@ -1,2 +1,8 @@
 pub mod init_display;
+pub mod screensaver;
+pub mod screenshots;
+pub mod touch_calibration;
 pub use init_display::*;
+pub use screensaver::*;
+pub use screenshots::*;
+pub use touch_calibration::*;
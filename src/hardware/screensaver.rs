@@ -0,0 +1,62 @@
+use moonraker_rs::printer_objects::PrintState;
+
+use crate::config::DisplayConfig;
+
+/// App side of the framebuffer screensaver, applying the print state rules.
+/// Does nothing on other display backends.
+#[derive(Clone, Default)]
+pub struct ScreensaverControl {
+    #[cfg(unix)]
+    handle: Option<driver::ScreensaverHandle>,
+    never_sleep_while_printing: bool,
+    wake_on_print_state_change: bool,
+}
+
+impl ScreensaverControl {
+    pub fn from_config(config: &DisplayConfig) -> ScreensaverControl {
+        #[cfg(unix)]
+        if config.default.is_none()
+            && let Some(fb_config) = &config.framebuffer
+            && let Some(screensaver_config) = &fb_config.screensaver
+        {
+            let options = driver::ScreensaverOptions {
+                dim_timeout: screensaver_config.dim_timeout.map(std::time::Duration::from_secs),
+                off_timeout: screensaver_config.off_timeout.map(std::time::Duration::from_secs),
+                dim_brightness: screensaver_config.dim_brightness.unwrap_or(20).min(100),
+                backlight_path: screensaver_config.backlight_path.clone(),
+            };
+
+            return ScreensaverControl {
+                handle: Some(driver::Screensaver::new(&fb_config.fb_path, options)),
+                never_sleep_while_printing: screensaver_config.never_sleep_while_printing,
+                wake_on_print_state_change: screensaver_config.wake_on_print_state_change,
+            };
+        }
+
+        let _ = config;
+        ScreensaverControl::default()
+    }
+
+    #[cfg(unix)]
+    pub fn handle(&self) -> Option<driver::ScreensaverHandle> {
+        self.handle.clone()
+    }
+
+    pub fn on_print_state(&self, last_state: &PrintState, state: &PrintState) {
+        #[cfg(unix)]
+        if let Some(handle) = &self.handle {
+            let mut screensaver = handle.lock().unwrap();
+
+            if self.never_sleep_while_printing {
+                screensaver.set_inhibited(*state == PrintState::Printing);
+            }
+
+            if self.wake_on_print_state_change && last_state != state {
+                screensaver.wake();
+            }
+        }
+
+        #[cfg(not(unix))]
+        let _ = (last_state, state);
+    }
+}
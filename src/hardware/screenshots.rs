@@ -0,0 +1,29 @@
+use crate::config::DisplayConfig;
+
+/// App side of screenshots asked for over the control socket. The headless
+/// backend takes them from its own buffer after the next frame, the other
+/// backends snapshot the window.
+#[derive(Clone, Default)]
+pub struct ScreenshotControl {
+    #[cfg(unix)]
+    requests: Option<driver::ScreenshotRequests>,
+}
+
+impl ScreenshotControl {
+    pub fn from_config(config: &DisplayConfig) -> ScreenshotControl {
+        #[cfg(unix)]
+        if config.default.is_none() && config.framebuffer.is_none() && config.headless.is_some() {
+            return ScreenshotControl {
+                requests: Some(driver::ScreenshotRequests::default()),
+            };
+        }
+
+        let _ = config;
+        ScreenshotControl::default()
+    }
+
+    #[cfg(unix)]
+    pub fn requests(&self) -> Option<driver::ScreenshotRequests> {
+        self.requests.clone()
+    }
+}
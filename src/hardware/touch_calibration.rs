@@ -0,0 +1,114 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    application_error::ApplicationError,
+    config::{DisplayConfig, TouchCalibrationFile},
+};
+
+#[cfg(unix)]
+use driver::compute_calibration_matrix;
+
+#[cfg(unix)]
+pub use driver::CalibrationSample;
+
+/// A `(touched, target)` pair in window coordinates.
+#[cfg(not(unix))]
+pub type CalibrationSample = ((f32, f32), (f32, f32));
+
+#[cfg(not(unix))]
+fn compute_calibration_matrix(_samples: &[CalibrationSample]) -> Option<[f32; 6]> {
+    None
+}
+
+/// App side of the touch calibration matrix. Only available on the framebuffer
+/// backend with an input device configured.
+#[derive(Clone, Default)]
+pub struct TouchCalibrationControl {
+    #[cfg(unix)]
+    handle: Option<driver::TouchCalibrationHandle>,
+    save_path: Option<PathBuf>,
+}
+
+impl TouchCalibrationControl {
+    pub fn from_config(config: &DisplayConfig, config_path: &Path) -> TouchCalibrationControl {
+        #[cfg(unix)]
+        if config.default.is_none()
+            && let Some(fb_config) = &config.framebuffer
+            && (fb_config.event_path.is_some() || fb_config.inputs.as_ref().is_none_or(|inputs| inputs.iter().any(|input| input.may_be_touch())))
+        {
+            let touch_config = fb_config.touch.clone().unwrap_or_default();
+            let save_path = config_path
+                .parent()
+                .unwrap_or(Path::new("."))
+                .join(touch_config.calibration_path.as_deref().unwrap_or("touch_calibration.toml"));
+
+            // A saved calibration takes precedence over the one in the config
+            let matrix = match fs::read_to_string(&save_path) {
+                Ok(contents) => match toml::from_str::<TouchCalibrationFile>(&contents) {
+                    Ok(file) => Some(file.matrix),
+                    Err(e) => {
+                        eprintln!("Ignoring invalid touch calibration {}: {}", save_path.display(), e);
+                        touch_config.calibration_matrix
+                    }
+                },
+                Err(_) => touch_config.calibration_matrix,
+            };
+
+            return TouchCalibrationControl {
+                handle: Some(std::sync::Arc::new(std::sync::Mutex::new(matrix))),
+                save_path: Some(save_path),
+            };
+        }
+
+        let _ = (config, config_path);
+        TouchCalibrationControl::default()
+    }
+
+    #[cfg(unix)]
+    pub fn handle(&self) -> Option<driver::TouchCalibrationHandle> {
+        self.handle.clone()
+    }
+
+    pub fn is_available(&self) -> bool {
+        self.save_path.is_some()
+    }
+
+    pub fn matrix(&self) -> Option<[f32; 6]> {
+        #[cfg(unix)]
+        if let Some(handle) = &self.handle {
+            return *handle.lock().unwrap();
+        }
+
+        None
+    }
+
+    pub fn set_matrix(&self, matrix: Option<[f32; 6]>) {
+        #[cfg(unix)]
+        if let Some(handle) = &self.handle {
+            *handle.lock().unwrap() = matrix;
+        }
+
+        #[cfg(not(unix))]
+        let _ = matrix;
+    }
+
+    /// Fits a matrix to the samples, `None` if they are all on a line.
+    pub fn compute_matrix(&self, samples: &[CalibrationSample]) -> Option<[f32; 6]> {
+        compute_calibration_matrix(samples)
+    }
+
+    pub fn save(&self, matrix: [f32; 6]) -> Result<(), ApplicationError> {
+        let Some(save_path) = &self.save_path else {
+            return Err(ApplicationError::Unknown(String::from("Touch calibration is not available")));
+        };
+
+        let contents = toml::to_string(&TouchCalibrationFile { matrix })
+            .map_err(|e| ApplicationError::Unknown(e.to_string()))?;
+        fs::write(save_path, contents)?;
+
+        Ok(())
+    }
+}
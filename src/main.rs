@@ -1,17 +1,18 @@
 // Prevent console window in addition to Slint window in Windows release builds when, e.g., starting the app via file manager. Ignored on other platforms.
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
-use std::{error::Error, fs, path::PathBuf, process::exit, sync::Arc};
+use std::{error::Error, fs, path::{Path, PathBuf}, process::exit, sync::Arc};
 
 use clap::Parser;
-use moonraker_rs::{
-    cache::Cache, connector::{read_deserialize::OptionalPrinterEvent}, printer_objects::{NamedOptionalTemperatureFan, OptionalExtruder, OptionalHeaterBed, OptionalTemperatureFan, TemperatureConfiguration}, 
-};
+use moonraker_rs::{cache::Cache, connector::{recording::{Recorder, Recording}, replay::ReplayTransport}, printer_objects::TemperatureConfiguration};
 
-use crate::{config::{MoonrakerConfig, OptionalGcodeCommands, OptionalUiConfig}, event_loop::EventLoop, hardware::init_display, ui_functions::*};
+use crate::{config::{MoonrakerConfig, OptionalGcodeCommands, OptionalUiConfig, UiConfig}, event_loop::EventLoop, hardware::{init_display, ScreensaverControl, ScreenshotControl, TouchCalibrationControl}, ui_functions::*};
 
 mod application_error;
 mod config;
+#[cfg(unix)]
+mod control;
+mod estimator;
 mod hardware;
 mod event_loop;
 mod ui_functions;
@@ -35,39 +36,36 @@ async fn main() -> Result<(), Box<dyn Error>> {
 
     if let Some(heater_presets) = config.heater_presets {
         for (name, preset) in heater_presets {
-            if name == "extruder" {
-                cache.complete_event(OptionalPrinterEvent::Extruder(OptionalExtruder {
-                    configuration: Some(TemperatureConfiguration::from(preset)),
-                    ..Default::default()
-                }));
-            } else if name == "heater_bed" {
-                cache.complete_event(OptionalPrinterEvent::HeaterBed(OptionalHeaterBed {
-                    configuration: Some(TemperatureConfiguration::from(preset)),
-                    ..Default::default()
-                }));
-            } else {
-                cache.complete_event(OptionalPrinterEvent::TemperatureFan(NamedOptionalTemperatureFan {
-                    name: name,
-                    fan: OptionalTemperatureFan {
-                        configuration: Some(TemperatureConfiguration::from(preset)),
-                        ..Default::default()
-                    }
-                }));
-            }
+            cache.set_heater_presets(&name, TemperatureConfiguration::from(preset));
         }
     }
 
-    let moonraker_connection = Arc::new(
-        moonraker_rs::moonraker_connection::MoonrakerConnection::new(
-            &moonraker_config.host,
-            moonraker_config.port,
-            Some(cache),
-        ),
+    let mut moonraker_connection = moonraker_rs::moonraker_connection::MoonrakerConnection::new(
+        &moonraker_config.host,
+        moonraker_config.port,
+        Some(cache),
     );
-    let ui = init_display(&config.display)?;
+
+    if let Some(record_path) = &args.record {
+        moonraker_connection.set_recorder(Recorder::create(Path::new(record_path))?);
+    }
+
+    if let Some(replay_path) = &args.replay {
+        let recording = Recording::load(Path::new(replay_path))?;
+        moonraker_connection.set_replay(ReplayTransport::new(recording, args.replay_speed));
+    }
+
+    let moonraker_connection = Arc::new(moonraker_connection);
+    let screensaver = ScreensaverControl::from_config(&config.display);
+    let touch_calibration = TouchCalibrationControl::from_config(&config.display, &config_path);
+    let screenshots = ScreenshotControl::from_config(&config.display);
+    let ui = init_display(&config.display, &screensaver, &touch_calibration, &screenshots)?;
     ui.global::<Webhooks>().set_moonraker_connected(false);
     let ui_weak = ui.as_weak();
-    let mut event_loop = EventLoop::new(ui_weak.clone(), moonraker_connection.clone());
+    let ui_settings = &config.ui.unwrap_or(OptionalUiConfig::default());
+    let estimator = UiConfig::from_optional(ui_settings).eta_estimator.create();
+    let material_presets = config.material_presets.unwrap_or_default();
+    let mut event_loop = EventLoop::new(ui_weak.clone(), moonraker_connection.clone(), estimator, material_presets.clone(), screensaver);
 
     {
         let moonraker_connection = moonraker_connection.clone();
@@ -83,8 +81,12 @@ async fn main() -> Result<(), Box<dyn Error>> {
     register_filesystem_list_files(&ui, &moonraker_connection);
     register_filesystem_fetch_metadata(&ui, &moonraker_connection);
     register_filesystem_load_high_res_thumbnail(&ui, &moonraker_connection);
+    register_filesystem_prepare_print(&ui, &moonraker_connection);
+    register_filesystem_confirm_print(&ui, &moonraker_connection);
 
     register_temperature_set_new_target_temperature(&ui, &moonraker_connection);
+    register_temperature_material_presets(&ui, &moonraker_connection, &material_presets);
+    register_temperature_cool_down(&ui, &moonraker_connection);
 
     register_util_format_bytes(&ui);
     register_util_prettify_name(&ui);
@@ -101,9 +103,11 @@ async fn main() -> Result<(), Box<dyn Error>> {
     register_extruder_retract(&ui, &moonraker_connection, gcode_command_config);
     register_extruder_load_filament(&ui, &moonraker_connection, gcode_command_config);
     register_extruder_unload_filament(&ui, &moonraker_connection, gcode_command_config);
+    register_extruder_filament_wizard(&ui, &moonraker_connection, gcode_command_config, &material_presets);
 
-    let ui_settings = &config.ui.unwrap_or(OptionalUiConfig::default());
     register_set_ui_settings(&ui, &ui_settings);
+    register_settings_touch_calibration(&ui, &touch_calibration);
+    register_navigation_swipe(&ui, &ui_settings);
 
     register_execute_quick_action(&ui, &config.quick_actions, &moonraker_connection);
 
@@ -115,9 +119,24 @@ async fn main() -> Result<(), Box<dyn Error>> {
     register_printjob_pause(&ui, &moonraker_connection);
     register_printjob_stop(&ui, &moonraker_connection);
 
-    tokio::task::block_in_place(|| {
-        ui.run().unwrap();
-    });
+    register_history_fetch_jobs(&ui, &moonraker_connection);
+    register_history_delete_job(&ui, &moonraker_connection);
+    register_history_reprint_job(&ui, &moonraker_connection);
+    register_history_reset_totals(&ui, &moonraker_connection);
+
+    register_job_queue_fetch(&ui, &moonraker_connection);
+    register_job_queue_enqueue_file(&ui, &moonraker_connection);
+    register_job_queue_remove_job(&ui, &moonraker_connection);
+    register_job_queue_move_job(&ui, &moonraker_connection);
+    register_job_queue_pause(&ui, &moonraker_connection);
+    register_job_queue_start(&ui, &moonraker_connection);
+
+    #[cfg(unix)]
+    if let Some(control_config) = &config.control {
+        control::start_control_socket(control_config, ui.as_weak(), screenshots)?;
+    }
+
+    tokio::task::block_in_place(|| ui.run())?;
 
     Ok(())
 }
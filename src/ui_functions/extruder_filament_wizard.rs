@@ -0,0 +1,155 @@
+use std::{collections::BTreeMap, sync::Arc};
+
+use moonraker_rs::{moonraker_connection::MoonrakerConnection, requests::PrinterAdministrationRequestHandler};
+use slint::{ComponentHandle, SharedString, Weak};
+use tokio::task::JoinHandle;
+
+use crate::{config::{GcodeCommands as GcodeCommandsConfig, MaterialPreset, OptionalGcodeCommands}, ui_functions::{heater_temperature_command, wait_for_temperatures}, AppWindow, FilamentWizard, TemperatureSensors};
+
+struct FilamentWizardCommands
+{
+    load : String,
+    unload : String,
+    purge : String,
+}
+
+fn set_wizard_step(ui_weak : &Weak<AppWindow>, step : &str, message : String)
+{
+    let step = SharedString::from(step);
+    let message = SharedString::from(message);
+
+    let _ = ui_weak.upgrade_in_event_loop(move |ui| {
+        ui.global::<FilamentWizard>().set_step(step);
+        ui.global::<FilamentWizard>().set_message(message);
+    });
+}
+
+/// Heats the extruder, waits until it is allowed to extrude and then runs the load or unload gcode.
+async fn run_filament_wizard(moonraker_connection : Arc<MoonrakerConnection>, ui_weak : Weak<AppWindow>, extruder_name : String, target : i32, loading : bool, command : String)
+{
+    set_wizard_step(&ui_weak, "heating", format!("Heating extruder to {}°C...", target));
+
+    if let Err(e) = moonraker_connection.run_gcode_script(&heater_temperature_command(&extruder_name, target)).await
+    {
+        set_wizard_step(&ui_weak, "failed", format!("Failed to heat the extruder: {}", e));
+        return;
+    }
+
+    if let Err(e) = wait_for_temperatures(&moonraker_connection, &extruder_name, target as f32, 0.0).await
+    {
+        set_wizard_step(&ui_weak, "failed", format!("Failed to heat the extruder: {}", e));
+        return;
+    }
+
+    set_wizard_step(&ui_weak, "running", if loading { "Loading filament...".into() } else { "Unloading filament...".into() });
+
+    if let Err(e) = moonraker_connection.run_gcode_script(&command).await
+    {
+        set_wizard_step(&ui_weak, "failed", format!("Failed to run '{}': {}", command, e));
+        return;
+    }
+
+    if loading
+    {
+        set_wizard_step(&ui_weak, "purge", "Filament loaded. Purge until the new color comes out cleanly.".into());
+    }
+    else
+    {
+        set_wizard_step(&ui_weak, "done", "Filament unloaded.".into());
+    }
+}
+
+pub fn register_extruder_filament_wizard(ui : &AppWindow, moonraker_connection : &Arc<MoonrakerConnection>, gcode_command_config : &OptionalGcodeCommands, presets : &BTreeMap<String, MaterialPreset>)
+{
+    let commands = Arc::new(FilamentWizardCommands {
+        load: gcode_command_config.extruder_load_filament.clone().unwrap_or(GcodeCommandsConfig::default().extruder_load_filament),
+        unload: gcode_command_config.extruder_unload_filament.clone().unwrap_or(GcodeCommandsConfig::default().extruder_unload_filament),
+        purge: gcode_command_config.extruder_extrude.clone().unwrap_or(GcodeCommandsConfig::default().extruder_extrude),
+    });
+    let wizard_task: Arc<std::sync::Mutex<Option<JoinHandle<()>>>> = Arc::new(std::sync::Mutex::new(None));
+
+    let start_wizard = {
+        let moonraker_connection = moonraker_connection.clone();
+        let ui_weak = ui.as_weak();
+        let commands = commands.clone();
+        let wizard_task = wizard_task.clone();
+
+        Arc::new(move |target : i32, loading : bool| {
+            let command = if loading { commands.load.clone() } else { commands.unload.clone() };
+            let extruder_name = ui_weak.upgrade().unwrap().global::<TemperatureSensors>().get_active_extruder().to_string();
+            let task = tokio::spawn(run_filament_wizard(moonraker_connection.clone(), ui_weak.clone(), extruder_name, target, loading, command));
+
+            if let Some(previous_task) = wizard_task.lock().unwrap().replace(task)
+            {
+                previous_task.abort();
+            }
+        })
+    };
+
+    {
+        let start_wizard = start_wizard.clone();
+        let presets = presets.clone();
+        let ui_weak = ui.as_weak();
+
+        ui.global::<FilamentWizard>().on_select_preset(move |name| {
+            let ui = ui_weak.upgrade().unwrap();
+            let loading = ui.global::<FilamentWizard>().get_loading();
+
+            match presets.get(name.as_str()).and_then(|preset| preset.temperatures.get("extruder"))
+            {
+                Some(target) if *target > 0 => start_wizard(*target as i32, loading),
+                _ => set_wizard_step(&ui_weak, "failed", format!("Material preset {} has no extruder temperature", name)),
+            }
+        });
+    }
+
+    {
+        let ui_weak = ui.as_weak();
+
+        ui.global::<FilamentWizard>().on_select_temperature(move |target| {
+            let loading = ui_weak.upgrade().unwrap().global::<FilamentWizard>().get_loading();
+            start_wizard(target, loading);
+        });
+    }
+
+    {
+        let moonraker_connection = moonraker_connection.clone();
+        let ui_weak = ui.as_weak();
+        let wizard_task = wizard_task.clone();
+
+        ui.global::<FilamentWizard>().on_purge_more(move || {
+            let moonraker_connection = moonraker_connection.clone();
+            let ui_weak = ui_weak.clone();
+            let command = commands.purge.clone();
+
+            set_wizard_step(&ui_weak, "running", "Purging...".into());
+
+            let task = tokio::spawn(async move {
+                if let Err(e) = moonraker_connection.run_gcode_script(&command).await
+                {
+                    set_wizard_step(&ui_weak, "failed", format!("Failed to purge: {}", e));
+                    return;
+                }
+
+                set_wizard_step(&ui_weak, "purge", "Purge until the new color comes out cleanly.".into());
+            });
+
+            if let Some(previous_task) = wizard_task.lock().unwrap().replace(task)
+            {
+                previous_task.abort();
+            }
+        });
+    }
+
+    let ui_weak = ui.as_weak();
+
+    // Aborting only stops waiting, a gcode script that was already sent keeps running in Klipper
+    ui.global::<FilamentWizard>().on_close(move || {
+        if let Some(task) = wizard_task.lock().unwrap().take()
+        {
+            task.abort();
+        }
+
+        set_wizard_step(&ui_weak, "", String::new());
+    });
+}
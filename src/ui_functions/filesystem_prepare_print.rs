@@ -0,0 +1,168 @@
+use std::sync::Arc;
+
+use moonraker_rs::{moonraker_connection::MoonrakerConnection, requests::{FileManagementRequestHandler, PrinterAdministrationRequestHandler}};
+use slint::{ComponentHandle, ModelRc, SharedString, VecModel};
+use tokio::task::JoinHandle;
+
+use crate::{ui_functions::{heater_temperature_command, wait_for_temperatures}, AppWindow, Filesystem, PrintConfirmation, PrinterInfo, TemperatureSensors};
+
+pub fn register_filesystem_prepare_print(ui : &AppWindow, moonraker_connection : &Arc<MoonrakerConnection>)
+{
+    let moonraker_connection = moonraker_connection.clone();
+    let ui_weak = ui.as_weak();
+
+    ui.global::<Filesystem>().on_prepare_print(move |file_path| {
+        let moonraker_connection = moonraker_connection.clone();
+        let ui_weak = ui_weak.clone();
+
+        slint::spawn_local(async move {
+            let metadata = match moonraker_connection.get_gcode_metadata_for_file(&file_path).await
+            {
+                Ok(m) => m,
+                Err(e) => {
+                    moonraker_connection.send_request_error(format!("Failed to fetch metadata for {}: {}", file_path, e));
+                    return;
+                }
+            };
+
+            let ui = ui_weak.upgrade().unwrap();
+            let printer_info = ui.global::<PrinterInfo>();
+            let max_z = printer_info.get_max_z();
+            let nozzle_diameter = printer_info.get_nozzle_diameter();
+            let extruder_max_temp = printer_info.get_extruder_max_temp();
+            let heater_bed_max_temp = printer_info.get_heater_bed_max_temp();
+
+            let mut warnings = Vec::new();
+
+            if nozzle_diameter > 0.0 && metadata.nozzle_diameter > 0.0 && (nozzle_diameter - metadata.nozzle_diameter).abs() > 0.01
+            {
+                warnings.push(format!("Sliced for a {}mm nozzle, but the printer has a {}mm nozzle", metadata.nozzle_diameter, nozzle_diameter));
+            }
+
+            if max_z > 0.0 && metadata.object_height > max_z
+            {
+                warnings.push(format!("Object is {}mm tall, but the printer can only reach {}mm", metadata.object_height, max_z));
+            }
+
+            if extruder_max_temp > 0.0 && metadata.first_layer_extr_temp > extruder_max_temp
+            {
+                warnings.push(format!("Extruder temperature {}°C exceeds the maximum of {}°C", metadata.first_layer_extr_temp, extruder_max_temp));
+            }
+
+            if heater_bed_max_temp > 0.0 && metadata.first_layer_bed_temp > heater_bed_max_temp
+            {
+                warnings.push(format!("Bed temperature {}°C exceeds the maximum of {}°C", metadata.first_layer_bed_temp, heater_bed_max_temp));
+            }
+
+            let warnings: Vec<SharedString> = warnings.into_iter().map(SharedString::from).collect();
+
+            ui.global::<Filesystem>().set_print_confirmation(PrintConfirmation {
+                filename: file_path,
+                slicer: SharedString::from(&metadata.slicer),
+                estimated_time_s: metadata.estimated_time,
+                filament_type: SharedString::from(&metadata.filament_type),
+                filament_weight_g: metadata.filament_weight_total,
+                nozzle_diameter_mm: metadata.nozzle_diameter,
+                first_layer_extr_temp: metadata.first_layer_extr_temp.round() as i32,
+                first_layer_bed_temp: metadata.first_layer_bed_temp.round() as i32,
+                object_height_mm: metadata.object_height,
+                max_height_mm: max_z,
+                warnings: ModelRc::new(VecModel::from(warnings)),
+                preheating: false,
+            });
+        })
+        .unwrap();
+    });
+}
+
+/// Turns "Preheating..." back into "Start", so the print can be retried after an error.
+fn stop_preheating(ui_weak: &slint::Weak<AppWindow>)
+{
+    let _ = ui_weak.upgrade_in_event_loop(|ui| {
+        let filesystem = ui.global::<Filesystem>();
+        let mut confirmation = filesystem.get_print_confirmation();
+        confirmation.preheating = false;
+        filesystem.set_print_confirmation(confirmation);
+    });
+}
+
+/// Registers both `confirm_print` and `cancel_print_confirmation`, as cancelling has to abort a running preheat.
+/// Cancelling leaves the heater targets as they are.
+pub fn register_filesystem_confirm_print(ui : &AppWindow, moonraker_connection : &Arc<MoonrakerConnection>)
+{
+    let preheat_task: Arc<std::sync::Mutex<Option<JoinHandle<()>>>> = Arc::new(std::sync::Mutex::new(None));
+
+    {
+        let moonraker_connection = moonraker_connection.clone();
+        let ui_weak = ui.as_weak();
+        let preheat_task = preheat_task.clone();
+
+        ui.global::<Filesystem>().on_confirm_print(move |file_path, preheat| {
+            let moonraker_connection = moonraker_connection.clone();
+            let ui_weak = ui_weak.clone();
+            let ui = ui_weak.upgrade().unwrap();
+            let mut confirmation = ui.global::<Filesystem>().get_print_confirmation();
+            let extruder_target = if preheat { confirmation.first_layer_extr_temp as f32 } else { 0.0 };
+            let heater_bed_target = if preheat { confirmation.first_layer_bed_temp as f32 } else { 0.0 };
+            let extruder_name = ui.global::<TemperatureSensors>().get_active_extruder().to_string();
+
+            if preheat
+            {
+                confirmation.preheating = true;
+                ui.global::<Filesystem>().set_print_confirmation(confirmation);
+            }
+
+            let task = tokio::spawn(async move {
+                for (heater, target) in [(extruder_name.as_str(), extruder_target), ("heater_bed", heater_bed_target)]
+                {
+                    if target <= 0.0
+                    {
+                        continue;
+                    }
+
+                    let command = heater_temperature_command(heater, target as i32);
+                    if let Err(e) = moonraker_connection.run_gcode_script(&command).await
+                    {
+                        moonraker_connection.send_request_error(format!("Failed to preheat {}: {}", heater, e));
+                        stop_preheating(&ui_weak);
+                        return;
+                    }
+                }
+
+                if let Err(e) = wait_for_temperatures(&moonraker_connection, &extruder_name, extruder_target, heater_bed_target).await
+                {
+                    moonraker_connection.send_request_error(format!("Failed to preheat for {}: {}", file_path, e));
+                    stop_preheating(&ui_weak);
+                    return;
+                }
+
+                if let Err(e) = moonraker_connection.start_print(&file_path).await
+                {
+                    moonraker_connection.send_request_error(format!("Failed to start print {}: {}", file_path, e));
+                    stop_preheating(&ui_weak);
+                    return;
+                }
+
+                let _ = ui_weak.upgrade_in_event_loop(|ui| {
+                    ui.global::<Filesystem>().set_print_confirmation(PrintConfirmation::default());
+                });
+            });
+
+            if let Some(previous_task) = preheat_task.lock().unwrap().replace(task)
+            {
+                previous_task.abort();
+            }
+        });
+    }
+
+    let ui_weak = ui.as_weak();
+
+    ui.global::<Filesystem>().on_cancel_print_confirmation(move || {
+        if let Some(task) = preheat_task.lock().unwrap().take()
+        {
+            task.abort();
+        }
+
+        ui_weak.upgrade().unwrap().global::<Filesystem>().set_print_confirmation(PrintConfirmation::default());
+    });
+}
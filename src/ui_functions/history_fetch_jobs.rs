@@ -0,0 +1,82 @@
+use std::sync::Arc;
+
+use chrono::{Local, TimeZone};
+use moonraker_rs::{moonraker_connection::MoonrakerConnection, requests::HistoryRequestHandler};
+use slint::{ComponentHandle, Model, ModelRc, SharedString, VecModel};
+
+use crate::{AppWindow, History, HistoryJob, HistoryTotals};
+
+const HISTORY_PAGE_SIZE: u32 = 50;
+
+pub fn format_unix_timestamp(timestamp: f64, format: &str) -> String
+{
+    match Local.timestamp_opt(timestamp as i64, 0).single() {
+        Some(time) => time.format(format).to_string(),
+        None => String::new(),
+    }
+}
+
+pub fn register_history_fetch_jobs(ui : &AppWindow, moonraker_connection : &Arc<MoonrakerConnection>)
+{
+    let moonraker_connection = moonraker_connection.clone();
+    let ui_weak = ui.as_weak();
+
+    ui.global::<History>().on_fetch_history(move || {
+        let moonraker_connection = moonraker_connection.clone();
+        let ui_weak = ui_weak.clone();
+
+        // Only show the spinner on the first load, refreshes happen in the background
+        let ui = ui_weak.upgrade().unwrap();
+        if ui.global::<History>().get_jobs().row_count() <= 0 {
+            ui.global::<History>().set_loading(true);
+        }
+
+        slint::spawn_local(async move {
+            let history = match moonraker_connection.list_history(HISTORY_PAGE_SIZE, 0).await
+            {
+                Ok(h) => h,
+                Err(e) => {
+                    moonraker_connection.send_request_error(format!("Failed to fetch print history: {}", e));
+                    ui_weak.upgrade().unwrap().global::<History>().set_loading(false);
+                    return;
+                }
+            };
+
+            let totals = match moonraker_connection.get_history_totals().await
+            {
+                Ok(t) => t,
+                Err(e) => {
+                    moonraker_connection.send_request_error(format!("Failed to fetch print history totals: {}", e));
+                    ui_weak.upgrade().unwrap().global::<History>().set_loading(false);
+                    return;
+                }
+            };
+
+            let ui = ui_weak.upgrade().unwrap();
+
+            let jobs: Vec<HistoryJob> = history.jobs.iter().map(|job| {
+                HistoryJob {
+                    job_id: SharedString::from(&job.job_id),
+                    filename: SharedString::from(&job.filename),
+                    status: SharedString::from(job.status.to_string()),
+                    exists: job.exists,
+                    start_time: SharedString::from(format_unix_timestamp(job.start_time, "%Y-%m-%d %H:%M")),
+                    print_duration: job.print_duration,
+                    total_duration: job.total_duration,
+                    filament_used_mm: job.filament_used,
+                }
+            }).collect();
+
+            ui.global::<History>().set_jobs(ModelRc::new(VecModel::from(jobs)));
+            ui.global::<History>().set_totals(HistoryTotals {
+                total_jobs: totals.total_jobs as i32,
+                total_time: totals.total_time,
+                total_print_time: totals.total_print_time,
+                total_filament_used_mm: totals.total_filament_used,
+                longest_job: totals.longest_job,
+                longest_print: totals.longest_print,
+            });
+            ui.global::<History>().set_loading(false);
+        }).unwrap();
+    });
+}
\ No newline at end of file
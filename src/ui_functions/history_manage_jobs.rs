@@ -0,0 +1,81 @@
+use std::sync::Arc;
+
+use moonraker_rs::{moonraker_connection::MoonrakerConnection, requests::{HistoryRequestHandler, PrinterAdministrationRequestHandler}};
+use slint::ComponentHandle;
+
+use crate::{AppWindow, History};
+
+
+pub fn register_history_delete_job(ui : &AppWindow, moonraker_connection : &Arc<MoonrakerConnection>)
+{
+    let moonraker_connection = moonraker_connection.clone();
+    let ui_weak = ui.as_weak();
+
+    ui.global::<History>().on_delete_job(move |job_id| {
+        let moonraker_connection = moonraker_connection.clone();
+        let ui_weak = ui_weak.clone();
+
+        slint::spawn_local(async move {
+            if let Err(e) = moonraker_connection.delete_history_job(&job_id).await
+            {
+                moonraker_connection.send_request_error(format!("Failed to delete job {} from history: {}", job_id, e));
+                return;
+            }
+
+            ui_weak.upgrade().unwrap().global::<History>().invoke_fetch_history();
+        }).unwrap();
+    });
+}
+
+pub fn register_history_reprint_job(ui : &AppWindow, moonraker_connection : &Arc<MoonrakerConnection>)
+{
+    let moonraker_connection = moonraker_connection.clone();
+
+    ui.global::<History>().on_reprint_job(move |job_id| {
+        let moonraker_connection = moonraker_connection.clone();
+
+        tokio::spawn(async move {
+            let job = match moonraker_connection.get_history_job(&job_id).await
+            {
+                Ok(j) => j,
+                Err(e) => {
+                    moonraker_connection.send_request_error(format!("Failed to fetch job {} from history: {}", job_id, e));
+                    return;
+                }
+            };
+
+            // The history keeps jobs around after their file was deleted
+            if !job.exists
+            {
+                moonraker_connection.send_request_error(format!("Cannot reprint {}: the file no longer exists", job.filename));
+                return;
+            }
+
+            if let Err(e) = moonraker_connection.start_print(&job.filename).await
+            {
+                moonraker_connection.send_request_error(format!("Failed to reprint {}: {}", job.filename, e));
+            }
+        });
+    });
+}
+
+pub fn register_history_reset_totals(ui : &AppWindow, moonraker_connection : &Arc<MoonrakerConnection>)
+{
+    let moonraker_connection = moonraker_connection.clone();
+    let ui_weak = ui.as_weak();
+
+    ui.global::<History>().on_reset_totals(move || {
+        let moonraker_connection = moonraker_connection.clone();
+        let ui_weak = ui_weak.clone();
+
+        slint::spawn_local(async move {
+            if let Err(e) = moonraker_connection.reset_history_totals().await
+            {
+                moonraker_connection.send_request_error(format!("Failed to reset history totals: {}", e));
+                return;
+            }
+
+            ui_weak.upgrade().unwrap().global::<History>().invoke_fetch_history();
+        }).unwrap();
+    });
+}
\ No newline at end of file
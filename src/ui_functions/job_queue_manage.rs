@@ -0,0 +1,169 @@
+use std::sync::Arc;
+
+use moonraker_rs::{moonraker_connection::MoonrakerConnection, requests::{JobQueueRequestHandler, JobQueueStatus}};
+use slint::{ComponentHandle, ModelRc, SharedString, VecModel, Weak};
+
+use crate::{AppWindow, JobQueue, QueuedJob};
+
+pub fn convert_queued_jobs(jobs: &[moonraker_rs::requests::QueuedJob]) -> Vec<QueuedJob>
+{
+    jobs.iter().map(|job| {
+        QueuedJob {
+            job_id: SharedString::from(&job.job_id),
+            filename: SharedString::from(&job.filename),
+            time_in_queue: job.time_in_queue,
+        }
+    }).collect()
+}
+
+fn apply_job_queue_status(ui_weak: &Weak<AppWindow>, status: JobQueueStatus)
+{
+    let _ = ui_weak.upgrade_in_event_loop(move |ui| {
+        ui.global::<JobQueue>().set_jobs(ModelRc::new(VecModel::from(convert_queued_jobs(&status.queued_jobs))));
+        ui.global::<JobQueue>().set_queue_state(SharedString::from(status.queue_state.to_string()));
+    });
+}
+
+pub fn register_job_queue_fetch(ui : &AppWindow, moonraker_connection : &Arc<MoonrakerConnection>)
+{
+    let moonraker_connection = moonraker_connection.clone();
+    let ui_weak = ui.as_weak();
+
+    ui.global::<JobQueue>().on_fetch_queue(move || {
+        let moonraker_connection = moonraker_connection.clone();
+        let ui_weak = ui_weak.clone();
+
+        tokio::spawn(async move {
+            match moonraker_connection.get_job_queue_status().await
+            {
+                Ok(status) => apply_job_queue_status(&ui_weak, status),
+                Err(e) => moonraker_connection.send_request_error(format!("Failed to fetch job queue: {}", e)),
+            }
+        });
+    });
+}
+
+pub fn register_job_queue_enqueue_file(ui : &AppWindow, moonraker_connection : &Arc<MoonrakerConnection>)
+{
+    let moonraker_connection = moonraker_connection.clone();
+    let ui_weak = ui.as_weak();
+
+    ui.global::<JobQueue>().on_enqueue_file(move |file_path| {
+        let moonraker_connection = moonraker_connection.clone();
+        let ui_weak = ui_weak.clone();
+        let file_path = file_path.to_string();
+
+        tokio::spawn(async move {
+            match moonraker_connection.enqueue_jobs(&[file_path.clone()], false).await
+            {
+                Ok(status) => apply_job_queue_status(&ui_weak, status),
+                Err(e) => moonraker_connection.send_request_error(format!("Failed to add {} to the job queue: {}", file_path, e)),
+            }
+        });
+    });
+}
+
+pub fn register_job_queue_remove_job(ui : &AppWindow, moonraker_connection : &Arc<MoonrakerConnection>)
+{
+    let moonraker_connection = moonraker_connection.clone();
+    let ui_weak = ui.as_weak();
+
+    ui.global::<JobQueue>().on_remove_job(move |job_id| {
+        let moonraker_connection = moonraker_connection.clone();
+        let ui_weak = ui_weak.clone();
+        let job_id = job_id.to_string();
+
+        tokio::spawn(async move {
+            match moonraker_connection.remove_queued_jobs(&[job_id.clone()]).await
+            {
+                Ok(status) => apply_job_queue_status(&ui_weak, status),
+                Err(e) => moonraker_connection.send_request_error(format!("Failed to remove job {} from the job queue: {}", job_id, e)),
+            }
+        });
+    });
+}
+
+pub fn register_job_queue_move_job(ui : &AppWindow, moonraker_connection : &Arc<MoonrakerConnection>)
+{
+    let moonraker_connection = moonraker_connection.clone();
+    let ui_weak = ui.as_weak();
+
+    ui.global::<JobQueue>().on_move_job(move |job_id, offset| {
+        let moonraker_connection = moonraker_connection.clone();
+        let ui_weak = ui_weak.clone();
+        let job_id = job_id.to_string();
+
+        tokio::spawn(async move {
+            let status = match moonraker_connection.get_job_queue_status().await
+            {
+                Ok(s) => s,
+                Err(e) => {
+                    moonraker_connection.send_request_error(format!("Failed to fetch job queue: {}", e));
+                    return;
+                }
+            };
+
+            let mut jobs = status.queued_jobs;
+            let index = match jobs.iter().position(|job| job.job_id == job_id)
+            {
+                Some(i) => i,
+                None => return,
+            };
+
+            let new_index = (index as i32 + offset).clamp(0, jobs.len() as i32 - 1) as usize;
+            if new_index == index
+            {
+                return;
+            }
+
+            let job = jobs.remove(index);
+            jobs.insert(new_index, job);
+
+            // Moonraker has no way to reorder the queue, so re-submit the whole queue in the new order
+            let filenames: Vec<String> = jobs.into_iter().map(|job| job.filename).collect();
+            match moonraker_connection.enqueue_jobs(&filenames, true).await
+            {
+                Ok(status) => apply_job_queue_status(&ui_weak, status),
+                Err(e) => moonraker_connection.send_request_error(format!("Failed to reorder the job queue: {}", e)),
+            }
+        });
+    });
+}
+
+pub fn register_job_queue_pause(ui : &AppWindow, moonraker_connection : &Arc<MoonrakerConnection>)
+{
+    let moonraker_connection = moonraker_connection.clone();
+    let ui_weak = ui.as_weak();
+
+    ui.global::<JobQueue>().on_pause_queue(move || {
+        let moonraker_connection = moonraker_connection.clone();
+        let ui_weak = ui_weak.clone();
+
+        tokio::spawn(async move {
+            match moonraker_connection.pause_job_queue().await
+            {
+                Ok(status) => apply_job_queue_status(&ui_weak, status),
+                Err(e) => moonraker_connection.send_request_error(format!("Failed to pause the job queue: {}", e)),
+            }
+        });
+    });
+}
+
+pub fn register_job_queue_start(ui : &AppWindow, moonraker_connection : &Arc<MoonrakerConnection>)
+{
+    let moonraker_connection = moonraker_connection.clone();
+    let ui_weak = ui.as_weak();
+
+    ui.global::<JobQueue>().on_start_queue(move || {
+        let moonraker_connection = moonraker_connection.clone();
+        let ui_weak = ui_weak.clone();
+
+        tokio::spawn(async move {
+            match moonraker_connection.start_job_queue().await
+            {
+                Ok(status) => apply_job_queue_status(&ui_weak, status),
+                Err(e) => moonraker_connection.send_request_error(format!("Failed to start the job queue: {}", e)),
+            }
+        });
+    });
+}
\ No newline at end of file
@@ -15,6 +15,15 @@ pub mod misc_set_power_device;
 pub mod misc_fetch_power_devices;
 pub mod filesystem_load_high_res_thumbnail;
 pub mod printjob_resume_stop_pause;
+pub mod history_fetch_jobs;
+pub mod history_manage_jobs;
+pub mod job_queue_manage;
+pub mod filesystem_prepare_print;
+pub mod util_wait_for_temperatures;
+pub mod temperature_material_presets;
+pub mod extruder_filament_wizard;
+pub mod settings_touch_calibration;
+pub mod navigation_swipe;
 
 pub use util_format_bytes::*;
 pub use filesystem_fetch_metadata::*;
@@ -32,4 +41,13 @@ pub use util_time_in_seconds_to_string::*;
 pub use misc_set_power_device::*;
 pub use misc_fetch_power_devices::*;
 pub use filesystem_load_high_res_thumbnail::*;
-pub use printjob_resume_stop_pause::*;
\ No newline at end of file
+pub use printjob_resume_stop_pause::*;
+pub use history_fetch_jobs::*;
+pub use history_manage_jobs::*;
+pub use job_queue_manage::*;
+pub use filesystem_prepare_print::*;
+pub use util_wait_for_temperatures::*;
+pub use temperature_material_presets::*;
+pub use extruder_filament_wizard::*;
+pub use settings_touch_calibration::*;
+pub use navigation_swipe::*;
\ No newline at end of file
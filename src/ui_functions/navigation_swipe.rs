@@ -0,0 +1,34 @@
+use slint::ComponentHandle;
+
+use crate::{config::{OptionalUiConfig, UiConfig}, ui_functions::name_to_id, AppWindow, Navigation};
+
+/// Swiping in from the left or right edge steps through the sidebar pages in order.
+pub fn register_navigation_swipe(ui : &AppWindow, configuration : &OptionalUiConfig)
+{
+    let configuration = UiConfig::from_optional(configuration);
+
+    // The emergency stop opens a prompt rather than a page
+    let pages: Vec<i32> = configuration.left_sidebar.iter()
+        .chain(configuration.right_sidebar.iter())
+        .map(|f| name_to_id(f))
+        .filter(|id| *id != 3)
+        .collect();
+
+    let ui_weak = ui.as_weak();
+    ui.global::<Navigation>().on_swipe(move |edge| {
+        let ui = ui_weak.upgrade().unwrap();
+        let current_page = ui.global::<Navigation>().get_current_page();
+
+        let Some(index) = pages.iter().position(|page| *page == current_page) else {
+            return;
+        };
+
+        let next = match edge.as_str() {
+            "left" if index > 0 => pages[index - 1],
+            "right" if index + 1 < pages.len() => pages[index + 1],
+            _ => return,
+        };
+
+        ui.global::<Navigation>().set_current_page(next);
+    });
+}
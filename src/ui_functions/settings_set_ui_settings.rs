@@ -6,21 +6,28 @@ use slint::{ComponentHandle, ModelRc, VecModel};
 use crate::{config::{OptionalUiConfig, UiConfig}, AppWindow, PrinterAdministration, UiSettings};
 
 
-pub fn name_to_id(name : &str) -> i32
+pub fn try_name_to_id(name : &str) -> Option<i32>
 {
     match name {
-        "files" => 0,
-        "temperature" => 1,
-        "move" => 2,
-        "emergency_stop" => 3,
-        "fan" => 4,
-        "macros" => 5,
-        "console" => 6,
-        "settings" => 7,
-        _ => panic!("Unknown menu {} for left/right sidebar", name)
+        "files" => Some(0),
+        "temperature" => Some(1),
+        "move" => Some(2),
+        "emergency_stop" => Some(3),
+        "fan" => Some(4),
+        "macros" => Some(5),
+        "console" => Some(6),
+        "settings" => Some(7),
+        "history" => Some(8),
+        "job_queue" => Some(9),
+        _ => None
     }
 }
 
+pub fn name_to_id(name : &str) -> i32
+{
+    try_name_to_id(name).unwrap_or_else(|| panic!("Unknown menu {} for left/right sidebar", name))
+}
+
 pub fn register_set_ui_settings(ui : &AppWindow, configuration : &OptionalUiConfig)
 {
     let configuration = UiConfig::from_optional(configuration);
@@ -0,0 +1,105 @@
+use std::{cell::RefCell, rc::Rc};
+
+use slint::{ComponentHandle, SharedString};
+
+use crate::{hardware::{CalibrationSample, TouchCalibrationControl}, AppWindow, TouchCalibration};
+
+#[derive(Default)]
+struct CalibrationSession
+{
+    samples : Vec<CalibrationSample>,
+    previous : Option<[f32; 6]>,
+}
+
+fn set_calibration_state(ui : &AppWindow, state : &str, message : &str)
+{
+    ui.global::<TouchCalibration>().set_state(SharedString::from(state));
+    ui.global::<TouchCalibration>().set_message(SharedString::from(message));
+}
+
+pub fn register_settings_touch_calibration(ui : &AppWindow, touch_calibration : &TouchCalibrationControl)
+{
+    ui.global::<TouchCalibration>().set_available(touch_calibration.is_available());
+
+    let session = Rc::new(RefCell::new(CalibrationSession::default()));
+
+    {
+        let ui_weak = ui.as_weak();
+        let touch_calibration = touch_calibration.clone();
+        let session = session.clone();
+
+        ui.global::<TouchCalibration>().on_start(move || {
+            let ui = ui_weak.upgrade().unwrap();
+            let mut session = session.borrow_mut();
+
+            // Sample with the raw mapping, the old matrix may be the reason for calibrating
+            session.samples.clear();
+            session.previous = touch_calibration.matrix();
+            touch_calibration.set_matrix(None);
+
+            ui.global::<TouchCalibration>().set_step(0);
+            set_calibration_state(&ui, "running", "Tap the center of each crosshair.");
+        });
+    }
+
+    {
+        let session = session.clone();
+
+        ui.global::<TouchCalibration>().on_add_sample(move |touch_x, touch_y, target_x, target_y| {
+            session.borrow_mut().samples.push(((touch_x, touch_y), (target_x, target_y)));
+        });
+    }
+
+    {
+        let ui_weak = ui.as_weak();
+        let touch_calibration = touch_calibration.clone();
+        let session = session.clone();
+
+        ui.global::<TouchCalibration>().on_finish(move || {
+            let ui = ui_weak.upgrade().unwrap();
+            let session = session.borrow();
+
+            match touch_calibration.compute_matrix(&session.samples) {
+                Some(matrix) => {
+                    touch_calibration.set_matrix(Some(matrix));
+                    set_calibration_state(&ui, "done", "Tap around to check the result. The old calibration is restored unless you save.");
+                }
+                None => {
+                    touch_calibration.set_matrix(session.previous);
+                    set_calibration_state(&ui, "failed", "The taps were too inconsistent to calibrate. Please try again.");
+                }
+            }
+        });
+    }
+
+    {
+        let ui_weak = ui.as_weak();
+        let touch_calibration = touch_calibration.clone();
+
+        ui.global::<TouchCalibration>().on_save(move || {
+            let ui = ui_weak.upgrade().unwrap();
+
+            let Some(matrix) = touch_calibration.matrix() else {
+                set_calibration_state(&ui, "", "");
+                return;
+            };
+
+            match touch_calibration.save(matrix) {
+                Ok(()) => set_calibration_state(&ui, "", ""),
+                Err(e) => set_calibration_state(&ui, "failed", &format!("Failed to save the calibration: {}", e)),
+            }
+        });
+    }
+
+    {
+        let ui_weak = ui.as_weak();
+        let touch_calibration = touch_calibration.clone();
+
+        ui.global::<TouchCalibration>().on_cancel(move || {
+            let ui = ui_weak.upgrade().unwrap();
+
+            touch_calibration.set_matrix(session.borrow().previous);
+            set_calibration_state(&ui, "", "");
+        });
+    }
+}
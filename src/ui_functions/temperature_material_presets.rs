@@ -0,0 +1,98 @@
+use std::{collections::BTreeMap, sync::Arc};
+
+use moonraker_rs::{moonraker_connection::MoonrakerConnection, printer_objects::Configfile, requests::PrinterAdministrationRequestHandler};
+use slint::{ComponentHandle, ModelRc, SharedString, VecModel};
+
+use crate::{config::MaterialPreset, ui_functions::target_temperature_command, AppWindow, MaterialPresetEntry, MaterialPresets};
+
+/// Looks up `max_temp` for a heater, which for anything besides the extruder and bed lives in a prefixed section.
+fn heater_max_temp(configfile : &Configfile, heater_name : &str) -> Option<f32>
+{
+    configfile.max_temp(heater_name)
+        .or_else(|| configfile.max_temp(&format!("heater_generic {}", heater_name)))
+        .or_else(|| configfile.max_temp(&format!("temperature_fan {}", heater_name)))
+}
+
+/// Converts the configured presets for the UI. Without a `configfile` nothing can be validated yet.
+pub fn create_material_preset_entries(presets : &BTreeMap<String, MaterialPreset>, configfile : Option<&Configfile>) -> Vec<MaterialPresetEntry>
+{
+    presets.iter().map(|(name, preset)| {
+        let summary = preset.temperatures.iter()
+            .filter(|(_, target)| **target > 0)
+            .map(|(_, target)| format!("{}°C", target))
+            .collect::<Vec<String>>()
+            .join(" / ");
+
+        let warning = configfile.and_then(|configfile| {
+            preset.temperatures.iter().find_map(|(heater_name, target)| {
+                match heater_max_temp(configfile, heater_name) {
+                    Some(max_temp) if *target as f32 > max_temp => Some(format!("{} exceeds {}°C", heater_name, max_temp)),
+                    _ => None,
+                }
+            })
+        });
+
+        MaterialPresetEntry {
+            name: SharedString::from(name),
+            summary: SharedString::from(summary),
+            valid: warning.is_none(),
+            warning: SharedString::from(warning.unwrap_or_default()),
+        }
+    }).collect()
+}
+
+pub fn register_temperature_material_presets(ui : &AppWindow, moonraker_connection : &Arc<MoonrakerConnection>, presets : &BTreeMap<String, MaterialPreset>)
+{
+    let entries = create_material_preset_entries(presets, None);
+    ui.global::<MaterialPresets>().set_presets(ModelRc::new(VecModel::from(entries)));
+
+    let moonraker_connection = moonraker_connection.clone();
+    let presets = presets.clone();
+    let ui_weak = ui.as_weak();
+
+    ui.global::<MaterialPresets>().on_apply_preset(move |name| {
+        let moonraker_connection = moonraker_connection.clone();
+        let ui = ui_weak.upgrade().unwrap();
+
+        let preset = match presets.get(name.as_str()) {
+            Some(preset) => preset,
+            None => {
+                moonraker_connection.send_request_error(format!("Unknown material preset {}", name));
+                return;
+            }
+        };
+
+        let mut commands: Vec<String> = preset.temperatures.iter()
+            .map(|(heater_name, target)| target_temperature_command(&ui, heater_name, *target as i32))
+            .collect();
+
+        if let Some(gcode) = &preset.gcode {
+            commands.push(gcode.clone());
+        }
+
+        let script = commands.join("\n");
+
+        tokio::spawn(async move {
+            if let Err(e) = moonraker_connection.run_gcode_script(&script).await
+            {
+                moonraker_connection.send_request_error(format!("Failed to apply material preset {}: {}", name, e));
+            }
+        });
+    });
+}
+
+pub fn register_temperature_cool_down(ui : &AppWindow, moonraker_connection : &Arc<MoonrakerConnection>)
+{
+    let moonraker_connection = moonraker_connection.clone();
+
+    ui.global::<MaterialPresets>().on_cool_down(move || {
+        let moonraker_connection = moonraker_connection.clone();
+
+        tokio::spawn(async move {
+            if let Err(e) = moonraker_connection.run_gcode_script("TURN_OFF_HEATERS").await
+            {
+                moonraker_connection.send_request_error(format!("Failed to turn off heaters: {}", e));
+            }
+        });
+    });
+}
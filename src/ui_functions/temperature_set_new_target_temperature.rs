@@ -1,26 +1,44 @@
 use std::sync::Arc;
 
 use moonraker_rs::{moonraker_connection::MoonrakerConnection, requests::PrinterAdministrationRequestHandler};
-use slint::ComponentHandle;
+use slint::{ComponentHandle, Model};
 
 use crate::{AppWindow, TemperatureSensors};
 
+/// Sets the target of an extruder, the bed or any other Klipper heater.
+pub fn heater_temperature_command(heater_name : &str, target : i32) -> String
+{
+    format!("SET_HEATER_TEMPERATURE HEATER={} TARGET={}", heater_name, target)
+}
+
+/// Temperature fans need their own command, everything else (extruders, the bed, heater_generic) is a heater.
+pub fn target_temperature_command(ui : &AppWindow, heater_name : &str, target : i32) -> String
+{
+    let is_temperature_fan = ui.global::<TemperatureSensors>()
+        .get_heater_fans()
+        .iter()
+        .any(|fan| fan.heater.name == heater_name);
+
+    if is_temperature_fan {
+        format!("SET_TEMPERATURE_FAN_TARGET TEMPERATURE_FAN={} TARGET={}", heater_name, target)
+    } else {
+        heater_temperature_command(heater_name, target)
+    }
+}
+
 pub fn register_temperature_set_new_target_temperature(ui : &AppWindow, moonraker_connection: &Arc<MoonrakerConnection>)
 {
     let moonraker_connection = moonraker_connection.clone();
+    let ui_weak = ui.as_weak();
     
     ui.global::<TemperatureSensors>().on_set_new_target_temperature(move |heater_name, target| {
         println!("Set new target temperature for {}: {}", heater_name, target);
         let moonraker_connection = moonraker_connection.clone();
+        let command = target_temperature_command(&ui_weak.upgrade().unwrap(), &heater_name, target);
 
         slint::spawn_local(async move {
             let heater_name = heater_name.to_string();
 
-            let command = match heater_name.as_str() {
-                "extruder" | "heater_bed" => format!("SET_HEATER_TEMPERATURE HEATER={} TARGET={}", heater_name, target),
-                _ => format!("SET_TEMPERATURE_FAN_TARGET TEMPERATURE_FAN={} TARGET={}", heater_name, target),
-            };
-
             if let Err(e) = moonraker_connection.run_gcode_script(&command).await
             {
                 moonraker_connection.send_request_error(format!("Failed to set new target temperature for {}: {}", heater_name, e));
@@ -0,0 +1,45 @@
+use moonraker_rs::{connector::websocket_read::{MoonrakerEvent, PrinterEvent}, moonraker_connection::{MoonrakerConnection, WebsocketEvent}, printer_objects::{Extruder, HeaterBed}};
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::application_error::ApplicationError;
+
+/// How close a heater has to get to its target before it counts as heated up, in °C.
+pub const TEMPERATURE_TOLERANCE: f32 = 2.0;
+
+/// Waits until the given extruder and the heater bed have reached their targets.
+/// The extruder additionally has to be above Klipper's `min_extrude_temp`.
+/// A target of 0 or less skips the heater.
+pub async fn wait_for_temperatures(moonraker_connection : &MoonrakerConnection, extruder_name : &str, extruder_target : f32, heater_bed_target : f32) -> Result<(), ApplicationError>
+{
+    // Listen before looking at the cache, so no update can slip through in between
+    let mut receiver = moonraker_connection.get_listener();
+    let extruder_reached = |extruder : &Extruder| extruder_target <= 0.0 || (extruder.can_extrude && extruder.temperature >= extruder_target - TEMPERATURE_TOLERANCE);
+    let heater_bed_reached = |heater_bed : &HeaterBed| heater_bed_target <= 0.0 || heater_bed.temperature >= heater_bed_target - TEMPERATURE_TOLERANCE;
+
+    // The heaters may already be hot, in which case no further updates might arrive
+    let cache = moonraker_connection.cache_snapshot().await;
+    let mut extruder_ready = extruder_target <= 0.0 || cache.extruders.iter().any(|extruder| extruder.name == extruder_name && extruder_reached(&extruder.extruder));
+    let mut heater_bed_ready = heater_bed_reached(&cache.heater_bed);
+
+    while !extruder_ready || !heater_bed_ready
+    {
+        let message = match receiver.recv().await
+        {
+            Ok(message) => message,
+            Err(RecvError::Lagged(_)) => continue,
+            Err(RecvError::Closed) => return Err(ApplicationError::Unknown("Moonraker connection closed while waiting for temperatures".into())),
+        };
+
+        match &*message
+        {
+            WebsocketEvent::MoonrakerEvent(MoonrakerEvent::NotifyStatusUpdate(PrinterEvent::Extruder(extruder))) if extruder.name == extruder_name =>
+                extruder_ready = extruder_reached(&extruder.extruder),
+            WebsocketEvent::MoonrakerEvent(MoonrakerEvent::NotifyStatusUpdate(PrinterEvent::HeaterBed(heater_bed))) =>
+                heater_bed_ready = heater_bed_reached(heater_bed),
+            WebsocketEvent::Disconnected => return Err(ApplicationError::Unknown("Moonraker disconnected while waiting for temperatures".into())),
+            _ => {}
+        }
+    }
+
+    Ok(())
+}